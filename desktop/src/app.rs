@@ -1,8 +1,9 @@
 use std::fs;
+use std::path::PathBuf;
 use std::time::Duration;
 use std::{sync::Arc, time::Instant};
 
-use emulator::Emulator;
+use emulator::{Emulator, EmulatorError};
 use miette::Report;
 use pixels::{Pixels, SurfaceTexture};
 use winit::application::ApplicationHandler;
@@ -15,40 +16,90 @@ use winit::{event_loop::ActiveEventLoop, window::Window};
 
 use anyhow::{Context, Result};
 
-use crate::args::Args;
+use crate::args::RunArgs;
+use crate::clip::ClipRecorder;
+use crate::commands;
+use crate::config::Config;
+use crate::crash::CrashOverlay;
 use crate::keyboard::map_keyboard;
+use crate::recording::Recording;
+use crate::screenshot;
 use crate::sound::Sound;
 
 const WINDOW_WIDTH: u32 = emulator::constants::DISPLAY_WIDTH as u32;
 const WINDOW_HEIGHT: u32 = emulator::constants::DISPLAY_HEIGHT as u32;
 const WINDOW_SCALE: u32 = 20;
+const WINDOW_TITLE: &str = "CHIP-8 Emulator";
+// Size of a CHIP-8 pixel in clips recorded from the window.
+const CLIP_SCALE: usize = 4;
+
+// Records keypad input while running, see `chip8-emu record`.
+pub struct Recorder {
+    pub recording: Recording,
+    pub path: PathBuf,
+}
 
 pub struct App {
-    pub args: Args,
+    pub args: RunArgs,
+    pub config: Config,
     pub window: Option<Arc<Window>>,
     pub pixels: Option<Pixels<'static>>,
     pub emu: Option<Emulator>,
     pub last_cpu_tick_time: Instant,
     pub last_timer_tick_time: Instant,
     pub sound: Sound,
+    pub recorder: Option<Recorder>,
+    // Clip being recorded, toggled with F10.
+    pub clip: Option<ClipRecorder>,
+    // When the display was last added to the clip.
+    pub last_clip_frame_time: Instant,
+    // Set when the emulator stopped with an error; it isn't run any more after that.
+    pub crash: Option<CrashOverlay>,
+    // Errors skipped by the error policy so far, shown in the window title.
+    pub skipped_errors: u64,
 }
 
 impl App {
-    pub fn new(args: Args) -> Self {
+    pub fn new(args: RunArgs, config: Config) -> Self {
         Self {
             args,
+            config,
             window: None,
             pixels: None,
             emu: None,
             last_cpu_tick_time: Instant::now(),
             last_timer_tick_time: Instant::now(),
             sound: Sound::new(),
+            recorder: None,
+            clip: None,
+            last_clip_frame_time: Instant::now(),
+            crash: None,
+            skipped_errors: 0,
+        }
+    }
+
+    // Create an app that records keypad input. Frames are locked to the display frequency so the
+    // recording can be replayed exactly.
+    pub fn with_recorder(args: RunArgs, config: Config, recorder: Recorder) -> Self {
+        Self {
+            recorder: Some(recorder),
+            ..Self::new(args, config)
         }
     }
 
     pub fn run(&mut self) -> Result<()> {
         let event_loop = EventLoop::new().context("Failed to create event loop.")?;
         event_loop.run_app(self).context("Failed to run app")?;
+        if let Some(recorder) = &self.recorder {
+            recorder.recording.save(&recorder.path)?;
+        }
+        if let Some(clip) = self.clip.take() {
+            clip.finish()?;
+        }
+        if let (Some(path), Some(emu)) = (&self.args.coverage, &self.emu) {
+            let rom = commands::read_rom(self.args.rom_path.as_ref())?;
+            commands::save_coverage(emu, &rom, Some(path))?;
+        }
         Ok(())
     }
 
@@ -57,58 +108,62 @@ impl App {
         let emu: &mut Emulator = self.emu.as_mut().context("Emulator not initialized")?;
 
         let frame = pixels.frame_mut();
-        let emu_screen = emu.get_display_buffer();
-
-        for y in 0..WINDOW_HEIGHT as usize {
-            for x in 0..WINDOW_WIDTH as usize {
-                let pixel_idx = (y * WINDOW_WIDTH as usize + x) * 4;
-                let is_pixel_on = emu_screen[y][x];
-
-                if is_pixel_on {
-                    frame[pixel_idx] = 0xFF;
-                    frame[pixel_idx + 1] = 0xFF;
-                    frame[pixel_idx + 2] = 0xFF;
-                    frame[pixel_idx + 3] = 0xFF;
-                } else {
-                    frame[pixel_idx] = 0x00;
-                    frame[pixel_idx + 1] = 0x00;
-                    frame[pixel_idx + 2] = 0x00;
-                    frame[pixel_idx + 3] = 0xFF;
-                }
-            }
+        frame.copy_from_slice(&emu.to_rgba(&self.config.palette(), 1));
+        if let Some(crash) = &self.crash {
+            crash.draw(frame);
         }
         Ok(())
     }
 
+    // Stop the emulator at its first error: print the report (with the failing instruction, the
+    // registers and the surrounding code) once, and show the crash in the window.
+    fn halt(&mut self, err: EmulatorError) {
+        self.crash = Some(CrashOverlay::new(&err));
+        self.sound.stop_beep();
+        if let Some(window) = &self.window {
+            let message = match &err {
+                EmulatorError::Cpu { source } => source.to_string(),
+                err => err.to_string(),
+            };
+            window.set_title(&format!("{WINDOW_TITLE} - crashed: {message}"));
+        }
+        eprintln!("{:?}", Report::new(err));
+    }
+
     fn step_cpu(&mut self) {
         let emu = match self.emu.as_mut() {
-            Some(emu) => emu,
-            None => return,
+            Some(emu) if self.crash.is_none() => emu,
+            _ => return,
         };
 
-        // Determine steps per frame: use argument if present, otherwise calculate.
-        let steps_per_frame = if let Some(steps) = self.args.steps_per_frame {
-            steps
-        } else {
-            // Calculate steps per frame as cpu_frequency / display_frequency.
-            (self.args.cpu_frequency / self.args.display_frequency) as usize
-        };
+        let steps = self.config.steps_per_frame();
+        if let Err(e) = (0..steps).try_for_each(|_| emu.cycle()) {
+            self.halt(e);
+            return;
+        }
+        self.show_skipped_errors();
+    }
 
-        for _ in 0..steps_per_frame {
-            if let Err(e) = emu.cycle() {
-                eprintln!("{:?}", Report::new(e));
-            }
+    // Keep the count of skipped errors in the window title up to date.
+    fn show_skipped_errors(&mut self) {
+        let (Some(emu), Some(window)) = (&self.emu, &self.window) else {
+            return;
+        };
+        let skipped = emu.get_skipped_errors().total();
+        if skipped != self.skipped_errors {
+            self.skipped_errors = skipped;
+            window.set_title(&format!("{WINDOW_TITLE} - {skipped} errors skipped"));
         }
     }
 
     fn tick_timers(&mut self, now: Instant) {
         let emu = match self.emu.as_mut() {
-            Some(emu) => emu,
-            None => return,
+            Some(emu) if self.crash.is_none() => emu,
+            _ => return,
         };
 
         let timer_tick_duration =
-            Duration::from_micros(1_000_000 / self.args.timer_frequency as u64);
+            Duration::from_micros(1_000_000 / self.config.timer_frequency as u64);
         while now.duration_since(self.last_timer_tick_time) >= timer_tick_duration {
             emu.tick_timers();
             let st = emu.get_st();
@@ -121,6 +176,60 @@ impl App {
         }
     }
 
+    // Run whole frames (CPU steps and a timer tick) at the display frequency, counting them for the
+    // recording and adding each one to the clip.
+    fn run_recorded_frames(&mut self, now: Instant) {
+        let (Some(emu), Some(recorder)) = (self.emu.as_mut(), self.recorder.as_mut()) else {
+            return;
+        };
+        if self.crash.is_some() {
+            return;
+        }
+
+        let frame_duration =
+            Duration::from_micros(1_000_000 / self.config.display_frequency as u64);
+        while now.duration_since(self.last_cpu_tick_time) >= frame_duration {
+            if let Err(e) = emu.run_frame(recorder.recording.steps_per_frame) {
+                self.halt(e);
+                return;
+            }
+            recorder.recording.frames += 1;
+            self.last_cpu_tick_time += frame_duration;
+            if let Some(clip) = self.clip.as_mut()
+                && let Err(e) = clip.add_frame(emu)
+            {
+                eprintln!("Error: {e:#}");
+                self.clip = None;
+            }
+        }
+
+        if emu.get_st() > 0 {
+            self.sound.start_beep();
+        } else {
+            self.sound.stop_beep();
+        }
+        self.show_skipped_errors();
+    }
+
+    // Add the display to the clip once per display refresh. Without a recorder the CPU doesn't run
+    // in whole frames, so the display is sampled by the wall clock, like the timers are ticked.
+    fn add_clip_frames(&mut self, now: Instant) {
+        let (Some(clip), Some(emu)) = (self.clip.as_mut(), self.emu.as_ref()) else {
+            return;
+        };
+
+        let frame_duration =
+            Duration::from_micros(1_000_000 / self.config.display_frequency as u64);
+        while now.duration_since(self.last_clip_frame_time) >= frame_duration {
+            if let Err(e) = clip.add_frame(emu) {
+                eprintln!("Error: {e:#}");
+                self.clip = None;
+                return;
+            }
+            self.last_clip_frame_time += frame_duration;
+        }
+    }
+
     fn draw_and_render(&mut self, event_loop: &ActiveEventLoop) {
         if let Err(e) = self.draw_screen() {
             eprintln!("Error: {e}");
@@ -150,7 +259,7 @@ impl ApplicationHandler for App {
         let window_pixel_height = WINDOW_HEIGHT * WINDOW_SCALE;
 
         let window_attributes = WindowAttributes::default()
-            .with_title("CHIP-8 Emulator")
+            .with_title(WINDOW_TITLE)
             .with_inner_size(LogicalSize::new(window_pixel_width, window_pixel_height))
             .with_min_inner_size(LogicalSize::new(window_pixel_width, window_pixel_height));
 
@@ -182,7 +291,14 @@ impl ApplicationHandler for App {
             }
         };
 
-        let mut emu = Emulator::new();
+        let mut emu = match &self.recorder {
+            Some(recorder) => Emulator::with_seed(recorder.recording.seed),
+            None => Emulator::new(),
+        };
+        emu.set_quirks(self.config.quirks);
+        emu.set_write_protection(self.config.write_protection);
+        emu.set_error_policy(self.config.error_policy);
+        emu.set_coverage_enabled(self.args.coverage.is_some());
 
         let rom_data = match fs::read(&self.args.rom_path)
             .with_context(|| format!("Failed to read ROM file '{}'", self.args.rom_path))
@@ -236,6 +352,7 @@ impl ApplicationHandler for App {
                     KeyEvent {
                         physical_key,
                         state,
+                        repeat,
                         ..
                     },
                 ..
@@ -244,7 +361,45 @@ impl ApplicationHandler for App {
                 if physical_key == PhysicalKey::Code(KeyCode::Escape) {
                     event_loop.exit();
                 }
-                if let Some(chip8_key_idx) = map_keyboard(physical_key) {
+                if physical_key == PhysicalKey::Code(KeyCode::F12) && is_pressed && !repeat {
+                    let path = screenshot::timestamped_path();
+                    match screenshot::save_png(
+                        emu,
+                        &self.config.palette(),
+                        screenshot::SCREENSHOT_SCALE,
+                        &path,
+                    ) {
+                        Ok(()) => println!("Saved screenshot to '{}'", path.display()),
+                        Err(e) => eprintln!("Error: {e:#}"),
+                    }
+                }
+                if physical_key == PhysicalKey::Code(KeyCode::F10) && is_pressed && !repeat {
+                    self.clip = match self.clip.take() {
+                        Some(clip) => {
+                            let path = clip.path().to_path_buf();
+                            match clip.finish() {
+                                Ok(()) => println!("Saved clip to '{}'", path.display()),
+                                Err(e) => eprintln!("Error: {e:#}"),
+                            }
+                            None
+                        }
+                        None => {
+                            let path = screenshot::timestamped_path().with_extension("gif");
+                            let clip = ClipRecorder::create(
+                                &path,
+                                self.config.palette(),
+                                CLIP_SCALE,
+                                self.config.display_frequency,
+                            );
+                            self.last_clip_frame_time = Instant::now();
+                            clip.inspect_err(|e| eprintln!("Error: {e:#}")).ok()
+                        }
+                    };
+                }
+                if let Some(chip8_key_idx) = map_keyboard(physical_key, &self.config.keys) {
+                    if let Some(recorder) = self.recorder.as_mut().filter(|_| !repeat) {
+                        recorder.recording.record(chip8_key_idx, is_pressed);
+                    }
                     if is_pressed {
                         if let Err(err) = emu.press_key(chip8_key_idx) {
                             eprintln!("Failed to press key: {:?}", err);
@@ -262,8 +417,13 @@ impl ApplicationHandler for App {
 
     fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
         let now = Instant::now();
-        self.step_cpu();
-        self.tick_timers(now);
+        if self.recorder.is_some() {
+            self.run_recorded_frames(now);
+        } else {
+            self.step_cpu();
+            self.tick_timers(now);
+            self.add_clip_frames(now);
+        }
         self.draw_and_render(event_loop);
     }
 }
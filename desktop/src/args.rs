@@ -1,28 +1,424 @@
-use clap::Parser;
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand, ValueEnum};
+use emulator::{ErrorAction, ErrorKind, ErrorPolicy, Platform, WriteProtection};
+
+use crate::config::{ErrorSettings, Settings};
+use crate::screenshot::SCREENSHOT_SCALE;
 
 #[derive(Parser, Debug)]
 #[command(
     author = "Teo Welton",
     version = "0.0.1",
-    about = "A simple CHIP-8 emulator written in Rust."
+    about = "A simple CHIP-8 emulator written in Rust.",
+    args_conflicts_with_subcommands = true,
+    arg_required_else_help = true
 )]
 pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Running `chip8-emu <ROM>` is a shortcut for `chip8-emu run <ROM>`.
+    #[command(flatten)]
+    pub run: Option<RunArgs>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Run a ROM in a window.
+    Run(RunArgs),
+
+    /// Print a disassembly listing of a ROM.
+    Disasm(DisasmArgs),
+
+    /// Print information about a ROM (title, size, hash, detected platform, entry instructions).
+    Info(RomArgs),
+
+    /// Print the control flow graph of a ROM: unreachable bytes and suspicious code, or Graphviz DOT.
+    Cfg(CfgArgs),
+
+    /// Print the regions of RAM (font, reserved, program, free) with a ROM loaded.
+    MemoryMap(MemoryMapArgs),
+
+    /// Count executions per address and per subroutine while running a ROM without a window.
+    Profile(ProfileArgs),
+
+    /// Measure how fast the emulator core runs a ROM.
+    Bench(BenchArgs),
+
+    /// Run a ROM without a window and print the final display.
+    Headless(HeadlessArgs),
+
+    /// Run a ROM in a window while recording keypad input to a file.
+    Record(RecordArgs),
+
+    /// Replay a recording without a window and print the final display.
+    Replay(ReplayArgs),
+
+    /// Run a ROM without a window and check the final display against an expected hash.
+    Test(TestArgs),
+
+    /// Run known test ROMs under each platform preset and print which checks pass.
+    TestSuite(TestSuiteArgs),
+}
+
+#[derive(clap::Args, Debug)]
+pub struct RunArgs {
     /// Path to the ROM file to be loaded.
     pub rom_path: String,
 
     /// CPU frequency in Hz (default: 500)
-    #[arg(short = 'f', long, value_name = "HZ", default_value_t = 500)]
-    pub cpu_frequency: u32,
+    #[arg(short = 'f', long, value_name = "HZ")]
+    pub cpu_frequency: Option<u32>,
 
     /// Display refresh frequency in Hz (default: 60)
-    #[arg(short = 'd', long, value_name = "HZ", default_value_t = 60)]
-    pub display_frequency: u32,
+    #[arg(short = 'd', long, value_name = "HZ")]
+    pub display_frequency: Option<u32>,
 
     /// Timer frequency in Hz (default: 60)
-    #[arg(short = 't', long, value_name = "HZ", default_value_t = 60)]
-    pub timer_frequency: u32,
+    #[arg(short = 't', long, value_name = "HZ")]
+    pub timer_frequency: Option<u32>,
 
     /// Number of CPU steps per frame (overrides calculated value using CPU and display frequency)
     #[arg(short = 's', long, value_name = "STEPS")]
     pub steps_per_frame: Option<usize>,
+
+    /// Platform whose quirks to emulate: chip8, superchip or xochip
+    #[arg(short = 'p', long, value_name = "PLATFORM")]
+    pub platform: Option<Platform>,
+
+    /// Color of lit pixels as #RRGGBB (default: #FFFFFF)
+    #[arg(long, value_name = "COLOR")]
+    pub foreground: Option<String>,
+
+    /// Color of unlit pixels as #RRGGBB (default: #000000)
+    #[arg(long, value_name = "COLOR")]
+    pub background: Option<String>,
+
+    /// What to do when an instruction fails: ACTION for every kind of error, or KIND=ACTION for
+    /// one kind (unknown-opcode, stack, memory or key). Actions are halt (the default), skip and
+    /// log (skip with a warning). Can be given more than once.
+    #[arg(long, value_name = "[KIND=]ACTION", value_parser = parse_error_rule)]
+    pub on_error: Vec<ErrorRule>,
+
+    /// What happens when the ROM writes to the font or the area below 0x200: off (the default),
+    /// warn or error
+    #[arg(long, value_name = "MODE")]
+    pub write_protection: Option<WriteProtection>,
+
+    /// Config file to use instead of `$XDG_CONFIG_HOME/chip8-rs/config.toml`
+    #[arg(short = 'c', long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Print the effective configuration for the ROM and exit
+    #[arg(long)]
+    pub print_config: bool,
+
+    /// Record which RAM addresses the ROM executes, reads and writes, and write a report to this file
+    /// (merged with an existing report for the same ROM).
+    #[arg(long, value_name = "FILE")]
+    pub coverage: Option<PathBuf>,
+}
+
+impl RunArgs {
+    // Settings given on the command line, which override the config file.
+    pub fn settings(&self) -> Settings {
+        Settings {
+            cpu_frequency: self.cpu_frequency,
+            display_frequency: self.display_frequency,
+            timer_frequency: self.timer_frequency,
+            steps_per_frame: self.steps_per_frame,
+            platform: self.platform.map(|platform| platform.name().to_string()),
+            foreground: self.foreground.clone(),
+            background: self.background.clone(),
+            write_protection: self
+                .write_protection
+                .map(|protection| protection.name().to_string()),
+            errors: error_policy_settings(&self.on_error),
+            ..Settings::default()
+        }
+    }
+}
+
+#[derive(clap::Args, Debug)]
+pub struct RomArgs {
+    /// Path to the ROM file.
+    pub rom_path: PathBuf,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct DisasmArgs {
+    /// Path to the ROM file.
+    pub rom_path: PathBuf,
+
+    /// Only list instructions starting at this address (e.g. 0x220).
+    #[arg(long, value_name = "ADDR", value_parser = parse_addr)]
+    pub start: Option<u16>,
+
+    /// Maximum number of instructions to list.
+    #[arg(short = 'n', long, value_name = "COUNT")]
+    pub count: Option<usize>,
+
+    /// Follow the control flow from the program start and list bytes that aren't reached as data.
+    #[arg(short = 'a', long)]
+    pub analyze: bool,
+
+    /// Coverage report (see `--coverage` of `run`) whose executed addresses are listed as code,
+    /// with a column showing how each address was used. Implies `--analyze`.
+    #[arg(long, value_name = "FILE")]
+    pub coverage: Option<PathBuf>,
+
+    /// Symbol file (`name 0x2A0` per line, e.g. from an assembler) used to print labels instead
+    /// of addresses.
+    #[arg(long, value_name = "FILE")]
+    pub symbols: Option<PathBuf>,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct CfgArgs {
+    /// Path to the ROM file.
+    pub rom_path: PathBuf,
+
+    /// Print the graph in Graphviz DOT format (e.g. `| dot -Tsvg > cfg.svg`).
+    #[arg(long)]
+    pub dot: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct MemoryMapArgs {
+    /// Path to the ROM file.
+    pub rom_path: PathBuf,
+
+    /// Also print the contents of each region as a hex dump.
+    #[arg(long)]
+    pub dump: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct BenchArgs {
+    /// Path to the ROM file.
+    pub rom_path: PathBuf,
+
+    /// Number of frames to run.
+    #[arg(long, value_name = "FRAMES", default_value_t = 10_000)]
+    pub frames: usize,
+
+    /// Number of CPU steps per frame.
+    #[arg(short = 's', long, value_name = "STEPS", default_value_t = 8)]
+    pub steps_per_frame: usize,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct HeadlessArgs {
+    /// Path to the ROM file.
+    pub rom_path: PathBuf,
+
+    /// Number of frames to run.
+    #[arg(long, value_name = "FRAMES", default_value_t = 600)]
+    pub frames: usize,
+
+    /// Number of CPU steps per frame.
+    #[arg(short = 's', long, value_name = "STEPS", default_value_t = 8)]
+    pub steps_per_frame: usize,
+
+    /// Seed for the random number generator (CXNN), for reproducible runs.
+    #[arg(long, value_name = "SEED", default_value_t = 0)]
+    pub seed: u64,
+
+    /// Platform whose quirks to emulate: chip8, superchip or xochip
+    #[arg(short = 'p', long, value_name = "PLATFORM")]
+    pub platform: Option<Platform>,
+
+    /// What to do when an instruction fails: ACTION for every kind of error, or KIND=ACTION for
+    /// one kind (unknown-opcode, stack, memory or key). Actions are halt (the default), skip and
+    /// log (skip with a warning). Can be given more than once.
+    #[arg(long, value_name = "[KIND=]ACTION", value_parser = parse_error_rule)]
+    pub on_error: Vec<ErrorRule>,
+
+    /// What happens when the ROM writes to the font or the area below 0x200: off (the default),
+    /// warn or error
+    #[arg(long, value_name = "MODE")]
+    pub write_protection: Option<WriteProtection>,
+
+    /// Record which RAM addresses the ROM executes, reads and writes, and write a report to this file
+    /// (merged with an existing report for the same ROM).
+    #[arg(long, value_name = "FILE")]
+    pub coverage: Option<PathBuf>,
+
+    /// Write every executed instruction with the registers before it to this file.
+    #[arg(long, value_name = "FILE")]
+    pub trace: Option<PathBuf>,
+
+    /// Save a PNG screenshot of the display after this many frames.
+    #[arg(long, value_name = "FRAMES")]
+    pub screenshot_after: Option<usize>,
+
+    /// File for `--screenshot-after` (default: `chip8-<date>-<time>.png` in the current directory).
+    #[arg(long, value_name = "FILE")]
+    pub screenshot: Option<PathBuf>,
+
+    /// Record every frame to this file (.gif, or .y4m / .ppm streams for video encoders), with the
+    /// beep written to a .wav file next to it.
+    #[arg(long, value_name = "FILE")]
+    pub clip: Option<PathBuf>,
+
+    /// Size of a CHIP-8 pixel in screenshots and clips.
+    #[arg(long, value_name = "SCALE", default_value_t = SCREENSHOT_SCALE)]
+    pub scale: usize,
+
+    /// Color of lit pixels in screenshots and clips as #RRGGBB (default: #FFFFFF)
+    #[arg(long, value_name = "COLOR")]
+    pub foreground: Option<String>,
+
+    /// Color of unlit pixels in screenshots and clips as #RRGGBB (default: #000000)
+    #[arg(long, value_name = "COLOR")]
+    pub background: Option<String>,
+
+    /// Symbol file (`name 0x2A0` per line, e.g. from an assembler) used to print labels instead
+    /// of addresses.
+    #[arg(long, value_name = "FILE")]
+    pub symbols: Option<PathBuf>,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct RecordArgs {
+    #[command(flatten)]
+    pub run: RunArgs,
+
+    /// File to write the recording to.
+    #[arg(short = 'o', long, value_name = "FILE")]
+    pub output: PathBuf,
+
+    /// Seed for the random number generator (CXNN), stored in the recording.
+    #[arg(long, value_name = "SEED", default_value_t = 0)]
+    pub seed: u64,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ReplayArgs {
+    /// Path to the ROM file.
+    pub rom_path: PathBuf,
+
+    /// Recording created by `chip8-emu record`.
+    pub recording: PathBuf,
+
+    /// Record which RAM addresses the ROM executes, reads and writes, and write a report to this file
+    /// (merged with an existing report for the same ROM).
+    #[arg(long, value_name = "FILE")]
+    pub coverage: Option<PathBuf>,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct TestArgs {
+    #[command(flatten)]
+    pub headless: HeadlessArgs,
+
+    /// Expected SHA-1 hash of the final display (as printed by `headless`).
+    #[arg(long, value_name = "SHA1")]
+    pub expect: Option<String>,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct TestSuiteArgs {
+    /// Test ROMs, or directories containing them. ROMs without definitions are skipped.
+    #[arg(default_value = "test_roms")]
+    pub paths: Vec<PathBuf>,
+
+    /// Test ROM definitions (see `desktop/data/test-suite.toml`) to add to the built-in ones.
+    #[arg(long, value_name = "FILE")]
+    pub suite: Option<PathBuf>,
+
+    /// List the hashes of result screens that aren't in the definitions.
+    #[arg(long)]
+    pub hashes: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ProfileArgs {
+    #[command(flatten)]
+    pub headless: HeadlessArgs,
+
+    /// Replay this recording (see `chip8-emu record`) instead of running without input. The
+    /// frames, steps, seed and quirks are taken from the recording.
+    #[arg(long, value_name = "FILE")]
+    pub recording: Option<PathBuf>,
+
+    /// Output format.
+    #[arg(long, value_enum, default_value_t = ProfileFormat::Flat)]
+    pub format: ProfileFormat,
+
+    /// Number of addresses and subroutines listed in the flat profile.
+    #[arg(short = 'n', long, value_name = "COUNT", default_value_t = 20)]
+    pub top: usize,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProfileFormat {
+    /// The most executed addresses and subroutines.
+    Flat,
+    /// Execution counts per call path, as an indented tree.
+    Tree,
+    /// Folded stacks for flamegraph.pl or inferno-flamegraph.
+    Folded,
+}
+
+// Parse an address given in hex (`0x200`) or decimal (`512`).
+fn parse_addr(value: &str) -> Result<u16, String> {
+    let parsed = match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => u16::from_str_radix(hex, 16),
+        None => value.parse(),
+    };
+    parsed.map_err(|err| format!("invalid address '{value}': {err}"))
+}
+
+// An `--on-error` rule: the action for one kind of error, or for all of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ErrorRule {
+    pub kind: Option<ErrorKind>,
+    pub action: ErrorAction,
+}
+
+impl ErrorRule {
+    // The kinds of error the rule applies to.
+    fn kinds(&self) -> Vec<ErrorKind> {
+        match self.kind {
+            Some(kind) => vec![kind],
+            None => ErrorKind::ALL.to_vec(),
+        }
+    }
+}
+
+// The policy set by `--on-error` rules, later rules overriding earlier ones.
+pub fn error_policy(rules: &[ErrorRule]) -> ErrorPolicy {
+    let mut policy = ErrorPolicy::default();
+    for rule in rules {
+        for kind in rule.kinds() {
+            policy.set_action(kind, rule.action);
+        }
+    }
+    policy
+}
+
+// `--on-error` rules as settings, leaving kinds they don't mention to the config file.
+fn error_policy_settings(rules: &[ErrorRule]) -> ErrorSettings {
+    let mut settings = ErrorSettings::default();
+    for rule in rules {
+        for kind in rule.kinds() {
+            *settings.action_mut(kind) = Some(rule.action.name().to_string());
+        }
+    }
+    settings
+}
+
+// Parse `ACTION` or `KIND=ACTION`.
+fn parse_error_rule(value: &str) -> Result<ErrorRule, String> {
+    let (kind, action) = match value.split_once('=') {
+        Some((kind, action)) => (Some(kind.parse().map_err(|err| format!("{err}"))?), action),
+        None => (None, value),
+    };
+    let action = action.parse().map_err(|err| format!("{err}"))?;
+    Ok(ErrorRule { kind, action })
 }
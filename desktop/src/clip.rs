@@ -0,0 +1,252 @@
+use std::borrow::Cow;
+use std::f32::consts::TAU;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use emulator::{
+    Emulator, Palette,
+    constants::{DISPLAY_HEIGHT, DISPLAY_WIDTH},
+};
+
+const SAMPLE_RATE: u32 = 44_100;
+// Same tone as the live beep in `Sound`.
+const BEEP_FREQUENCY: f32 = 440.0;
+const BEEP_VOLUME: f32 = 0.25;
+// Browsers show GIF frames shorter than 2/100 s for much longer, so faster changes are dropped.
+const MIN_GIF_DELAY: u16 = 2;
+
+// Output format of a clip, picked from the file extension.
+enum Video {
+    // Animated GIF, with a frame written whenever the display changes.
+    Gif {
+        encoder: gif::Encoder<BufWriter<File>>,
+        // The frame being shown (palette indices) and the time it started, in 1/100 s.
+        held: Option<(Vec<u8>, u64)>,
+    },
+    // YUV4MPEG2 stream (4:4:4), e.g. for `ffmpeg -i clip.y4m`.
+    Y4m(BufWriter<File>),
+    // Concatenated binary PPM images, e.g. for `ffmpeg -f image2pipe -c:v ppm -i clip.ppm`.
+    Ppm(BufWriter<File>),
+}
+
+// Records the display once per frame into a video file, and the beep into a WAV file next to it
+// (`clip.gif` -> `clip.wav`).
+pub struct ClipRecorder {
+    path: PathBuf,
+    video: Video,
+    palette: Palette,
+    scale: usize,
+    frame_rate: u32,
+    frames: u64,
+    samples: Vec<i16>,
+    phase: f32,
+}
+
+impl ClipRecorder {
+    pub fn create(path: &Path, palette: Palette, scale: usize, frame_rate: u32) -> Result<Self> {
+        let extension = path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_ascii_lowercase());
+        let file = || {
+            File::create(path)
+                .map(BufWriter::new)
+                .with_context(|| format!("Failed to create clip '{}'", path.display()))
+        };
+        let (width, height) = (DISPLAY_WIDTH * scale, DISPLAY_HEIGHT * scale);
+        let video = match extension.as_deref() {
+            Some("gif") => {
+                let mut colors = palette.background.to_vec();
+                colors.extend_from_slice(&palette.foreground);
+                let encoder = gif::Encoder::new(file()?, width as u16, height as u16, &colors)
+                    .and_then(|mut encoder| {
+                        encoder.set_repeat(gif::Repeat::Infinite)?;
+                        Ok(encoder)
+                    })
+                    .with_context(|| format!("Failed to write clip '{}'", path.display()))?;
+                Video::Gif {
+                    encoder,
+                    held: None,
+                }
+            }
+            Some("y4m") => {
+                let mut writer = file()?;
+                writeln!(
+                    writer,
+                    "YUV4MPEG2 W{width} H{height} F{frame_rate}:1 Ip A1:1 C444"
+                )
+                .with_context(|| format!("Failed to write clip '{}'", path.display()))?;
+                Video::Y4m(writer)
+            }
+            Some("ppm") => Video::Ppm(file()?),
+            _ => bail!(
+                "Unsupported clip format '{}' (expected a .gif, .y4m or .ppm file)",
+                path.display()
+            ),
+        };
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            video,
+            palette,
+            scale,
+            frame_rate,
+            frames: 0,
+            samples: Vec::new(),
+            phase: 0.0,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // The WAV file written next to the clip.
+    pub fn audio_path(&self) -> PathBuf {
+        self.path.with_extension("wav")
+    }
+
+    // Record the current display, and a frame's worth of audio (the beep while the sound timer
+    // is running).
+    pub fn add_frame(&mut self, emu: &Emulator) -> Result<()> {
+        let time = self.frames * 100 / self.frame_rate as u64;
+        let (width, height) = (DISPLAY_WIDTH * self.scale, DISPLAY_HEIGHT * self.scale);
+        let written = match &mut self.video {
+            Video::Gif { encoder, held } => {
+                let indices: Vec<u8> = emu
+                    .to_rgba(&INDEX_PALETTE, self.scale)
+                    .chunks_exact(4)
+                    .map(|rgba| rgba[0])
+                    .collect();
+                match held {
+                    Some((shown, _)) if *shown == indices => Ok(()),
+                    Some((shown, since)) if time - *since < MIN_GIF_DELAY as u64 => {
+                        // Replace a frame that would be shown too briefly.
+                        *shown = indices;
+                        Ok(())
+                    }
+                    Some((shown, since)) => {
+                        let result = write_gif_frame(encoder, width, height, shown, time - *since);
+                        *held = Some((indices, time));
+                        result
+                    }
+                    None => {
+                        *held = Some((indices, time));
+                        Ok(())
+                    }
+                }
+            }
+            Video::Y4m(writer) => {
+                let rgba = emu.to_rgba(&self.palette, self.scale);
+                let planes: [Vec<u8>; 3] = [0, 1, 2].map(|plane| {
+                    rgba.chunks_exact(4)
+                        .map(|pixel| rgb_to_yuv([pixel[0], pixel[1], pixel[2]])[plane])
+                        .collect()
+                });
+                writer
+                    .write_all(b"FRAME\n")
+                    .and_then(|()| planes.iter().try_for_each(|plane| writer.write_all(plane)))
+                    .map_err(Into::into)
+            }
+            Video::Ppm(writer) => {
+                let rgb: Vec<u8> = emu
+                    .to_rgba(&self.palette, self.scale)
+                    .chunks_exact(4)
+                    .flat_map(|pixel| [pixel[0], pixel[1], pixel[2]])
+                    .collect();
+                write!(writer, "P6\n{width} {height}\n255\n")
+                    .and_then(|()| writer.write_all(&rgb))
+                    .map_err(Into::into)
+            }
+        };
+        written.with_context(|| format!("Failed to write clip '{}'", self.path.display()))?;
+
+        // Samples up to the end of this frame, so rounding doesn't add up over a long clip.
+        self.frames += 1;
+        let end = (self.frames * SAMPLE_RATE as u64 / self.frame_rate as u64) as usize;
+        let beeping = emu.get_st() > 0;
+        while self.samples.len() < end {
+            let sample = match beeping {
+                true => (self.phase * TAU).sin() * BEEP_VOLUME * i16::MAX as f32,
+                false => 0.0,
+            };
+            self.phase = (self.phase + BEEP_FREQUENCY / SAMPLE_RATE as f32).fract();
+            self.samples.push(sample as i16);
+        }
+        Ok(())
+    }
+
+    // Finish the video file and write the audio.
+    pub fn finish(self) -> Result<()> {
+        let end = self.frames * 100 / self.frame_rate as u64;
+        let (width, height) = (DISPLAY_WIDTH * self.scale, DISPLAY_HEIGHT * self.scale);
+        let audio_path = self.audio_path();
+        let written = match self.video {
+            Video::Gif { mut encoder, held } => held
+                .map_or(Ok(()), |(shown, since)| {
+                    write_gif_frame(&mut encoder, width, height, &shown, end - since)
+                })
+                .and_then(|()| Ok(encoder.into_inner()?.flush()?)),
+            Video::Y4m(mut writer) | Video::Ppm(mut writer) => Ok(writer.flush()?),
+        };
+        written.with_context(|| format!("Failed to write clip '{}'", self.path.display()))?;
+
+        fs::write(&audio_path, wav(&self.samples))
+            .with_context(|| format!("Failed to write audio '{}'", audio_path.display()))
+    }
+}
+
+// Renders lit pixels as 1 and unlit pixels as 0 in the red channel, i.e. GIF palette indices.
+const INDEX_PALETTE: Palette = Palette {
+    foreground: [1, 1, 1],
+    background: [0, 0, 0],
+};
+
+fn write_gif_frame(
+    encoder: &mut gif::Encoder<BufWriter<File>>,
+    width: usize,
+    height: usize,
+    indices: &[u8],
+    delay: u64,
+) -> Result<()> {
+    let frame = gif::Frame {
+        width: width as u16,
+        height: height as u16,
+        buffer: Cow::Borrowed(indices),
+        delay: delay.clamp(MIN_GIF_DELAY as u64, u16::MAX as u64) as u16,
+        ..gif::Frame::default()
+    };
+    Ok(encoder.write_frame(&frame)?)
+}
+
+// BT.601 limited range, the default of Y4M readers.
+fn rgb_to_yuv([r, g, b]: [u8; 3]) -> [u8; 3] {
+    let (r, g, b) = (r as f32, g as f32, b as f32);
+    let y = 16.0 + 0.257 * r + 0.504 * g + 0.098 * b;
+    let u = 128.0 - 0.148 * r - 0.291 * g + 0.439 * b;
+    let v = 128.0 + 0.439 * r - 0.368 * g - 0.071 * b;
+    [y, u, v].map(|value| value.round().clamp(0.0, 255.0) as u8)
+}
+
+// A 16-bit mono WAV file.
+fn wav(samples: &[i16]) -> Vec<u8> {
+    let data_len = (samples.len() * 2) as u32;
+    let mut bytes = Vec::with_capacity(44 + data_len as usize);
+    bytes.extend_from_slice(b"RIFF");
+    bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+    bytes.extend_from_slice(b"WAVEfmt ");
+    bytes.extend_from_slice(&16_u32.to_le_bytes());
+    bytes.extend_from_slice(&1_u16.to_le_bytes()); // PCM
+    bytes.extend_from_slice(&1_u16.to_le_bytes()); // Mono
+    bytes.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    bytes.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes()); // Bytes per second
+    bytes.extend_from_slice(&2_u16.to_le_bytes()); // Bytes per sample
+    bytes.extend_from_slice(&16_u16.to_le_bytes()); // Bits per sample
+    bytes.extend_from_slice(b"data");
+    bytes.extend_from_slice(&data_len.to_le_bytes());
+    for sample in samples {
+        bytes.extend_from_slice(&sample.to_le_bytes());
+    }
+    bytes
+}
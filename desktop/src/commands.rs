@@ -0,0 +1,488 @@
+use std::collections::BTreeSet;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
+
+use anyhow::{Context, Result, anyhow, bail};
+use emulator::{
+    ControlFlowGraph, Coverage, DisassembledLine, Emulator, EmulatorError, ErrorKind, Instruction,
+    Palette, SymbolTable, analyze_rom, constants::PROGRAM_START, disassemble,
+};
+use miette::Report;
+
+use crate::args::{
+    BenchArgs, CfgArgs, DisasmArgs, HeadlessArgs, MemoryMapArgs, ProfileArgs, ProfileFormat,
+    ReplayArgs, RomArgs, TestArgs, TestSuiteArgs, error_policy,
+};
+use crate::clip::ClipRecorder;
+use crate::config::parse_color;
+use crate::coverage::{CoverageReport, access_flags};
+use crate::profile;
+use crate::recording::{Recording, quirk_flags};
+use crate::screenshot::{save_png, timestamped_path};
+use crate::test_suite::{Suite, run_suite};
+use crate::trace::Tracer;
+
+// Headless runs count one display frame per timer tick.
+const HEADLESS_FRAME_RATE: u32 = 60;
+// Number of instructions shown by `info`.
+const ENTRY_INSTRUCTIONS: usize = 8;
+// Number of instructions `info` shows as evidence for the detected platform.
+const EVIDENCE_INSTRUCTIONS: usize = 4;
+
+// Helper function to read a ROM file.
+pub fn read_rom(path: &Path) -> Result<Vec<u8>> {
+    fs::read(path).with_context(|| format!("Failed to read ROM file '{}'", path.display()))
+}
+
+// Helper function to create an emulator with `rom` loaded.
+fn load_emulator(rom: &[u8], seed: u64) -> Result<Emulator> {
+    let mut emu = Emulator::with_seed(seed);
+    emu.load_rom(rom).context("Failed to load ROM")?;
+    Ok(emu)
+}
+
+// Load a symbol file, or an empty table if none is given.
+pub fn load_symbols(path: Option<&Path>) -> Result<SymbolTable> {
+    let Some(path) = path else {
+        return Ok(SymbolTable::new());
+    };
+    let text = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read symbol file '{}'", path.display()))?;
+    SymbolTable::parse(&text).with_context(|| format!("Invalid symbol file '{}'", path.display()))
+}
+
+// Render the display as text, one line per row (`#` = on, `.` = off).
+pub fn display_to_text(emu: &Emulator) -> String {
+    let mut text = String::new();
+    for row in emu.get_display_buffer() {
+        text.extend(
+            row.iter()
+                .map(|&is_pixel_on| if is_pixel_on { '#' } else { '.' }),
+        );
+        text.push('\n');
+    }
+    text
+}
+
+// SHA-1 of the display (one byte per pixel, row by row), used to compare runs.
+pub fn display_sha1(emu: &Emulator) -> String {
+    let pixels: Vec<u8> = emu
+        .get_display_buffer()
+        .iter()
+        .flatten()
+        .map(|&is_pixel_on| is_pixel_on as u8)
+        .collect();
+    sha1_smol::Sha1::from(pixels).digest().to_string()
+}
+
+pub fn disasm(args: &DisasmArgs) -> Result<()> {
+    let rom = read_rom(&args.rom_path)?;
+    let emu = load_emulator(&rom, 0)?;
+    let start = args.start.unwrap_or(PROGRAM_START);
+    let end = PROGRAM_START as usize + rom.len();
+    let count = args.count.unwrap_or(usize::MAX);
+    let symbols = load_symbols(args.symbols.as_deref())?;
+
+    let coverage = match &args.coverage {
+        Some(path) => {
+            let report = CoverageReport::load(path)?;
+            let rom_sha1 = sha1_smol::Sha1::from(&rom).digest().to_string();
+            if report.rom_sha1 != rom_sha1 {
+                bail!(
+                    "The coverage report was made with a different ROM (SHA-1 {}, got {rom_sha1})",
+                    report.rom_sha1
+                );
+            }
+            Some(report.coverage)
+        }
+        None => None,
+    };
+
+    let lines = if args.analyze || coverage.is_some() {
+        analyzed_listing(&rom, start, count, coverage.as_ref())
+    } else {
+        disassemble(emu.get_ram(), start, count)
+    };
+    for line in lines
+        .into_iter()
+        .take_while(|line| (line.addr as usize) < end)
+    {
+        if let Some(name) = symbols.name(line.addr) {
+            println!("{name}:");
+        }
+        let instruction = symbols.instruction(line.instruction);
+        match &coverage {
+            Some(coverage) => println!(
+                "{:03X}  {:04X}  {}  {instruction}",
+                line.addr,
+                line.opcode,
+                access_flags(&coverage.get(line.addr))
+            ),
+            None => println!("{:03X}  {:04X}  {instruction}", line.addr, line.opcode),
+        }
+    }
+    Ok(())
+}
+
+// List the reachable instructions, and the bytes in between as data words (`DW`). Addresses a
+// recorded session executed count as code too, which covers the targets of computed jumps.
+fn analyzed_listing(
+    rom: &[u8],
+    start: u16,
+    count: usize,
+    coverage: Option<&Coverage>,
+) -> Vec<DisassembledLine> {
+    let cfg = ControlFlowGraph::build(rom);
+    let executed = |addr: u16| coverage.is_some_and(|coverage| coverage.get(addr).executed);
+    let is_code = |addr: u16| cfg.is_code(addr) || executed(addr);
+    let instructions: BTreeSet<u16> = cfg.instructions().map(|line| line.addr).collect();
+
+    let end = PROGRAM_START as usize + rom.len();
+    let byte = |addr: usize| {
+        addr.checked_sub(PROGRAM_START as usize)
+            .and_then(|offset| rom.get(offset))
+            .copied()
+    };
+    let mut lines = Vec::new();
+    // Only the ROM is analyzed, so the listing starts at the program start at the earliest.
+    let mut addr = (start as usize).max(PROGRAM_START as usize);
+    while lines.len() < count && addr < end {
+        let high = byte(addr).unwrap_or(0);
+        let line = if instructions.contains(&(addr as u16)) || executed(addr as u16) {
+            let opcode = u16::from_be_bytes([high, byte(addr + 1).unwrap_or(0)]);
+            DisassembledLine {
+                addr: addr as u16,
+                opcode,
+                instruction: Instruction::decode(opcode),
+            }
+        } else {
+            // A data word stops short of the next instruction (and pads past the end of the ROM).
+            let low = match is_code(addr as u16 + 1) {
+                true => 0,
+                false => byte(addr + 1).unwrap_or(0),
+            };
+            let opcode = u16::from_be_bytes([high, low]);
+            DisassembledLine {
+                addr: addr as u16,
+                opcode,
+                instruction: Instruction::Unknown(opcode),
+            }
+        };
+        addr += match line.instruction {
+            Instruction::Unknown(_) if is_code(addr as u16 + 1) => 1,
+            instruction => instruction.size() as usize,
+        };
+        lines.push(line);
+    }
+    lines
+}
+
+pub fn info(args: &RomArgs) -> Result<()> {
+    let rom = read_rom(&args.rom_path)?;
+    let emu = load_emulator(&rom, 0)?;
+    let sha1 = sha1_smol::Sha1::from(&rom).digest().to_string();
+
+    println!("File:      {}", args.rom_path.display());
+    println!("Size:      {} bytes", rom.len());
+    println!("SHA-1:     {sha1}");
+    let analysis = analyze_rom(&rom);
+    println!("Platform:  {}", analysis.platform.display_name());
+    for line in analysis.evidence.iter().take(EVIDENCE_INSTRUCTIONS) {
+        println!(
+            "  {:03X}  {:04X}  {}",
+            line.addr, line.opcode, line.instruction
+        );
+    }
+    let quirks: Vec<String> = quirk_flags(&analysis.quirks)
+        .into_iter()
+        .map(|(name, enabled)| format!("{name} {}", if enabled { "on" } else { "off" }))
+        .collect();
+    println!("Quirks:    {}", quirks.join(", "));
+    println!("Entry:");
+    for line in disassemble(emu.get_ram(), PROGRAM_START, ENTRY_INSTRUCTIONS) {
+        println!(
+            "  {:03X}  {:04X}  {}",
+            line.addr, line.opcode, line.instruction
+        );
+    }
+    Ok(())
+}
+
+pub fn cfg(args: &CfgArgs) -> Result<()> {
+    let rom = read_rom(&args.rom_path)?;
+    // Rejects ROMs that don't fit in memory, like the other commands.
+    load_emulator(&rom, 0)?;
+    let cfg = ControlFlowGraph::build(&rom);
+    if args.dot {
+        print!("{}", cfg.to_dot());
+        return Ok(());
+    }
+
+    let code_bytes = (0..rom.len() as u16)
+        .filter(|&offset| cfg.is_code(PROGRAM_START + offset))
+        .count();
+    println!("Blocks:    {}", cfg.blocks.len());
+    println!("Code:      {code_bytes} of {} bytes", rom.len());
+    println!("Unreachable:");
+    for range in cfg.unreachable_ranges() {
+        println!(
+            "  {:03X}-{:03X}  ({} bytes)",
+            range.start,
+            range.end - 1,
+            range.len()
+        );
+    }
+    println!("Warnings:");
+    for warning in &cfg.warnings {
+        println!("  {warning}");
+    }
+    if cfg.warnings.is_empty() {
+        println!("  none");
+    }
+    Ok(())
+}
+
+pub fn memory_map(args: &MemoryMapArgs) -> Result<()> {
+    let rom = read_rom(&args.rom_path)?;
+    let emu = load_emulator(&rom, 0)?;
+    for region in emu.memory_map() {
+        println!(
+            "{:03X}-{:03X}  {:<8}  {:>4} bytes",
+            region.start,
+            region.end - 1,
+            region.kind.name(),
+            region.len()
+        );
+        if args.dump {
+            print!(
+                "{}",
+                hex_dump(
+                    &emu.get_ram()[region.start as usize..region.end as usize],
+                    region.start
+                )
+            );
+        }
+    }
+    Ok(())
+}
+
+// 16 bytes per line, with runs of zero lines collapsed into a `*` line.
+fn hex_dump(bytes: &[u8], start: u16) -> String {
+    let mut text = String::new();
+    let mut skipping = false;
+    for (idx, line) in bytes.chunks(16).enumerate() {
+        if line.iter().all(|&byte| byte == 0) && idx > 0 {
+            if !skipping {
+                text.push_str("  *\n");
+                skipping = true;
+            }
+            continue;
+        }
+        skipping = false;
+        let hex: Vec<String> = line.iter().map(|byte| format!("{byte:02X}")).collect();
+        let _ = writeln!(
+            text,
+            "  {:03X}  {}",
+            start as usize + idx * 16,
+            hex.join(" ")
+        );
+    }
+    text
+}
+
+pub fn bench(args: &BenchArgs) -> Result<()> {
+    let mut emu = load_emulator(&read_rom(&args.rom_path)?, 0)?;
+
+    let start = Instant::now();
+    for frame in 0..args.frames {
+        emu.run_frame(args.steps_per_frame)
+            .with_context(|| format!("Emulator error in frame {frame}"))?;
+    }
+    let elapsed = start.elapsed().as_secs_f64();
+
+    let cycles = (args.frames * args.steps_per_frame) as f64;
+    println!(
+        "{} frames ({cycles} cycles) in {:.3} s",
+        args.frames, elapsed
+    );
+    println!(
+        "{:.2} M cycles/s, {:.0} frames/s ({:.0}x real time at 60 Hz)",
+        cycles / elapsed / 1e6,
+        args.frames as f64 / elapsed,
+        args.frames as f64 / elapsed / 60.0
+    );
+    Ok(())
+}
+
+// Run a ROM for the given number of frames without a window. `setup` can enable
+// instrumentation before the ROM is loaded.
+fn run_headless(args: &HeadlessArgs, setup: impl FnOnce(&mut Emulator)) -> Result<Emulator> {
+    let rom = read_rom(&args.rom_path)?;
+    let mut emu = Emulator::with_seed(args.seed);
+    if let Some(platform) = args.platform {
+        emu.set_quirks(platform.quirks());
+    }
+    emu.set_write_protection(args.write_protection.unwrap_or_default());
+    emu.set_error_policy(error_policy(&args.on_error));
+    emu.set_coverage_enabled(args.coverage.is_some());
+    setup(&mut emu);
+    emu.load_rom(&rom).context("Failed to load ROM")?;
+
+    if let Some(after) = args.screenshot_after
+        && !(1..=args.frames).contains(&after)
+    {
+        bail!("--screenshot-after must be between 1 and the number of frames");
+    }
+    let default = Palette::default();
+    let palette = Palette {
+        foreground: parse_color(args.foreground.as_deref())?.unwrap_or(default.foreground),
+        background: parse_color(args.background.as_deref())?.unwrap_or(default.background),
+    };
+    let symbols = load_symbols(args.symbols.as_deref())?;
+    let mut tracer = args.trace.as_deref().map(Tracer::create).transpose()?;
+    let mut clip = args
+        .clip
+        .as_deref()
+        .map(|path| ClipRecorder::create(path, palette, args.scale, HEADLESS_FRAME_RATE))
+        .transpose()?;
+
+    for frame in 0..args.frames {
+        match &mut tracer {
+            Some(tracer) => {
+                for _ in 0..args.steps_per_frame {
+                    tracer.record(frame, &emu, &symbols)?;
+                    emu.cycle().map_err(|err| crashed(err, frame))?;
+                }
+                emu.tick_timers();
+            }
+            None => emu
+                .run_frame(args.steps_per_frame)
+                .map_err(|err| crashed(err, frame))?,
+        }
+        if let Some(clip) = &mut clip {
+            clip.add_frame(&emu)?;
+        }
+        if args.screenshot_after == Some(frame + 1) {
+            let path = args.screenshot.clone().unwrap_or_else(timestamped_path);
+            save_png(&emu, &palette, args.scale, &path)?;
+        }
+    }
+    if let Some(tracer) = tracer {
+        tracer.finish()?;
+    }
+    if let Some(clip) = clip {
+        clip.finish()?;
+    }
+    save_coverage(&emu, &rom, args.coverage.as_deref())?;
+    report_skipped_errors(&emu);
+    Ok(emu)
+}
+
+// Print how many errors the error policy skipped, if any.
+fn report_skipped_errors(emu: &Emulator) {
+    let skipped = emu.get_skipped_errors();
+    if skipped.total() == 0 {
+        return;
+    }
+    let counts: Vec<String> = ErrorKind::ALL
+        .into_iter()
+        .filter(|&kind| skipped.get(kind) > 0)
+        .map(|kind| format!("{kind} {}", skipped.get(kind)))
+        .collect();
+    eprintln!("Skipped {} errors ({})", skipped.total(), counts.join(", "));
+}
+
+// Print the report of an emulator error, with the failing instruction, the registers and the
+// surrounding code, and return the error to stop with.
+fn crashed(err: EmulatorError, frame: usize) -> anyhow::Error {
+    eprintln!("{:?}", Report::new(err));
+    anyhow!("Emulator crashed in frame {frame}")
+}
+
+// Write the coverage the emulator recorded, if a report was asked for.
+pub fn save_coverage(emu: &Emulator, rom: &[u8], path: Option<&Path>) -> Result<()> {
+    if let (Some(path), Some(coverage)) = (path, emu.get_coverage()) {
+        CoverageReport::new(rom, coverage.clone()).save_merged(path)?;
+    }
+    Ok(())
+}
+
+pub fn headless(args: &HeadlessArgs) -> Result<()> {
+    let emu = run_headless(args, |_| {})?;
+    print!("{}", display_to_text(&emu));
+    println!("Display SHA-1: {}", display_sha1(&emu));
+    Ok(())
+}
+
+pub fn profile(args: &ProfileArgs) -> Result<()> {
+    let enable = |emu: &mut Emulator| emu.set_profiling_enabled(true);
+    let symbols = load_symbols(args.headless.symbols.as_deref())?;
+    let emu = match &args.recording {
+        Some(_)
+            if args.headless.trace.is_some()
+                || args.headless.screenshot_after.is_some()
+                || args.headless.clip.is_some() =>
+        {
+            bail!("--trace, --screenshot-after and --clip can't be used together with --recording")
+        }
+        Some(path) => {
+            let rom = read_rom(&args.headless.rom_path)?;
+            let emu = Recording::load(path)?.replay(&rom, enable)?;
+            save_coverage(&emu, &rom, args.headless.coverage.as_deref())?;
+            emu
+        }
+        None => run_headless(&args.headless, enable)?,
+    };
+    let profile = emu.get_profile().context("Profiling was not enabled")?;
+
+    match args.format {
+        ProfileFormat::Flat => print!(
+            "{}",
+            profile::flat(profile, emu.get_ram(), &symbols, args.top)
+        ),
+        ProfileFormat::Tree => print!("{}", profile::call_tree(profile, &symbols)),
+        ProfileFormat::Folded => print!("{}", profile::folded(profile, &symbols)),
+    }
+    Ok(())
+}
+
+pub fn replay(args: &ReplayArgs) -> Result<()> {
+    let recording = Recording::load(&args.recording)?;
+    let rom = read_rom(&args.rom_path)?;
+    let emu = recording.replay(&rom, |emu| {
+        emu.set_coverage_enabled(args.coverage.is_some())
+    })?;
+    save_coverage(&emu, &rom, args.coverage.as_deref())?;
+    print!("{}", display_to_text(&emu));
+    println!("Display SHA-1: {}", display_sha1(&emu));
+    Ok(())
+}
+
+pub fn test(args: &TestArgs) -> Result<()> {
+    let emu = run_headless(&args.headless, |_| {})?;
+    let hash = display_sha1(&emu);
+
+    if let Some(expected) = &args.expect
+        && !hash.eq_ignore_ascii_case(expected)
+    {
+        eprint!("{}", display_to_text(&emu));
+        bail!("Display SHA-1 {hash} does not match the expected {expected}");
+    }
+    println!("ok: {} ({hash})", args.headless.rom_path.display());
+    Ok(())
+}
+
+pub fn test_suite(args: &TestSuiteArgs) -> Result<()> {
+    let mut suite = Suite::built_in()?;
+    if let Some(path) = &args.suite {
+        suite.extend(Suite::load(path)?);
+    }
+    let (matrix, passed) = run_suite(&suite, &args.paths, args.hashes)?;
+    print!("{matrix}");
+    if !passed {
+        bail!("Not all test ROM checks passed");
+    }
+    Ok(())
+}
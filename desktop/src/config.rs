@@ -0,0 +1,400 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use emulator::{
+    ErrorAction, ErrorKind, ErrorPolicy, Palette, Platform, Quirks, WriteProtection, analyze_rom,
+    constants::NUM_KEYS,
+};
+use serde::{Deserialize, Serialize};
+use winit::keyboard::KeyCode;
+
+use crate::args::RunArgs;
+use crate::keyboard::DEFAULT_KEYS;
+
+const DEFAULT_CPU_FREQUENCY: u32 = 500;
+const DEFAULT_DISPLAY_FREQUENCY: u32 = 60;
+const DEFAULT_TIMER_FREQUENCY: u32 = 60;
+const DEFAULT_FOREGROUND: [u8; 3] = [0xFF, 0xFF, 0xFF];
+const DEFAULT_BACKGROUND: [u8; 3] = [0x00, 0x00, 0x00];
+
+// Individual quirk overrides, applied on top of the platform's quirks.
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct QuirkSettings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shift_vy: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub increment_i: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vf_reset: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jump_vx: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wrap_sprites: Option<bool>,
+}
+
+impl From<Quirks> for QuirkSettings {
+    fn from(quirks: Quirks) -> Self {
+        Self {
+            shift_vy: Some(quirks.shift_vy),
+            increment_i: Some(quirks.increment_i),
+            vf_reset: Some(quirks.vf_reset),
+            jump_vx: Some(quirks.jump_vx),
+            wrap_sprites: Some(quirks.wrap_sprites),
+        }
+    }
+}
+
+// What to do on each kind of CPU error: `halt`, `skip` or `log`.
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ErrorSettings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unknown_opcode: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stack: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memory: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+}
+
+impl ErrorSettings {
+    pub fn action(&self, kind: ErrorKind) -> Option<&str> {
+        match kind {
+            ErrorKind::UnknownOpcode => self.unknown_opcode.as_deref(),
+            ErrorKind::Stack => self.stack.as_deref(),
+            ErrorKind::Memory => self.memory.as_deref(),
+            ErrorKind::Key => self.key.as_deref(),
+        }
+    }
+
+    pub fn action_mut(&mut self, kind: ErrorKind) -> &mut Option<String> {
+        match kind {
+            ErrorKind::UnknownOpcode => &mut self.unknown_opcode,
+            ErrorKind::Stack => &mut self.stack,
+            ErrorKind::Memory => &mut self.memory,
+            ErrorKind::Key => &mut self.key,
+        }
+    }
+}
+
+impl From<ErrorPolicy> for ErrorSettings {
+    fn from(policy: ErrorPolicy) -> Self {
+        let mut settings = Self::default();
+        for kind in ErrorKind::ALL {
+            *settings.action_mut(kind) = Some(policy.action(kind).name().to_string());
+        }
+        settings
+    }
+}
+
+// Settings from one section of the config file, or from the command line.
+// Values that are not set fall through to the previous source.
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Settings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cpu_frequency: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_frequency: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timer_frequency: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub steps_per_frame: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub platform: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub foreground: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub background: Option<String>,
+    // off, warn or error.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub write_protection: Option<String>,
+    pub quirks: QuirkSettings,
+    pub errors: ErrorSettings,
+    // CHIP-8 key (hex digit) -> keyboard key code, e.g. `5 = "KeyW"`.
+    pub keys: BTreeMap<String, KeyCode>,
+}
+
+impl Settings {
+    // Override these settings with the values set in `other`.
+    fn merge(&mut self, other: Settings) {
+        fn pick<T>(current: &mut Option<T>, new: Option<T>) {
+            if new.is_some() {
+                *current = new;
+            }
+        }
+        pick(&mut self.cpu_frequency, other.cpu_frequency);
+        pick(&mut self.display_frequency, other.display_frequency);
+        pick(&mut self.timer_frequency, other.timer_frequency);
+        pick(&mut self.steps_per_frame, other.steps_per_frame);
+        pick(&mut self.platform, other.platform);
+        pick(&mut self.foreground, other.foreground);
+        pick(&mut self.background, other.background);
+        pick(&mut self.quirks.shift_vy, other.quirks.shift_vy);
+        pick(&mut self.quirks.increment_i, other.quirks.increment_i);
+        pick(&mut self.quirks.vf_reset, other.quirks.vf_reset);
+        pick(&mut self.quirks.jump_vx, other.quirks.jump_vx);
+        pick(&mut self.quirks.wrap_sprites, other.quirks.wrap_sprites);
+        pick(&mut self.write_protection, other.write_protection);
+        let mut errors = other.errors;
+        for kind in ErrorKind::ALL {
+            pick(self.errors.action_mut(kind), errors.action_mut(kind).take());
+        }
+        self.keys.extend(other.keys);
+    }
+}
+
+// Layout of the config file: global defaults and per-ROM sections keyed by SHA-1 or file name.
+//
+//     [default]
+//     cpu-frequency = 700
+//
+//     [rom."Pong (1 player).ch8"]
+//     platform = "superchip"
+//     foreground = "#33FF66"
+//     keys = { 1 = "ArrowUp", 4 = "ArrowDown" }
+//
+//     [rom.112dab1eec8627329152b26d29c40fa2c5757c5e]
+//     quirks = { vf-reset = true }
+//     errors = { unknown-opcode = "skip" }
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigFile {
+    default: Settings,
+    rom: BTreeMap<String, Settings>,
+}
+
+// The effective configuration for running a ROM, after merging all sources.
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub cpu_frequency: u32,
+    pub display_frequency: u32,
+    pub timer_frequency: u32,
+    pub steps_per_frame: Option<usize>,
+    pub platform: Option<Platform>,
+    pub quirks: Quirks,
+    pub error_policy: ErrorPolicy,
+    pub write_protection: WriteProtection,
+    pub foreground: [u8; 3],
+    pub background: [u8; 3],
+    pub keys: [KeyCode; NUM_KEYS],
+    // Where the settings came from, lowest priority first (for `--print-config`).
+    pub sources: Vec<String>,
+}
+
+impl Config {
+    // Resolve the configuration for a ROM. Later sources win: built-in defaults, a guess from the
+    // ROM's code, the `[default]` section, the ROM's file name section, the ROM's SHA-1 section,
+    // then command line flags.
+    pub fn load(args: &RunArgs, rom: &[u8]) -> Result<Self> {
+        let mut settings = Settings::default();
+        let mut sources = vec!["built-in defaults".to_string()];
+        let sha1 = sha1_smol::Sha1::from(rom).digest().to_string();
+
+        // Guess the platform and quirks from the code.
+        let analysis = analyze_rom(rom);
+        sources.push(format!(
+            "ROM analysis: {}",
+            analysis.platform.display_name()
+        ));
+        // Only the quirks that differ from the platform's set, so choosing another platform later
+        // still switches all of them.
+        let platform_quirks = QuirkSettings::from(analysis.platform.quirks());
+        let mut quirks = QuirkSettings::from(analysis.quirks);
+        for (quirk, preset) in [
+            (&mut quirks.shift_vy, platform_quirks.shift_vy),
+            (&mut quirks.increment_i, platform_quirks.increment_i),
+            (&mut quirks.vf_reset, platform_quirks.vf_reset),
+            (&mut quirks.jump_vx, platform_quirks.jump_vx),
+            (&mut quirks.wrap_sprites, platform_quirks.wrap_sprites),
+        ] {
+            if *quirk == preset {
+                *quirk = None;
+            }
+        }
+        settings.merge(Settings {
+            platform: Some(analysis.platform.name().to_string()),
+            quirks,
+            ..Settings::default()
+        });
+
+        let path = match &args.config {
+            Some(path) => Some(path.clone()),
+            None => default_config_path().filter(|path| path.exists()),
+        };
+        if let Some(path) = path {
+            let mut file = read_config_file(&path)?;
+            let display = path.display();
+
+            settings.merge(file.default);
+            sources.push(format!("{display} [default]"));
+
+            let file_name = Path::new(&args.rom_path)
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned());
+            if let Some(section) = file_name.and_then(|name| file.rom.remove_entry(&name)) {
+                sources.push(format!("{display} [rom.\"{}\"]", section.0));
+                settings.merge(section.1);
+            }
+
+            let section = file
+                .rom
+                .into_iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(&sha1));
+            if let Some((key, section)) = section {
+                sources.push(format!("{display} [rom.{key}]"));
+                settings.merge(section);
+            }
+        }
+
+        settings.merge(args.settings());
+        sources.push("command line".to_string());
+
+        Self::resolve(settings, sources)
+    }
+
+    // Turn merged settings into concrete values, filling in defaults.
+    fn resolve(settings: Settings, sources: Vec<String>) -> Result<Self> {
+        let platform = settings
+            .platform
+            .as_deref()
+            .map(|name| name.parse::<Platform>())
+            .transpose()
+            .context("Invalid platform")?;
+
+        // Quirks start from the platform's set (or the emulator's defaults) and can be tweaked individually.
+        let mut quirks = platform
+            .map(|platform| platform.quirks())
+            .unwrap_or_default();
+        let overrides = &settings.quirks;
+        quirks.shift_vy = overrides.shift_vy.unwrap_or(quirks.shift_vy);
+        quirks.increment_i = overrides.increment_i.unwrap_or(quirks.increment_i);
+        quirks.vf_reset = overrides.vf_reset.unwrap_or(quirks.vf_reset);
+        quirks.jump_vx = overrides.jump_vx.unwrap_or(quirks.jump_vx);
+        quirks.wrap_sprites = overrides.wrap_sprites.unwrap_or(quirks.wrap_sprites);
+
+        let mut error_policy = ErrorPolicy::default();
+        for kind in ErrorKind::ALL {
+            if let Some(action) = settings.errors.action(kind) {
+                let action = action
+                    .parse::<ErrorAction>()
+                    .with_context(|| format!("Invalid action for {kind} errors"))?;
+                error_policy.set_action(kind, action);
+            }
+        }
+
+        let write_protection = settings
+            .write_protection
+            .as_deref()
+            .map(|mode| mode.parse::<WriteProtection>())
+            .transpose()
+            .context("Invalid write protection")?
+            .unwrap_or_default();
+
+        let mut keys = DEFAULT_KEYS;
+        for (key, code) in &settings.keys {
+            match usize::from_str_radix(key, 16) {
+                Ok(idx) if idx < NUM_KEYS => keys[idx] = *code,
+                _ => bail!("Invalid CHIP-8 key '{key}' in key bindings (expected 0 - F)"),
+            }
+        }
+
+        let config = Self {
+            cpu_frequency: settings.cpu_frequency.unwrap_or(DEFAULT_CPU_FREQUENCY),
+            display_frequency: settings
+                .display_frequency
+                .unwrap_or(DEFAULT_DISPLAY_FREQUENCY),
+            timer_frequency: settings.timer_frequency.unwrap_or(DEFAULT_TIMER_FREQUENCY),
+            steps_per_frame: settings.steps_per_frame,
+            platform,
+            quirks,
+            error_policy,
+            write_protection,
+            foreground: parse_color(settings.foreground.as_deref())?.unwrap_or(DEFAULT_FOREGROUND),
+            background: parse_color(settings.background.as_deref())?.unwrap_or(DEFAULT_BACKGROUND),
+            keys,
+            sources,
+        };
+        if config.cpu_frequency == 0 || config.display_frequency == 0 || config.timer_frequency == 0
+        {
+            bail!("Frequencies must be greater than 0");
+        }
+        Ok(config)
+    }
+
+    pub fn palette(&self) -> Palette {
+        Palette {
+            foreground: self.foreground,
+            background: self.background,
+        }
+    }
+
+    // Steps per frame: use the setting if present, otherwise cpu_frequency / display_frequency.
+    pub fn steps_per_frame(&self) -> usize {
+        self.steps_per_frame
+            .unwrap_or((self.cpu_frequency / self.display_frequency) as usize)
+    }
+
+    // The effective configuration in config file syntax, preceded by the sources it came from.
+    pub fn to_toml(&self) -> Result<String> {
+        let settings = Settings {
+            cpu_frequency: Some(self.cpu_frequency),
+            display_frequency: Some(self.display_frequency),
+            timer_frequency: Some(self.timer_frequency),
+            steps_per_frame: Some(self.steps_per_frame()),
+            platform: self.platform.map(|platform| platform.name().to_string()),
+            foreground: Some(format_color(self.foreground)),
+            background: Some(format_color(self.background)),
+            quirks: QuirkSettings::from(self.quirks),
+            write_protection: Some(self.write_protection.name().to_string()),
+            errors: ErrorSettings::from(self.error_policy),
+            keys: self
+                .keys
+                .iter()
+                .enumerate()
+                .map(|(idx, code)| (format!("{idx:X}"), *code))
+                .collect(),
+        };
+
+        let mut text: String = self
+            .sources
+            .iter()
+            .map(|source| format!("# from: {source}\n"))
+            .collect();
+        text.push_str(&toml::to_string(&settings).context("Failed to format config")?);
+        Ok(text)
+    }
+}
+
+// `$XDG_CONFIG_HOME/chip8-rs/config.toml` (or the platform equivalent).
+pub fn default_config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("chip8-rs").join("config.toml"))
+}
+
+fn read_config_file(path: &Path) -> Result<ConfigFile> {
+    let text = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read config file '{}'", path.display()))?;
+    toml::from_str(&text).with_context(|| format!("Invalid config file '{}'", path.display()))
+}
+
+// Parse a `#RRGGBB` color.
+pub fn parse_color(value: Option<&str>) -> Result<Option<[u8; 3]>> {
+    let Some(value) = value else {
+        return Ok(None);
+    };
+    let hex = value.strip_prefix('#').unwrap_or(value);
+    let rgb = u32::from_str_radix(hex, 16)
+        .ok()
+        .filter(|_| hex.len() == 6)
+        .with_context(|| format!("Invalid color '{value}' (expected #RRGGBB)"))?;
+    let [_, r, g, b] = rgb.to_be_bytes();
+    Ok(Some([r, g, b]))
+}
+
+fn format_color([r, g, b]: [u8; 3]) -> String {
+    format!("#{r:02X}{g:02X}{b:02X}")
+}
@@ -0,0 +1,143 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use emulator::{Access, Coverage};
+
+const HEADER: &str = "chip8-coverage 1";
+
+// RAM coverage of one or more sessions with a ROM, see `Emulator::set_coverage_enabled`.
+//
+// The text format is a header line, the ROM's SHA-1 and one `start-end flags` line per run of
+// addresses with the same accesses (`x` = executed, `r` = read, `w` = written). Lines starting
+// with `#` are comments:
+//
+//     chip8-coverage 1
+//     rom-sha1 0123abcd...
+//     # 42 bytes executed, 90 read, 0 written
+//     200-229 x--
+//     22A-284 -r-
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoverageReport {
+    pub rom_sha1: String,
+    pub coverage: Coverage,
+}
+
+impl CoverageReport {
+    pub fn new(rom: &[u8], coverage: Coverage) -> Self {
+        Self {
+            rom_sha1: sha1_smol::Sha1::from(rom).digest().to_string(),
+            coverage,
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read coverage report '{}'", path.display()))?;
+        Self::parse(&text).with_context(|| format!("Invalid coverage report '{}'", path.display()))
+    }
+
+    // Save the report, adding the coverage of an existing report for the same ROM so that
+    // several sessions add up.
+    pub fn save_merged(mut self, path: &Path) -> Result<()> {
+        if path.exists() {
+            let existing = Self::load(path)?;
+            if existing.rom_sha1 == self.rom_sha1 {
+                for (range, access) in existing.coverage.ranges() {
+                    range.for_each(|addr| self.coverage.add(addr, access));
+                }
+            }
+        }
+        fs::write(path, self.to_text())
+            .with_context(|| format!("Failed to write coverage report '{}'", path.display()))
+    }
+
+    pub fn to_text(&self) -> String {
+        let ranges = self.coverage.ranges();
+        let count = |used: fn(&Access) -> bool| -> usize {
+            ranges
+                .iter()
+                .filter(|(_, access)| used(access))
+                .map(|(range, _)| range.len())
+                .sum()
+        };
+
+        let mut text = format!("{HEADER}\nrom-sha1 {}\n", self.rom_sha1);
+        let _ = writeln!(
+            text,
+            "# {} bytes executed, {} read, {} written",
+            count(|access| access.executed),
+            count(|access| access.read),
+            count(|access| access.written)
+        );
+        for (range, access) in &ranges {
+            let _ = writeln!(
+                text,
+                "{:03X}-{:03X} {}",
+                range.start,
+                range.end - 1,
+                access_flags(access)
+            );
+        }
+        text
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut lines = text
+            .lines()
+            .filter(|line| !line.trim().is_empty() && !line.starts_with('#'));
+        if lines.next() != Some(HEADER) {
+            bail!("Missing '{HEADER}' header");
+        }
+
+        let mut report = CoverageReport {
+            rom_sha1: String::new(),
+            coverage: Coverage::new(),
+        };
+        for line in lines {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields.as_slice() {
+                ["rom-sha1", hash] => report.rom_sha1 = hash.to_string(),
+                [range, flags] => {
+                    let (start, end) = range
+                        .split_once('-')
+                        .with_context(|| format!("Invalid address range '{range}'"))?;
+                    let start = u16::from_str_radix(start, 16).context("Invalid start address")?;
+                    let end = u16::from_str_radix(end, 16).context("Invalid end address")?;
+                    let access = parse_access_flags(flags)
+                        .with_context(|| format!("Invalid access flags '{flags}'"))?;
+                    (start..=end).for_each(|addr| report.coverage.add(addr, access));
+                }
+                _ => bail!("Unexpected line '{line}'"),
+            }
+        }
+        Ok(report)
+    }
+}
+
+// `xrw` flags, with `-` for accesses that didn't happen.
+pub fn access_flags(access: &Access) -> String {
+    [
+        (access.executed, 'x'),
+        (access.read, 'r'),
+        (access.written, 'w'),
+    ]
+    .into_iter()
+    .map(|(used, flag)| if used { flag } else { '-' })
+    .collect()
+}
+
+fn parse_access_flags(flags: &str) -> Option<Access> {
+    let mut access = Access::default();
+    for flag in flags.chars() {
+        match flag {
+            'x' => access.executed = true,
+            'r' => access.read = true,
+            'w' => access.written = true,
+            '-' => {}
+            _ => return None,
+        }
+    }
+    Some(access)
+}
@@ -0,0 +1,89 @@
+use emulator::{
+    EmulatorError,
+    constants::{DISPLAY_WIDTH, FONTSET},
+};
+
+const BOX_COLOR: [u8; 4] = [0x90, 0x10, 0x10, 0xFF];
+const TEXT_COLOR: [u8; 4] = [0xFF, 0xFF, 0xFF, 0xFF];
+// The screen behind the box is darkened to a quarter of its brightness.
+const DIM_SHIFT: u8 = 2;
+// Glyphs are 4x5 pixels, one pixel apart.
+const GLYPH_WIDTH: usize = 5;
+const GLYPH_HEIGHT: usize = 5;
+// "P" is the only letter missing from the hex digits of the CHIP-8 font.
+const GLYPH_P: [u8; 5] = [0xF0, 0x90, 0xF0, 0x80, 0x80];
+
+// Drawn over the (frozen) display after the emulator stopped with an error: the screen is dimmed
+// and a red box shows the address and opcode of the failing instruction, as "PC 0204" and
+// "0P FFFF" in the CHIP-8 font.
+pub struct CrashOverlay {
+    pc: Option<u16>,
+    opcode: Option<u16>,
+}
+
+impl CrashOverlay {
+    pub fn new(err: &EmulatorError) -> Self {
+        match err {
+            EmulatorError::Cpu { source } => Self {
+                pc: Some(source.pc),
+                opcode: source.opcode,
+            },
+            _ => Self {
+                pc: None,
+                opcode: None,
+            },
+        }
+    }
+
+    // Draw onto an RGBA frame of the display's size.
+    pub fn draw(&self, frame: &mut [u8]) {
+        for channel in frame.chunks_exact_mut(4).flat_map(|pixel| &mut pixel[..3]) {
+            *channel >>= DIM_SHIFT;
+        }
+
+        let (top, bottom) = (8, 25);
+        let (left, right) = (12, 52);
+        for y in top..bottom {
+            for x in left..right {
+                set_pixel(frame, x, y, BOX_COLOR);
+            }
+        }
+
+        let text_left = 15;
+        draw_line(frame, text_left, top + 2, ['P', 'C'], self.pc);
+        draw_line(frame, text_left, top + 10, ['0', 'P'], self.opcode);
+    }
+}
+
+// A two letter label and a 16-bit value in hex (blank if unknown).
+fn draw_line(frame: &mut [u8], x: usize, y: usize, label: [char; 2], value: Option<u16>) {
+    let digits = value.map(|value| format!("{value:04X}"));
+    let text = label
+        .into_iter()
+        .chain([' '])
+        .chain(digits.iter().flat_map(|digits| digits.chars()));
+    for (idx, letter) in text.enumerate() {
+        let glyph = match letter {
+            'P' => &GLYPH_P[..],
+            ' ' => continue,
+            digit => {
+                let digit = digit.to_digit(16).unwrap_or(0) as usize;
+                &FONTSET[digit * GLYPH_HEIGHT..(digit + 1) * GLYPH_HEIGHT]
+            }
+        };
+        for (row, bits) in glyph.iter().enumerate() {
+            for col in 0..4 {
+                if bits & (0x80 >> col) != 0 {
+                    set_pixel(frame, x + idx * GLYPH_WIDTH + col, y + row, TEXT_COLOR);
+                }
+            }
+        }
+    }
+}
+
+fn set_pixel(frame: &mut [u8], x: usize, y: usize, color: [u8; 4]) {
+    let offset = (y * DISPLAY_WIDTH + x) * 4;
+    if let Some(pixel) = frame.get_mut(offset..offset + 4) {
+        pixel.copy_from_slice(&color);
+    }
+}
@@ -1,22 +1,83 @@
 mod app;
 mod args;
+mod clip;
+mod commands;
+mod config;
+mod coverage;
+mod crash;
 mod keyboard;
+mod profile;
+mod recording;
+mod screenshot;
 mod sound;
+mod test_suite;
+mod trace;
 
-use app::App;
-use args::Args;
+use anyhow::Result;
+use app::{App, Recorder};
+use args::{Args, Command, RunArgs};
 use clap::Parser;
+use config::Config;
+use recording::Recording;
 
-fn main() {
+fn main() -> Result<()> {
     // Parse command line arguments.
     let args = Args::parse();
 
     // Initialize logging.
     pretty_env_logger::init();
 
+    match (args.command, args.run) {
+        (Some(Command::Run(run_args)), _) | (None, Some(run_args)) => run(run_args),
+        (Some(Command::Disasm(disasm_args)), _) => commands::disasm(&disasm_args),
+        (Some(Command::Info(rom_args)), _) => commands::info(&rom_args),
+        (Some(Command::Cfg(cfg_args)), _) => commands::cfg(&cfg_args),
+        (Some(Command::MemoryMap(map_args)), _) => commands::memory_map(&map_args),
+        (Some(Command::Profile(profile_args)), _) => commands::profile(&profile_args),
+        (Some(Command::Bench(bench_args)), _) => commands::bench(&bench_args),
+        (Some(Command::Headless(headless_args)), _) => commands::headless(&headless_args),
+        (Some(Command::Record(record_args)), _) => {
+            let rom = commands::read_rom(record_args.run.rom_path.as_ref())?;
+            let config = Config::load(&record_args.run, &rom)?;
+            if record_args.run.print_config {
+                print!("{}", config.to_toml()?);
+                return Ok(());
+            }
+            let recording = Recording::new(
+                &rom,
+                record_args.seed,
+                config.steps_per_frame(),
+                config.quirks,
+                config.write_protection,
+                config.error_policy,
+            );
+            let recorder = Recorder {
+                recording,
+                path: record_args.output,
+            };
+            App::with_recorder(record_args.run, config, recorder).run()
+        }
+        (Some(Command::Replay(replay_args)), _) => commands::replay(&replay_args),
+        (Some(Command::Test(test_args)), _) => commands::test(&test_args),
+        (Some(Command::TestSuite(suite_args)), _) => commands::test_suite(&suite_args),
+        // `arg_required_else_help` makes clap print the help text in this case.
+        (None, None) => Ok(()),
+    }
+}
+
+// Run a ROM in a window.
+fn run(args: RunArgs) -> Result<()> {
+    // Merge the config file with the command line flags.
+    let rom = commands::read_rom(args.rom_path.as_ref())?;
+    let config = Config::load(&args, &rom)?;
+    if args.print_config {
+        print!("{}", config.to_toml()?);
+        return Ok(());
+    }
+
     // Initialize application.
-    let mut app = App::new(args);
+    let mut app = App::new(args, config);
 
     // Initialize event loop & run the application.
-    app.run().unwrap();
+    app.run()
 }
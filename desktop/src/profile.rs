@@ -0,0 +1,132 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+use emulator::{Instruction, Profile, SymbolTable};
+
+// Name of a subroutine (the program start for the outermost frame) in profiles.
+fn frame_name(addr: u16, symbols: &SymbolTable) -> String {
+    symbols.describe(addr)
+}
+
+fn percent(count: u64, total: u64) -> String {
+    format!("{:.1}%", 100.0 * count as f64 / total.max(1) as f64)
+}
+
+// The `top` most executed addresses and subroutines. A subroutine's self count is the
+// instructions executed in it directly, its total count includes the subroutines it calls.
+pub fn flat(profile: &Profile, ram: &[u8], symbols: &SymbolTable, top: usize) -> String {
+    let total = profile.total();
+    let mut text = format!("Instructions: {total}\n\n");
+
+    let mut addresses: Vec<(u16, u64)> = profile.address_counts().collect();
+    addresses.sort_by_key(|&(addr, count)| (std::cmp::Reverse(count), addr));
+    let _ = writeln!(
+        text,
+        "{:<10}  {:>12}  {:>6}  Instruction",
+        "Address", "Count", "%"
+    );
+    for (addr, count) in addresses.into_iter().take(top) {
+        let opcode = match (ram.get(addr as usize), ram.get(addr as usize + 1)) {
+            (Some(&high), Some(&low)) => u16::from_be_bytes([high, low]),
+            _ => 0,
+        };
+        let _ = writeln!(
+            text,
+            "{:<10}  {count:>12}  {:>6}  {}",
+            match symbols.is_empty() {
+                true => format!("{addr:03X}"),
+                false => symbols.describe(addr),
+            },
+            percent(count, total),
+            symbols.instruction(Instruction::decode(opcode))
+        );
+    }
+
+    // Subroutine -> (self, total).
+    let mut subroutines: BTreeMap<u16, (u64, u64)> = BTreeMap::new();
+    for (stack, count) in profile.stack_counts() {
+        if let Some(&current) = stack.last() {
+            subroutines.entry(current).or_default().0 += count;
+        }
+        // Count recursive calls once.
+        let mut seen: Vec<u16> = stack.to_vec();
+        seen.sort_unstable();
+        seen.dedup();
+        for addr in seen {
+            subroutines.entry(addr).or_default().1 += count;
+        }
+    }
+    let mut subroutines: Vec<(u16, (u64, u64))> = subroutines.into_iter().collect();
+    subroutines.sort_by_key(|&(addr, (own, _))| (std::cmp::Reverse(own), addr));
+
+    let _ = writeln!(
+        text,
+        "\n{:<10}  {:>12}  {:>6}  {:>12}  {:>6}",
+        "Subroutine", "Self", "%", "Total", "%"
+    );
+    for (addr, (own, all)) in subroutines.into_iter().take(top) {
+        let _ = writeln!(
+            text,
+            "{:<10}  {own:>12}  {:>6}  {all:>12}  {:>6}",
+            frame_name(addr, symbols),
+            percent(own, total),
+            percent(all, total)
+        );
+    }
+    text
+}
+
+// The call stacks as an indented tree, heaviest calls first.
+pub fn call_tree(profile: &Profile, symbols: &SymbolTable) -> String {
+    // Call path -> (self, total).
+    let mut nodes: BTreeMap<Vec<u16>, (u64, u64)> = BTreeMap::new();
+    for (stack, count) in profile.stack_counts() {
+        nodes.entry(stack.to_vec()).or_default().0 += count;
+        for depth in 1..=stack.len() {
+            nodes.entry(stack[..depth].to_vec()).or_default().1 += count;
+        }
+    }
+
+    let total = profile.total();
+    let mut text = format!(
+        "{:>12}  {:>6}  {:>12}  {:>6}  Subroutine\n",
+        "Total", "%", "Self", "%"
+    );
+    let mut pending: Vec<&Vec<u16>> = children(&nodes, &[]);
+    while let Some(path) = pending.pop() {
+        let (own, all) = nodes[path];
+        let _ = writeln!(
+            text,
+            "{all:>12}  {:>6}  {own:>12}  {:>6}  {}{}",
+            percent(all, total),
+            percent(own, total),
+            "  ".repeat(path.len() - 1),
+            frame_name(path[path.len() - 1], symbols)
+        );
+        pending.extend(children(&nodes, path));
+    }
+    text
+}
+
+// Direct children of `path`, lightest first (so popping them visits the heaviest first).
+fn children<'a>(nodes: &'a BTreeMap<Vec<u16>, (u64, u64)>, path: &[u16]) -> Vec<&'a Vec<u16>> {
+    let mut children: Vec<&Vec<u16>> = nodes
+        .keys()
+        .filter(|child| child.len() == path.len() + 1 && child.starts_with(path))
+        .collect();
+    children.sort_by_key(|child| (nodes[*child].1, std::cmp::Reverse(child[path.len()])));
+    children
+}
+
+// Folded stacks (`0x200;0x2A0;0x2B4 1234`), the input format of flamegraph.pl and inferno.
+pub fn folded(profile: &Profile, symbols: &SymbolTable) -> String {
+    let mut text = String::new();
+    for (stack, count) in profile.stack_counts() {
+        let frames: Vec<String> = stack
+            .iter()
+            .map(|&addr| frame_name(addr, symbols))
+            .collect();
+        let _ = writeln!(text, "{} {count}", frames.join(";"));
+    }
+    text
+}
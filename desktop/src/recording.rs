@@ -0,0 +1,220 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use emulator::{Emulator, ErrorKind, ErrorPolicy, Quirks, WriteProtection};
+
+const HEADER: &str = "chip8-recording 1";
+
+// A keypad press or release, applied before the given frame is run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InputEvent {
+    pub frame: u64,
+    pub key: usize,
+    pub pressed: bool,
+}
+
+// Keypad input recorded against emulated frames, so a run can be replayed exactly.
+//
+// The text format is a header line, `key value` settings (including one `quirk name on|off` line
+// per quirk, `write-protection` and one `on-error kind action` line per kind of error) and one
+// `frame press|release key` line per event:
+//
+//     chip8-recording 1
+//     rom-sha1 0123abcd...
+//     seed 0
+//     steps-per-frame 8
+//     quirk shift-vy on
+//     ...
+//     write-protection off
+//     on-error unknown-opcode skip
+//     ...
+//     frames 600
+//     12 press 5
+//     20 release 5
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recording {
+    pub rom_sha1: String,
+    pub seed: u64,
+    pub steps_per_frame: usize,
+    pub quirks: Quirks,
+    // Recordings without `write-protection` and `on-error` lines use the defaults.
+    pub write_protection: WriteProtection,
+    pub error_policy: ErrorPolicy,
+    pub frames: u64,
+    pub events: Vec<InputEvent>,
+}
+
+impl Recording {
+    pub fn new(
+        rom: &[u8],
+        seed: u64,
+        steps_per_frame: usize,
+        quirks: Quirks,
+        write_protection: WriteProtection,
+        error_policy: ErrorPolicy,
+    ) -> Self {
+        Self {
+            rom_sha1: sha1_smol::Sha1::from(rom).digest().to_string(),
+            seed,
+            steps_per_frame,
+            quirks,
+            write_protection,
+            error_policy,
+            frames: 0,
+            events: Vec::new(),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read recording '{}'", path.display()))?;
+        Self::parse(&text).with_context(|| format!("Invalid recording '{}'", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, self.to_text())
+            .with_context(|| format!("Failed to write recording '{}'", path.display()))
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = format!(
+            "{HEADER}\nrom-sha1 {}\nseed {}\nsteps-per-frame {}\n",
+            self.rom_sha1, self.seed, self.steps_per_frame
+        );
+        for (name, enabled) in quirk_flags(&self.quirks) {
+            let _ = writeln!(text, "quirk {name} {}", if enabled { "on" } else { "off" });
+        }
+        let _ = writeln!(text, "write-protection {}", self.write_protection);
+        for kind in ErrorKind::ALL {
+            let _ = writeln!(text, "on-error {kind} {}", self.error_policy.action(kind));
+        }
+        let _ = writeln!(text, "frames {}", self.frames);
+        for event in &self.events {
+            let action = if event.pressed { "press" } else { "release" };
+            let _ = writeln!(text, "{} {action} {:X}", event.frame, event.key);
+        }
+        text
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut lines = text.lines().filter(|line| !line.trim().is_empty());
+        if lines.next() != Some(HEADER) {
+            bail!("Missing '{HEADER}' header");
+        }
+
+        let mut recording = Recording {
+            rom_sha1: String::new(),
+            seed: 0,
+            steps_per_frame: 0,
+            quirks: Quirks::default(),
+            write_protection: WriteProtection::default(),
+            error_policy: ErrorPolicy::default(),
+            frames: 0,
+            events: Vec::new(),
+        };
+        for line in lines {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields.as_slice() {
+                ["rom-sha1", hash] => recording.rom_sha1 = hash.to_string(),
+                ["seed", seed] => recording.seed = seed.parse().context("Invalid seed")?,
+                ["steps-per-frame", steps] => {
+                    recording.steps_per_frame = steps.parse().context("Invalid steps per frame")?
+                }
+                ["quirk", name, value @ ("on" | "off")] => {
+                    let flag = quirk_flag(&mut recording.quirks, name)
+                        .with_context(|| format!("Unknown quirk '{name}'"))?;
+                    *flag = *value == "on";
+                }
+                ["write-protection", mode] => {
+                    recording.write_protection = mode
+                        .parse()
+                        .with_context(|| format!("Unknown write protection '{mode}'"))?
+                }
+                ["on-error", kind, action] => {
+                    let kind = kind
+                        .parse()
+                        .with_context(|| format!("Unknown error kind '{kind}'"))?;
+                    let action = action
+                        .parse()
+                        .with_context(|| format!("Unknown error action '{action}'"))?;
+                    recording.error_policy.set_action(kind, action);
+                }
+                ["frames", frames] => {
+                    recording.frames = frames.parse().context("Invalid frame count")?
+                }
+                [frame, action @ ("press" | "release"), key] => recording.events.push(InputEvent {
+                    frame: frame.parse().context("Invalid event frame")?,
+                    key: usize::from_str_radix(key, 16).context("Invalid event key")?,
+                    pressed: *action == "press",
+                }),
+                _ => bail!("Unexpected line '{line}'"),
+            }
+        }
+        Ok(recording)
+    }
+
+    pub fn record(&mut self, key: usize, pressed: bool) {
+        self.events.push(InputEvent {
+            frame: self.frames,
+            key,
+            pressed,
+        });
+    }
+
+    // Replay the recording on a fresh emulator, returning it in its final state. `setup` can
+    // enable instrumentation (coverage, profiling) before the ROM is loaded.
+    pub fn replay(&self, rom: &[u8], setup: impl FnOnce(&mut Emulator)) -> Result<Emulator> {
+        let rom_sha1 = sha1_smol::Sha1::from(rom).digest().to_string();
+        if rom_sha1 != self.rom_sha1 {
+            bail!(
+                "The recording was made with a different ROM (SHA-1 {}, got {rom_sha1})",
+                self.rom_sha1
+            );
+        }
+
+        let mut emu = Emulator::with_seed(self.seed);
+        emu.set_quirks(self.quirks);
+        emu.set_write_protection(self.write_protection);
+        emu.set_error_policy(self.error_policy);
+        setup(&mut emu);
+        emu.load_rom(rom)?;
+
+        let mut events = self.events.iter().peekable();
+        for frame in 0..self.frames {
+            while let Some(event) = events.next_if(|event| event.frame == frame) {
+                if event.pressed {
+                    emu.press_key(event.key)?;
+                } else {
+                    emu.release_key(event.key)?;
+                }
+            }
+            emu.run_frame(self.steps_per_frame)
+                .with_context(|| format!("Emulator error in frame {frame}"))?;
+        }
+        Ok(emu)
+    }
+}
+
+// Names used for the quirks in recordings (the same as in config files).
+pub fn quirk_flags(quirks: &Quirks) -> [(&'static str, bool); 5] {
+    [
+        ("shift-vy", quirks.shift_vy),
+        ("increment-i", quirks.increment_i),
+        ("vf-reset", quirks.vf_reset),
+        ("jump-vx", quirks.jump_vx),
+        ("wrap-sprites", quirks.wrap_sprites),
+    ]
+}
+
+fn quirk_flag<'a>(quirks: &'a mut Quirks, name: &str) -> Option<&'a mut bool> {
+    match name {
+        "shift-vy" => Some(&mut quirks.shift_vy),
+        "increment-i" => Some(&mut quirks.increment_i),
+        "vf-reset" => Some(&mut quirks.vf_reset),
+        "jump-vx" => Some(&mut quirks.jump_vx),
+        "wrap-sprites" => Some(&mut quirks.wrap_sprites),
+        _ => None,
+    }
+}
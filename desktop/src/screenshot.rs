@@ -0,0 +1,62 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use emulator::{
+    Emulator, Palette,
+    constants::{DISPLAY_HEIGHT, DISPLAY_WIDTH},
+};
+
+// Size of a CHIP-8 pixel in screenshots.
+pub const SCREENSHOT_SCALE: usize = 10;
+
+// Write the display to a PNG file.
+pub fn save_png(emu: &Emulator, palette: &Palette, scale: usize, path: &Path) -> Result<()> {
+    let file = File::create(path)
+        .with_context(|| format!("Failed to create screenshot '{}'", path.display()))?;
+    let mut encoder = png::Encoder::new(
+        BufWriter::new(file),
+        (DISPLAY_WIDTH * scale) as u32,
+        (DISPLAY_HEIGHT * scale) as u32,
+    );
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(&emu.to_rgba(palette, scale)))
+        .with_context(|| format!("Failed to write screenshot '{}'", path.display()))
+}
+
+// A screenshot file name in the current directory with the UTC date and time, e.g.
+// `chip8-2024-05-01-183012.png`.
+pub fn timestamped_path() -> PathBuf {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0);
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    let time = secs % 86_400;
+    PathBuf::from(format!(
+        "chip8-{year:04}-{month:02}-{day:02}-{:02}{:02}{:02}.png",
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    ))
+}
+
+// Convert days since 1970-01-01 to a (year, month, day) date, see
+// http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
@@ -0,0 +1,304 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use emulator::{
+    Emulator, Platform,
+    constants::{DISPLAY_HEIGHT, DISPLAY_WIDTH},
+};
+use serde::Deserialize;
+
+use crate::commands::read_rom;
+
+const BUILT_IN: &str = include_str!("../data/test-suite.toml");
+
+// Test ROMs that report their results on screen, and how to read those results: each check hashes
+// a region of the display after the run and compares it with the hashes of known result screens.
+//
+//     [[rom]]
+//     name = "Flags"
+//     sha1 = "0123abcd..."
+//     frames = 120
+//     keys = [{ key = 0x1, press = 10, release = 15, platform = "chip8" }]
+//
+//     [[rom.check]]
+//     name = "8XY4"
+//     region = [0, 0, 32, 5]  # x, y, width, height (default: the whole display)
+//     pass = ["..."]
+//     fail = ["..."]
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Suite {
+    #[serde(default, rename = "rom")]
+    roms: Vec<TestRom>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+struct TestRom {
+    name: String,
+    sha1: String,
+    #[serde(default = "default_frames")]
+    frames: usize,
+    #[serde(default = "default_steps_per_frame")]
+    steps_per_frame: usize,
+    #[serde(default)]
+    keys: Vec<KeyPress>,
+    #[serde(rename = "check")]
+    checks: Vec<Check>,
+}
+
+// A key held from frame `press` until frame `release`, only for `platform` if given (e.g. to pick
+// the platform in a test ROM's menu).
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KeyPress {
+    key: usize,
+    press: usize,
+    release: usize,
+    platform: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Check {
+    name: String,
+    region: Option<[usize; 4]>,
+    #[serde(default)]
+    pass: Vec<String>,
+    #[serde(default)]
+    fail: Vec<String>,
+}
+
+fn default_frames() -> usize {
+    120
+}
+
+fn default_steps_per_frame() -> usize {
+    10
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Outcome {
+    Pass,
+    Fail,
+    // A result screen that isn't in the definitions, with its hash.
+    Unknown(String),
+    // The emulator stopped with an error before the end of the run.
+    Error(String),
+}
+
+// The checks of one ROM under one platform preset.
+struct Run<'a> {
+    rom: &'a TestRom,
+    platform: Platform,
+    outcomes: Vec<Outcome>,
+}
+
+impl Suite {
+    pub fn built_in() -> Result<Self> {
+        Self::parse(BUILT_IN).context("Invalid built-in test suite definitions")
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read test suite '{}'", path.display()))?;
+        Self::parse(&text).with_context(|| format!("Invalid test suite '{}'", path.display()))
+    }
+
+    fn parse(text: &str) -> Result<Self> {
+        let suite: Suite = toml::from_str(text)?;
+        for rom in &suite.roms {
+            for key in &rom.keys {
+                if let Some(platform) = &key.platform {
+                    platform.parse::<Platform>()?;
+                }
+            }
+            for check in &rom.checks {
+                if let Some([x, y, width, height]) = check.region
+                    && (x + width > DISPLAY_WIDTH || y + height > DISPLAY_HEIGHT)
+                {
+                    bail!("Region of check '{}' is outside the display", check.name);
+                }
+            }
+        }
+        Ok(suite)
+    }
+
+    // Add definitions, replacing those of the same ROMs.
+    pub fn extend(&mut self, other: Suite) {
+        self.roms
+            .retain(|rom| !other.roms.iter().any(|new| new.sha1 == rom.sha1));
+        self.roms.extend(other.roms);
+    }
+
+    fn find(&self, sha1: &str) -> Option<&TestRom> {
+        self.roms
+            .iter()
+            .find(|rom| rom.sha1.eq_ignore_ascii_case(sha1))
+    }
+}
+
+// ROM files in `paths`, with directories expanded (not recursively).
+fn rom_files(paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            let mut entries: Vec<PathBuf> = fs::read_dir(path)
+                .with_context(|| format!("Failed to read directory '{}'", path.display()))?
+                .map(|entry| entry.map(|entry| entry.path()))
+                .collect::<Result<_, _>>()?;
+            entries.retain(|entry| entry.is_file());
+            entries.sort();
+            files.extend(entries);
+        } else {
+            files.push(path.clone());
+        }
+    }
+    Ok(files)
+}
+
+fn run(rom: &TestRom, data: &[u8], platform: Platform) -> Result<Vec<Outcome>> {
+    let mut emu = Emulator::with_seed(0);
+    emu.set_quirks(platform.quirks());
+    emu.load_rom(data).context("Failed to load ROM")?;
+
+    let keys: Vec<&KeyPress> = rom
+        .keys
+        .iter()
+        .filter(|key| {
+            key.platform
+                .as_ref()
+                .is_none_or(|name| name.parse() == Ok(platform))
+        })
+        .collect();
+    for frame in 0..rom.frames {
+        for key in &keys {
+            if key.press == frame {
+                emu.press_key(key.key)?;
+            }
+            if key.release == frame {
+                emu.release_key(key.key)?;
+            }
+        }
+        emu.run_frame(rom.steps_per_frame)
+            .with_context(|| format!("{} failed in frame {frame}", rom.name))?;
+    }
+
+    let outcomes = rom
+        .checks
+        .iter()
+        .map(|check| {
+            let hash = region_sha1(&emu, check.region);
+            if check.pass.contains(&hash) {
+                Outcome::Pass
+            } else if check.fail.contains(&hash) {
+                Outcome::Fail
+            } else {
+                Outcome::Unknown(hash)
+            }
+        })
+        .collect();
+    Ok(outcomes)
+}
+
+// SHA-1 of a region of the display (one byte per pixel, row by row), the same as `display_sha1`
+// for the whole display.
+fn region_sha1(emu: &Emulator, region: Option<[usize; 4]>) -> String {
+    let [x, y, width, height] = region.unwrap_or([0, 0, DISPLAY_WIDTH, DISPLAY_HEIGHT]);
+    let pixels: Vec<u8> = emu.get_display_buffer()[y..y + height]
+        .iter()
+        .flat_map(|row| &row[x..x + width])
+        .map(|&is_pixel_on| is_pixel_on as u8)
+        .collect();
+    sha1_smol::Sha1::from(pixels).digest().to_string()
+}
+
+// Run every known ROM in `paths` under each platform preset and return the pass/fail matrix,
+// and whether all checks passed. An emulator error fails every check of its run. With `hashes`,
+// unknown results are listed with their hashes (for adding them to the definitions).
+pub fn run_suite(suite: &Suite, paths: &[PathBuf], hashes: bool) -> Result<(String, bool)> {
+    let mut runs = Vec::new();
+    for path in rom_files(paths)? {
+        let data = read_rom(&path)?;
+        let sha1 = sha1_smol::Sha1::from(&data).digest().to_string();
+        let Some(rom) = suite.find(&sha1) else {
+            continue;
+        };
+        for platform in Platform::ALL {
+            let outcomes = run(rom, &data, platform)
+                .unwrap_or_else(|err| vec![Outcome::Error(format!("{err:#}")); rom.checks.len()]);
+            runs.push(Run {
+                rom,
+                platform,
+                outcomes,
+            });
+        }
+    }
+    if runs.is_empty() {
+        bail!(
+            "None of the ROMs are known test ROMs (definitions for other ROMs can be added with --suite)"
+        );
+    }
+
+    let rom_width = runs.iter().map(|run| run.rom.name.len()).max().unwrap_or(0);
+    let check_width = runs
+        .iter()
+        .flat_map(|run| run.rom.checks.iter().map(|check| check.name.len()))
+        .max()
+        .unwrap_or(0);
+    let mut text = format!("{:<rom_width$}  {:<check_width$}", "ROM", "Check");
+    for platform in Platform::ALL {
+        let _ = write!(text, "  {:<9}", platform.name());
+    }
+    text.truncate(text.trim_end().len());
+    text.push('\n');
+
+    let mut passed = true;
+    let mut unknown = String::new();
+    let mut errors = String::new();
+    for platform_runs in runs.chunks(Platform::ALL.len()) {
+        let rom = platform_runs[0].rom;
+        for (idx, check) in rom.checks.iter().enumerate() {
+            let _ = write!(
+                text,
+                "{:<rom_width$}  {:<check_width$}",
+                rom.name, check.name
+            );
+            for run in platform_runs {
+                let cell = match &run.outcomes[idx] {
+                    Outcome::Pass => "pass",
+                    Outcome::Fail => "FAIL",
+                    Outcome::Unknown(hash) => {
+                        let _ = writeln!(
+                            unknown,
+                            "{} / {} / {}: {hash}",
+                            rom.name,
+                            check.name,
+                            run.platform.name()
+                        );
+                        "?"
+                    }
+                    Outcome::Error(message) => {
+                        if idx == 0 {
+                            let _ = writeln!(errors, "{} / {}: {message}", rom.name, run.platform);
+                        }
+                        "ERROR"
+                    }
+                };
+                passed &= run.outcomes[idx] == Outcome::Pass;
+                let _ = write!(text, "  {cell:<9}");
+            }
+            text.truncate(text.trim_end().len());
+            text.push('\n');
+        }
+    }
+    if !errors.is_empty() {
+        let _ = write!(text, "\nErrors:\n{errors}");
+    }
+    if hashes && !unknown.is_empty() {
+        let _ = write!(text, "\nUnrecognized result screens:\n{unknown}");
+    }
+    Ok((text, passed))
+}
@@ -0,0 +1,54 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use emulator::{Emulator, Instruction, SymbolTable};
+
+// Writes one line per executed instruction: the frame, the location of the PC (a symbol with
+// offset if there is one), the opcode and instruction, and I and V0-VF before it runs:
+//
+//     0     0x200          00E0  CLS                   I=000 V=00 00 00 ...
+//     0     draw+0x2       A22A  LD I, sprite          I=000 V=00 00 00 ...
+pub struct Tracer {
+    path: PathBuf,
+    writer: BufWriter<File>,
+}
+
+impl Tracer {
+    pub fn create(path: &Path) -> Result<Self> {
+        let file = File::create(path)
+            .with_context(|| format!("Failed to create trace file '{}'", path.display()))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            writer: BufWriter::new(file),
+        })
+    }
+
+    // Trace the instruction at the PC, before `Emulator::cycle` executes it.
+    pub fn record(&mut self, frame: usize, emu: &Emulator, symbols: &SymbolTable) -> Result<()> {
+        let registers = emu.get_registers();
+        let ram = emu.get_ram();
+        let pc = registers.pc as usize;
+        let opcode = match (ram.get(pc), ram.get(pc + 1)) {
+            (Some(&high), Some(&low)) => u16::from_be_bytes([high, low]),
+            _ => 0,
+        };
+        let v: Vec<String> = registers.v.iter().map(|v| format!("{v:02X}")).collect();
+        writeln!(
+            self.writer,
+            "{frame:<5} {:<14} {opcode:04X}  {:<20}  I={:03X} V={}",
+            symbols.describe(registers.pc),
+            symbols.instruction(Instruction::decode(opcode)).to_string(),
+            registers.i,
+            v.join(" ")
+        )
+        .with_context(|| format!("Failed to write trace file '{}'", self.path.display()))
+    }
+
+    pub fn finish(mut self) -> Result<()> {
+        self.writer
+            .flush()
+            .with_context(|| format!("Failed to write trace file '{}'", self.path.display()))
+    }
+}
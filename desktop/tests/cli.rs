@@ -0,0 +1,493 @@
+// Runs the `chip8-emu` subcommands that don't need a window.
+use std::env;
+use std::fs;
+use std::process::{Command, Output};
+
+const IBM_LOGO: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../test_roms/IBM Logo.ch8");
+
+fn chip8_emu(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_chip8-emu"))
+        .args(args)
+        .output()
+        .expect("Failed to run chip8-emu")
+}
+
+fn stdout(output: &Output) -> String {
+    assert!(
+        output.status.success(),
+        "chip8-emu failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+// Helper function to pick the value of a `Label: value` line.
+fn field(text: &str, label: &str) -> String {
+    text.lines()
+        .find_map(|line| line.strip_prefix(label))
+        .map(|value| value.trim().to_string())
+        .unwrap_or_else(|| panic!("No '{label}' in output:\n{text}"))
+}
+
+#[test]
+fn disasm_and_info_describe_the_rom() {
+    let listing = stdout(&chip8_emu(&["disasm", IBM_LOGO, "-n", "2"]));
+    assert_eq!(listing, "200  00E0  CLS\n202  A22A  LD I, 0x22A\n");
+
+    // Without a count the listing stops at the end of the ROM.
+    let listing = stdout(&chip8_emu(&["disasm", IBM_LOGO]));
+    assert_eq!(listing.lines().count(), 133_usize.div_ceil(2));
+
+    // With analysis the sprite data after the final jump is listed as data.
+    let listing = stdout(&chip8_emu(&[
+        "disasm", IBM_LOGO, "-a", "--start", "0x228", "-n", "2",
+    ]));
+    assert_eq!(listing, "228  1228  JP 0x228\n22A  FF00  DW 0xFF00\n");
+
+    // Analysis only covers the ROM, so an earlier start is moved up to it.
+    let listing = stdout(&chip8_emu(&[
+        "disasm", IBM_LOGO, "-a", "--start", "0x100", "-n", "1",
+    ]));
+    assert_eq!(listing, "200  00E0  CLS\n");
+
+    let cfg = stdout(&chip8_emu(&["cfg", IBM_LOGO]));
+    assert_eq!(field(&cfg, "Blocks:"), "2");
+    assert!(cfg.contains("22A-284  (91 bytes)"));
+
+    let info = stdout(&chip8_emu(&["info", IBM_LOGO]));
+    assert_eq!(field(&info, "Size:"), "133 bytes");
+    assert_eq!(field(&info, "Platform:"), "CHIP-8");
+    assert_eq!(
+        field(&info, "Quirks:"),
+        "shift-vy on, increment-i on, vf-reset on, jump-vx off, wrap-sprites off"
+    );
+}
+
+#[test]
+fn cfg_rejects_roms_that_dont_fit_in_memory() {
+    let rom = env::temp_dir().join(format!("chip8-cli-test-{}-large.ch8", std::process::id()));
+    fs::write(&rom, vec![0xFF; 70_000]).unwrap();
+    let output = chip8_emu(&["cfg", rom.to_str().unwrap()]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Failed to load ROM"), "{stderr}");
+    fs::remove_file(rom).unwrap();
+}
+
+#[test]
+fn test_checks_the_headless_display_hash() {
+    let headless = stdout(&chip8_emu(&["headless", IBM_LOGO, "--frames", "30"]));
+    assert!(headless.contains('#'));
+    let hash = field(&headless, "Display SHA-1:");
+
+    stdout(&chip8_emu(&[
+        "test", IBM_LOGO, "--frames", "30", "--expect", &hash,
+    ]));
+    let mismatch = chip8_emu(&["test", IBM_LOGO, "--frames", "30", "--expect", "0000"]);
+    assert!(!mismatch.status.success());
+}
+
+#[test]
+fn replay_matches_headless_run() {
+    let info = stdout(&chip8_emu(&["info", IBM_LOGO]));
+    let recording = env::temp_dir().join(format!("chip8-cli-test-{}.rec", std::process::id()));
+    fs::write(
+        &recording,
+        format!(
+            "chip8-recording 1\nrom-sha1 {}\nseed 0\nsteps-per-frame 8\nframes 30\n3 press 5\n9 release 5\n",
+            field(&info, "SHA-1:")
+        ),
+    )
+    .unwrap();
+
+    let replay = stdout(&chip8_emu(&[
+        "replay",
+        IBM_LOGO,
+        recording.to_str().unwrap(),
+    ]));
+    let headless = stdout(&chip8_emu(&["headless", IBM_LOGO, "--frames", "30"]));
+    assert_eq!(replay, headless);
+
+    let other_rom = concat!(env!("CARGO_MANIFEST_DIR"), "/../test_roms/Bowling.ch8");
+    let mismatch = chip8_emu(&["replay", other_rom, recording.to_str().unwrap()]);
+    assert!(!mismatch.status.success());
+    fs::remove_file(recording).unwrap();
+}
+
+#[test]
+fn print_config_merges_file_sections_and_flags() {
+    let config = env::temp_dir().join(format!("chip8-cli-test-{}.toml", std::process::id()));
+    fs::write(
+        &config,
+        r##"
+[default]
+cpu-frequency = 700
+foreground = "#33FF66"
+
+[rom."IBM Logo.ch8"]
+platform = "superchip"
+keys = { 1 = "ArrowUp" }
+
+[rom.112dab1eec8627329152b26d29c40fa2c5757c5e]
+quirks = { vf-reset = true }
+"##,
+    )
+    .unwrap();
+    let config_path = config.to_str().unwrap();
+
+    let effective = stdout(&chip8_emu(&[
+        IBM_LOGO,
+        "--config",
+        config_path,
+        "--cpu-frequency",
+        "900",
+        "--print-config",
+    ]));
+    let lines: Vec<&str> = effective.lines().collect();
+    for expected in [
+        "cpu-frequency = 900",
+        "steps-per-frame = 15",
+        "platform = \"superchip\"",
+        "foreground = \"#33FF66\"",
+        "vf-reset = true",
+        "jump-vx = true",
+        "1 = \"ArrowUp\"",
+        "2 = \"Digit2\"",
+    ] {
+        assert!(
+            lines.contains(&expected),
+            "'{expected}' missing from:\n{effective}"
+        );
+    }
+
+    let invalid = chip8_emu(&[
+        IBM_LOGO,
+        "-c",
+        config_path,
+        "--foreground",
+        "red",
+        "--print-config",
+    ]);
+    assert!(!invalid.status.success());
+    fs::remove_file(config).unwrap();
+}
+
+#[test]
+fn rom_analysis_supplies_platform() {
+    let pong = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../test_roms/Pong (1 player).ch8"
+    );
+    let effective = stdout(&chip8_emu(&[pong, "-c", "/dev/null", "--print-config"]));
+    assert!(effective.contains("# from: ROM analysis: CHIP-8"));
+    assert!(effective.lines().any(|line| line == "platform = \"chip8\""));
+    assert!(effective.lines().any(|line| line == "steps-per-frame = 8"));
+}
+
+#[test]
+fn coverage_report_marks_code_and_data_in_disasm() {
+    let report = env::temp_dir().join(format!("chip8-cli-test-{}.cov", std::process::id()));
+    let report_path = report.to_str().unwrap();
+    let _ = fs::remove_file(&report);
+
+    stdout(&chip8_emu(&[
+        "headless",
+        IBM_LOGO,
+        "--frames",
+        "30",
+        "--coverage",
+        report_path,
+    ]));
+    let text = fs::read_to_string(&report).unwrap();
+    assert!(text.lines().any(|line| line == "200-229 x--"));
+    assert!(text.lines().any(|line| line == "22A-283 -r-"));
+
+    let listing = stdout(&chip8_emu(&[
+        "disasm",
+        IBM_LOGO,
+        "--coverage",
+        report_path,
+        "--start",
+        "0x228",
+        "-n",
+        "2",
+    ]));
+    assert_eq!(
+        listing,
+        "228  1228  x--  JP 0x228\n22A  FF00  -r-  DW 0xFF00\n"
+    );
+
+    let other_rom = concat!(env!("CARGO_MANIFEST_DIR"), "/../test_roms/Bowling.ch8");
+    let mismatch = chip8_emu(&["disasm", other_rom, "--coverage", report_path]);
+    assert!(!mismatch.status.success());
+    fs::remove_file(report).unwrap();
+}
+
+#[test]
+fn profile_exports_flat_and_folded_stacks() {
+    let flat = stdout(&chip8_emu(&[
+        "profile", IBM_LOGO, "--frames", "30", "-n", "1",
+    ]));
+    assert_eq!(field(&flat, "Instructions:"), "240");
+    // Most of the time is spent in the final `JP 0x228` loop.
+    assert!(
+        flat.lines()
+            .any(|line| line.starts_with("228") && line.ends_with("JP 0x228"))
+    );
+
+    let folded = stdout(&chip8_emu(&[
+        "profile", IBM_LOGO, "--frames", "30", "--format", "folded",
+    ]));
+    assert_eq!(folded, "0x200 240\n");
+}
+
+#[test]
+fn symbols_label_disasm_profiles_and_traces() {
+    let symbols = env::temp_dir().join(format!("chip8-cli-test-{}.sym", std::process::id()));
+    let trace = env::temp_dir().join(format!("chip8-cli-test-{}.trace", std::process::id()));
+    fs::write(&symbols, "main 0x200\nhalt 0x228\nlogo 0x22A\n").unwrap();
+    let symbols_path = symbols.to_str().unwrap();
+    let trace_path = trace.to_str().unwrap();
+
+    let listing = stdout(&chip8_emu(&[
+        "disasm",
+        IBM_LOGO,
+        "--symbols",
+        symbols_path,
+        "-n",
+        "2",
+    ]));
+    assert_eq!(listing, "main:\n200  00E0  CLS\n202  A22A  LD I, logo\n");
+
+    let folded = stdout(&chip8_emu(&[
+        "profile",
+        IBM_LOGO,
+        "--frames",
+        "30",
+        "--format",
+        "folded",
+        "--symbols",
+        symbols_path,
+    ]));
+    assert_eq!(folded, "main 240\n");
+
+    stdout(&chip8_emu(&[
+        "headless",
+        IBM_LOGO,
+        "--frames",
+        "1",
+        "--trace",
+        trace_path,
+        "--symbols",
+        symbols_path,
+    ]));
+    let text = fs::read_to_string(&trace).unwrap();
+    assert_eq!(text.lines().count(), 8);
+    let second = text.lines().nth(1).unwrap();
+    assert!(second.starts_with("0     main+0x2"), "{second}");
+    assert!(second.contains("A22A  LD I, logo"), "{second}");
+
+    fs::remove_file(symbols).unwrap();
+    fs::remove_file(trace).unwrap();
+}
+
+#[test]
+fn headless_saves_a_screenshot() {
+    let png = env::temp_dir().join(format!("chip8-cli-test-{}.png", std::process::id()));
+    stdout(&chip8_emu(&[
+        "headless",
+        IBM_LOGO,
+        "--frames",
+        "30",
+        "--screenshot-after",
+        "20",
+        "--screenshot",
+        png.to_str().unwrap(),
+        "--scale",
+        "4",
+    ]));
+
+    // PNG signature, then the IHDR chunk with the width and height.
+    let data = fs::read(&png).unwrap();
+    assert_eq!(&data[..8], b"\x89PNG\r\n\x1a\n");
+    assert_eq!(&data[12..16], b"IHDR");
+    assert_eq!(data[16..20], 256_u32.to_be_bytes());
+    assert_eq!(data[20..24], 128_u32.to_be_bytes());
+    fs::remove_file(png).unwrap();
+
+    let output = chip8_emu(&[
+        "headless",
+        IBM_LOGO,
+        "--frames",
+        "10",
+        "--screenshot-after",
+        "20",
+    ]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn headless_records_clips_with_audio() {
+    let base = env::temp_dir().join(format!("chip8-cli-test-{}-clip", std::process::id()));
+    let (gif, y4m, wav) = (
+        base.with_extension("gif"),
+        base.with_extension("y4m"),
+        base.with_extension("wav"),
+    );
+    for clip in [&gif, &y4m] {
+        stdout(&chip8_emu(&[
+            "headless",
+            IBM_LOGO,
+            "--frames",
+            "30",
+            "--clip",
+            clip.to_str().unwrap(),
+            "--scale",
+            "2",
+        ]));
+    }
+
+    let data = fs::read(&gif).unwrap();
+    assert_eq!(&data[..6], b"GIF89a");
+    assert_eq!(data[6..10], [128, 0, 64, 0]);
+
+    // A header, then 30 frames of 4:4:4 planes.
+    let data = fs::read(&y4m).unwrap();
+    let header = b"YUV4MPEG2 W128 H64 F60:1 Ip A1:1 C444\n";
+    assert_eq!(&data[..header.len()], header);
+    assert_eq!(data.len(), header.len() + 30 * (6 + 128 * 64 * 3));
+
+    // 30 frames at 60 Hz is half a second of 16-bit samples.
+    let data = fs::read(&wav).unwrap();
+    assert_eq!(&data[..4], b"RIFF");
+    assert_eq!(data.len(), 44 + 44_100 / 2 * 2);
+
+    for path in [gif, y4m, wav] {
+        fs::remove_file(path).unwrap();
+    }
+}
+
+#[test]
+fn test_suite_prints_a_matrix_per_platform() {
+    let roms = concat!(env!("CARGO_MANIFEST_DIR"), "/../test_roms");
+    let matrix = stdout(&chip8_emu(&["test-suite", roms]));
+    let ibm = matrix
+        .lines()
+        .find(|line| line.starts_with("IBM Logo"))
+        .unwrap();
+    assert!(ibm.ends_with("pass       pass       pass"), "{matrix}");
+    // The quirks self-test picks each platform from its menu and expects that platform's quirks.
+    let quirks: Vec<&str> = matrix
+        .lines()
+        .filter(|line| line.starts_with("Self-Test Quirks"))
+        .collect();
+    assert_eq!(quirks.len(), 5, "{matrix}");
+    for line in quirks {
+        assert!(line.ends_with("pass       pass       pass"), "{matrix}");
+    }
+
+    // A result screen without a known hash fails, and `--hashes` lists it.
+    let suite = env::temp_dir().join(format!("chip8-cli-test-{}.toml", std::process::id()));
+    fs::write(
+        &suite,
+        "[[rom]]\nname = \"Pong\"\nsha1 = \"607c4f7f4e4dce9f99d96b3182bfe7e88bb090ee\"\n\n\
+         [[rom.check]]\nname = \"score\"\nregion = [0, 0, 32, 8]\n",
+    )
+    .unwrap();
+    let output = chip8_emu(&[
+        "test-suite",
+        roms,
+        "--suite",
+        suite.to_str().unwrap(),
+        "--hashes",
+    ]);
+    assert!(!output.status.success());
+    let text = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        text.lines()
+            .any(|line| line.split_whitespace().take(2).eq(["Pong", "score"])),
+        "{text}"
+    );
+    assert!(text.contains("Pong / score / chip8: "), "{text}");
+    fs::remove_file(suite).unwrap();
+}
+
+#[test]
+fn headless_prints_a_crash_report() {
+    // 6A2A -> VA = 0x2A, 00EE -> return with an empty stack.
+    let rom = env::temp_dir().join(format!("chip8-cli-test-{}-crash.ch8", std::process::id()));
+    fs::write(&rom, [0x6A, 0x2A, 0x00, 0xEE]).unwrap();
+    let output = chip8_emu(&["headless", rom.to_str().unwrap(), "--frames", "10"]);
+    assert!(!output.status.success());
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("emulator::cpu::return_with_empty_stack"),
+        "{stderr}"
+    );
+    assert!(
+        stderr.contains("00EE at 0x202 returns with an empty stack"),
+        "{stderr}"
+    );
+    assert!(stderr.contains("202  00EE  RET"), "{stderr}");
+    assert!(stderr.contains("failed here"), "{stderr}");
+    assert!(stderr.contains("VA=2A"), "{stderr}");
+    assert!(stderr.contains("Emulator crashed in frame 0"), "{stderr}");
+    fs::remove_file(rom).unwrap();
+}
+
+#[test]
+fn on_error_skips_and_counts_errors() {
+    // FFFF -> unknown opcode, 1200 -> jump back to it.
+    let rom = env::temp_dir().join(format!("chip8-cli-test-{}-skip.ch8", std::process::id()));
+    fs::write(&rom, [0xFF, 0xFF, 0x12, 0x00]).unwrap();
+    let rom_path = rom.to_str().unwrap();
+    let args = ["headless", rom_path, "--frames", "10", "-s", "8"];
+    assert!(!chip8_emu(&args).status.success());
+
+    let output = chip8_emu(&[&args[..], &["--on-error", "unknown-opcode=skip"]].concat());
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("Skipped 40 errors (unknown-opcode 40)"),
+        "{stderr}"
+    );
+
+    let output = chip8_emu(&[&args[..], &["--on-error", "opcode=skip"]].concat());
+    assert!(!output.status.success());
+    fs::remove_file(rom).unwrap();
+}
+
+#[test]
+fn memory_map_and_write_protection() {
+    let output = chip8_emu(&["memory-map", IBM_LOGO]);
+    assert!(output.status.success());
+    let map = stdout(&output);
+    assert!(map.contains("050-1FF  reserved   432 bytes"), "{map}");
+    assert!(map.contains("200-284  program    133 bytes"), "{map}");
+
+    // A100 -> I = 0x100, F033 -> BCD of V0 at I.
+    let rom = env::temp_dir().join(format!("chip8-cli-test-{}-protect.ch8", std::process::id()));
+    fs::write(&rom, [0xA1, 0x00, 0xF0, 0x33]).unwrap();
+    let rom_path = rom.to_str().unwrap();
+    let output = chip8_emu(&["headless", rom_path, "--frames", "1", "-s", "2"]);
+    assert!(output.status.success());
+
+    let output = chip8_emu(&[
+        "headless",
+        rom_path,
+        "--frames",
+        "1",
+        "-s",
+        "2",
+        "--write-protection",
+        "error",
+    ]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("Write to reserved memory at address 0x100"),
+        "{stderr}"
+    );
+    fs::remove_file(rom).unwrap();
+}
@@ -0,0 +1,92 @@
+use alloc::vec::Vec;
+
+use crate::cfg::ControlFlowGraph;
+use crate::disasm::{DisassembledLine, Instruction};
+use crate::quirks::{Platform, Quirks};
+
+// Best guess at what a ROM needs, for ROMs without known metadata.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RomAnalysis {
+    pub platform: Platform,
+    // The platform's quirks, adjusted for how the ROM uses the affected instructions.
+    pub quirks: Quirks,
+    // The reachable extension instructions the platform was guessed from.
+    pub evidence: Vec<DisassembledLine>,
+}
+
+// The instructions reachable from the program start (see `ControlFlowGraph`), sorted by address.
+// Following the control flow keeps sprite data mixed in with the code from being mistaken for
+// instructions.
+pub fn reachable_instructions(rom: &[u8]) -> Vec<DisassembledLine> {
+    ControlFlowGraph::build(rom)
+        .instructions()
+        .copied()
+        .collect()
+}
+
+// Guess the platform a ROM targets from the extension instructions in its reachable code
+// (e.g. 00FF / DXY0 for SUPER-CHIP, F000 NNNN / FN01 for XO-CHIP), then guess the quirks
+// from how it uses the instructions they affect.
+pub fn analyze_rom(rom: &[u8]) -> RomAnalysis {
+    let lines = reachable_instructions(rom);
+
+    let xo_chip: Vec<_> = lines
+        .iter()
+        .filter(|line| line.instruction.is_xo_chip())
+        .copied()
+        .collect();
+    // DXY0 draws nothing on CHIP-8 but a 16x16 sprite on SUPER-CHIP.
+    let super_chip: Vec<_> = lines
+        .iter()
+        .filter(|line| {
+            line.instruction.is_super_chip()
+                || matches!(line.instruction, Instruction::Drw(_, _, 0))
+        })
+        .copied()
+        .collect();
+    let (platform, evidence) = if !xo_chip.is_empty() {
+        (Platform::XoChip, xo_chip)
+    } else if !super_chip.is_empty() {
+        (Platform::SuperChip, super_chip)
+    } else {
+        (Platform::Chip8, Vec::new())
+    };
+
+    let mut quirks = platform.quirks();
+
+    // Shifting VY into VX only makes sense if VY holds something else (8XY6 with Y != X, Y != 0).
+    // Shifts like 8X06 don't mean to read V0, so they are written for in-place shifts.
+    let shifts = lines.iter().filter_map(|line| match line.instruction {
+        Instruction::Shr(x, y) | Instruction::Shl(x, y) => Some((x, y)),
+        _ => None,
+    });
+    let (mut from_vy, mut in_place) = (false, false);
+    for (x, y) in shifts {
+        from_vy |= y != x && y != 0;
+        in_place |= y != x && y == 0;
+    }
+    if from_vy != in_place {
+        quirks.shift_vy = from_vy;
+    }
+
+    // Back to back FX55 / FX65 without setting I in between rely on I moving past the registers.
+    if lines.windows(2).any(|pair| {
+        pair[1].addr == pair[0].addr + 2
+            && matches!(
+                pair[0].instruction,
+                Instruction::Store(_) | Instruction::Load(_)
+            )
+            && matches!(
+                pair[1].instruction,
+                Instruction::Store(_) | Instruction::Load(_)
+            )
+    }) {
+        quirks.increment_i = true;
+    }
+
+    RomAnalysis {
+        platform,
+        quirks,
+        evidence,
+    }
+}
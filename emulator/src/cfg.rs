@@ -0,0 +1,368 @@
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{self, Write as _};
+use core::ops::Range;
+
+use crate::constants::{MEMORY_SIZE, PROGRAM_START};
+use crate::disasm::{DisassembledLine, Instruction};
+
+// How control gets from one instruction (or block) to another.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgeKind {
+    // The following instruction (also the return site after a call).
+    Next,
+    // 1NNN
+    Jump,
+    // 2NNN
+    Call,
+    // The instruction after next, taken by the 3XNN / 4XNN / 5XY0 / 9XY0 / EX9E / EXA1 skips.
+    Skip,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Edge {
+    pub kind: EdgeKind,
+    pub target: u16,
+}
+
+// A run of instructions that is only entered at the top and only left at the bottom.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BasicBlock {
+    pub start: u16,
+    pub lines: Vec<DisassembledLine>,
+    pub edges: Vec<Edge>,
+}
+
+impl BasicBlock {
+    // Address after the last instruction of the block.
+    pub fn end(&self) -> u16 {
+        self.lines
+            .last()
+            .map_or(self.start, |line| line.addr + line.instruction.size())
+    }
+
+    fn last_instruction(&self) -> Option<Instruction> {
+        self.lines.last().map(|line| line.instruction)
+    }
+}
+
+// Things in the reachable code that are likely bugs, or that static analysis can't see through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CfgWarning {
+    // BNNN: the target depends on a register, so the code it leads to is unknown.
+    ComputedJump { addr: u16 },
+    // A jump, call, skip or fall through that leaves the ROM.
+    TargetOutsideRom { addr: u16, target: u16 },
+    // 0NNN: a call to a machine code routine of the original interpreter.
+    MachineCodeCall { addr: u16, target: u16 },
+    UnknownOpcode { addr: u16, opcode: u16 },
+    // Two reachable instructions share bytes (e.g. a jump into the middle of an instruction).
+    OverlappingInstructions { addr: u16, other: u16 },
+    // A subroutine that can't reach a 00EE.
+    CallWithoutReturn { addr: u16, target: u16 },
+    // A 00EE reachable from the program start without a call.
+    ReturnWithoutCall { addr: u16 },
+}
+
+impl fmt::Display for CfgWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::ComputedJump { addr } => {
+                write!(f, "{addr:#05X}: computed jump (BNNN), target unknown")
+            }
+            Self::TargetOutsideRom { addr, target } => {
+                write!(
+                    f,
+                    "{addr:#05X}: control flow leaves the ROM (to {target:#05X})"
+                )
+            }
+            Self::MachineCodeCall { addr, target } => {
+                write!(f, "{addr:#05X}: call to machine code routine {target:#05X}")
+            }
+            Self::UnknownOpcode { addr, opcode } => {
+                write!(f, "{addr:#05X}: unknown opcode {opcode:04X}")
+            }
+            Self::OverlappingInstructions { addr, other } => {
+                write!(
+                    f,
+                    "{addr:#05X}: instruction overlaps the one at {other:#05X}"
+                )
+            }
+            Self::CallWithoutReturn { addr, target } => {
+                write!(f, "{addr:#05X}: subroutine {target:#05X} never returns")
+            }
+            Self::ReturnWithoutCall { addr } => {
+                write!(f, "{addr:#05X}: return without a call")
+            }
+        }
+    }
+}
+
+// Static control flow graph of a ROM, built by following jumps, calls, skips and returns from
+// the program start. Bytes that are never reached are data (sprites, tables) or dead code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ControlFlowGraph {
+    pub blocks: BTreeMap<u16, BasicBlock>,
+    pub warnings: Vec<CfgWarning>,
+    // Whether each ROM byte belongs to a reachable instruction.
+    code: Vec<bool>,
+}
+
+impl ControlFlowGraph {
+    // Bytes past the end of memory can't be loaded, so they are ignored.
+    pub fn build(rom: &[u8]) -> Self {
+        let rom = &rom[..rom.len().min(MEMORY_SIZE - PROGRAM_START as usize)];
+        let in_rom =
+            |addr: u16| addr >= PROGRAM_START && ((addr - PROGRAM_START) as usize) + 1 < rom.len();
+
+        let mut instructions: BTreeMap<u16, (DisassembledLine, Vec<Edge>)> = BTreeMap::new();
+        let mut leaders = BTreeSet::from([PROGRAM_START]);
+        let mut warnings = Vec::new();
+        let mut pending = Vec::new();
+        if in_rom(PROGRAM_START) {
+            pending.push(PROGRAM_START);
+        }
+
+        while let Some(addr) = pending.pop() {
+            if instructions.contains_key(&addr) {
+                continue;
+            }
+            let offset = (addr - PROGRAM_START) as usize;
+            let opcode = u16::from_be_bytes([rom[offset], rom[offset + 1]]);
+            let instruction = Instruction::decode(opcode);
+            let next = addr.wrapping_add(instruction.size());
+
+            let successors: &[(EdgeKind, u16)] = match instruction {
+                Instruction::Jp(nnn) => &[(EdgeKind::Jump, nnn)],
+                Instruction::Call(nnn) => &[(EdgeKind::Call, nnn), (EdgeKind::Next, next)],
+                Instruction::SeByte(..)
+                | Instruction::SneByte(..)
+                | Instruction::SeReg(..)
+                | Instruction::SneReg(..)
+                | Instruction::Skp(_)
+                | Instruction::Sknp(_) => &[
+                    (EdgeKind::Next, next),
+                    (EdgeKind::Skip, next.wrapping_add(2)),
+                ],
+                Instruction::Ret | Instruction::Exit => &[],
+                Instruction::JpV0(_) => {
+                    warnings.push(CfgWarning::ComputedJump { addr });
+                    &[]
+                }
+                Instruction::Unknown(opcode) => {
+                    warnings.push(CfgWarning::UnknownOpcode { addr, opcode });
+                    &[]
+                }
+                Instruction::Sys(target) => {
+                    warnings.push(CfgWarning::MachineCodeCall { addr, target });
+                    &[(EdgeKind::Next, next)]
+                }
+                _ => &[(EdgeKind::Next, next)],
+            };
+
+            let mut edges = Vec::with_capacity(successors.len());
+            for &(kind, target) in successors {
+                if !in_rom(target) {
+                    warnings.push(CfgWarning::TargetOutsideRom { addr, target });
+                    continue;
+                }
+                edges.push(Edge { kind, target });
+                pending.push(target);
+            }
+            // Anything that isn't plain fall through starts new blocks.
+            if successors.len() != 1 || successors[0].0 != EdgeKind::Next {
+                leaders.extend(edges.iter().map(|edge| edge.target));
+            }
+
+            let line = DisassembledLine {
+                addr,
+                opcode,
+                instruction,
+            };
+            instructions.insert(addr, (line, edges));
+        }
+
+        let mut blocks = BTreeMap::new();
+        for &leader in &leaders {
+            let Some((line, edges)) = instructions.get(&leader) else {
+                continue;
+            };
+            let mut block = BasicBlock {
+                start: leader,
+                lines: alloc::vec![*line],
+                edges: edges.clone(),
+            };
+            while let [
+                Edge {
+                    kind: EdgeKind::Next,
+                    target,
+                },
+            ] = block.edges[..]
+                && !leaders.contains(&target)
+                && let Some((line, edges)) = instructions.get(&target)
+            {
+                block.lines.push(*line);
+                block.edges = edges.clone();
+            }
+            blocks.insert(leader, block);
+        }
+
+        let mut code = alloc::vec![false; rom.len()];
+        let mut previous: Option<(u16, u16)> = None;
+        for (&addr, (line, _)) in &instructions {
+            let end = addr + line.instruction.size();
+            if let Some((other, other_end)) = previous
+                && addr < other_end
+            {
+                warnings.push(CfgWarning::OverlappingInstructions { addr, other });
+            }
+            previous = Some((addr, end));
+
+            let offset = (addr - PROGRAM_START) as usize;
+            let size = (line.instruction.size() as usize).min(rom.len() - offset);
+            code[offset..offset + size].fill(true);
+        }
+
+        let mut cfg = Self {
+            blocks,
+            warnings,
+            code,
+        };
+        cfg.check_subroutines();
+        cfg
+    }
+
+    // Flag subroutines that can't return, and returns that can be reached without a call.
+    fn check_subroutines(&mut self) {
+        let mut calls = BTreeMap::new();
+        for block in self.blocks.values() {
+            for edge in &block.edges {
+                if edge.kind == EdgeKind::Call {
+                    let call = block.end() - 2;
+                    calls.entry(edge.target).or_insert(call);
+                }
+            }
+        }
+
+        for (target, addr) in calls {
+            let exits = self.exits(target);
+            if exits.returns.is_empty() && !exits.unknown {
+                self.warnings
+                    .push(CfgWarning::CallWithoutReturn { addr, target });
+            }
+        }
+        for addr in self.exits(PROGRAM_START).returns {
+            self.warnings.push(CfgWarning::ReturnWithoutCall { addr });
+        }
+    }
+
+    // Walk the code from `entry` without entering calls (assuming they return).
+    fn exits(&self, entry: u16) -> Exits {
+        let mut exits = Exits::default();
+        let mut visited = BTreeSet::new();
+        let mut pending = alloc::vec![entry];
+        while let Some(start) = pending.pop() {
+            let Some(block) = self.blocks.get(&start) else {
+                continue;
+            };
+            if !visited.insert(start) {
+                continue;
+            }
+            match block.last_instruction() {
+                Some(Instruction::Ret) => exits.returns.push(block.end() - 2),
+                // An exit or a computed jump may well be how the subroutine ends.
+                Some(Instruction::Exit | Instruction::JpV0(_) | Instruction::Unknown(_)) => {
+                    exits.unknown = true
+                }
+                _ => {}
+            }
+            pending.extend(
+                block
+                    .edges
+                    .iter()
+                    .filter(|edge| edge.kind != EdgeKind::Call)
+                    .map(|edge| edge.target),
+            );
+        }
+        exits
+    }
+
+    // The reachable instructions, by address.
+    pub fn instructions(&self) -> impl Iterator<Item = &DisassembledLine> {
+        self.blocks.values().flat_map(|block| &block.lines)
+    }
+
+    // Whether the byte at `addr` is part of a reachable instruction.
+    pub fn is_code(&self, addr: u16) -> bool {
+        addr.checked_sub(PROGRAM_START)
+            .and_then(|offset| self.code.get(offset as usize))
+            .is_some_and(|&code| code)
+    }
+
+    // Address ranges of the ROM that are never reached: data, or dead code.
+    pub fn unreachable_ranges(&self) -> Vec<Range<u16>> {
+        let mut ranges = Vec::new();
+        let mut start = None;
+        for (offset, &code) in self.code.iter().enumerate() {
+            let addr = PROGRAM_START + offset as u16;
+            match (code, start) {
+                (false, None) => start = Some(addr),
+                (true, Some(range_start)) => {
+                    ranges.push(range_start..addr);
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+        if let Some(range_start) = start {
+            ranges.push(range_start..PROGRAM_START + self.code.len() as u16);
+        }
+        ranges
+    }
+
+    // The graph in Graphviz DOT format, one node per basic block. Computed jumps lead to a `?`
+    // node.
+    pub fn to_dot(&self) -> String {
+        let mut dot =
+            String::from("digraph cfg {\n    node [shape=box, fontname=\"monospace\"];\n");
+        for block in self.blocks.values() {
+            let mut label = String::new();
+            for line in &block.lines {
+                let _ = write!(label, "{:03X}  {}\\l", line.addr, line.instruction);
+            }
+            let _ = writeln!(dot, "    b{:03X} [label=\"{label}\"];", block.start);
+
+            for edge in &block.edges {
+                let style = match edge.kind {
+                    EdgeKind::Next => "",
+                    EdgeKind::Jump => " [label=\"jump\"]",
+                    EdgeKind::Call => " [label=\"call\", style=dashed]",
+                    EdgeKind::Skip => " [label=\"skip\"]",
+                };
+                let _ = writeln!(
+                    dot,
+                    "    b{:03X} -> b{:03X}{style};",
+                    block.start, edge.target
+                );
+            }
+            if let Some(Instruction::JpV0(_)) = block.last_instruction() {
+                let _ = writeln!(
+                    dot,
+                    "    unknown{0:03X} [label=\"?\", shape=circle];\n    b{0:03X} -> unknown{0:03X} [style=dotted];",
+                    block.start
+                );
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+#[derive(Default)]
+struct Exits {
+    // Addresses of the reachable returns (00EE).
+    returns: Vec<u16>,
+    // Whether the code may leave some other way static analysis can't follow.
+    unknown: bool,
+}
@@ -26,8 +26,20 @@ pub const FONTSET: [u8; FONTSET_SIZE] = [
 pub const DISPLAY_WIDTH: usize = 64;
 pub const DISPLAY_HEIGHT: usize = 32;
 
+// Save State Constants
+// Header (magic + version) + CPU + RAM + stack + program length + display (1 bit per pixel) + keypad
+// (1 bit per key).
+pub const STATE_SIZE: usize = 5
+    + (2 + 2 + NUM_REGS + 2 + 1 + 1)
+    + MEMORY_SIZE
+    + STACK_SIZE * 2
+    + 2
+    + DISPLAY_WIDTH * DISPLAY_HEIGHT / 8
+    + NUM_KEYS / 8;
+
 // Keypad Constants
 pub const NUM_KEYS: usize = 16;
 
 // CPU Constants
 pub const NUM_REGS: usize = 16;
+pub const DEFAULT_SEED: u64 = 0xC8; // Random number generator seed used when no entropy source is available (no_std).
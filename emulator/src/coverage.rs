@@ -0,0 +1,114 @@
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::constants::MEMORY_SIZE;
+
+// How a RAM address was used by the running program.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Access {
+    // Fetched as part of an opcode.
+    pub executed: bool,
+    // Read as data (DXYN sprites, FX65).
+    pub read: bool,
+    // Written by the program (FX33, FX55).
+    pub written: bool,
+}
+
+impl Access {
+    pub fn is_empty(&self) -> bool {
+        !self.executed && !self.read && !self.written
+    }
+
+    // Combine the accesses of two runs.
+    pub fn union(self, other: Access) -> Access {
+        Access {
+            executed: self.executed || other.executed,
+            read: self.read || other.read,
+            written: self.written || other.written,
+        }
+    }
+}
+
+// Which RAM addresses the program executed, read or written while coverage was enabled.
+// Static analysis can't follow computed jumps or tell sprite data from code that is never
+// reached, but a recorded session can.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Coverage {
+    access: Vec<Access>,
+}
+
+impl Default for Coverage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Coverage {
+    pub fn new() -> Self {
+        Self {
+            access: alloc::vec![Access::default(); MEMORY_SIZE],
+        }
+    }
+
+    // How the byte at `addr` was used (nothing for addresses outside RAM).
+    pub fn get(&self, addr: u16) -> Access {
+        self.access.get(addr as usize).copied().unwrap_or_default()
+    }
+
+    // Add accesses, e.g. when merging a coverage report from an earlier session.
+    pub fn add(&mut self, addr: u16, access: Access) {
+        if let Some(current) = self.access.get_mut(addr as usize) {
+            *current = current.union(access);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.access.fill(Access::default());
+    }
+
+    // Runs of consecutive addresses with the same (non-empty) accesses.
+    pub fn ranges(&self) -> Vec<(Range<u16>, Access)> {
+        let mut ranges: Vec<(Range<u16>, Access)> = Vec::new();
+        for (addr, &access) in self.access.iter().enumerate() {
+            let addr = addr as u16;
+            if access.is_empty() {
+                continue;
+            }
+            match ranges.last_mut() {
+                Some((range, last)) if range.end == addr && *last == access => range.end += 1,
+                _ => ranges.push((addr..addr + 1, access)),
+            }
+        }
+        ranges
+    }
+
+    pub(crate) fn mark_executed(&mut self, addr: u16) {
+        self.add(
+            addr,
+            Access {
+                executed: true,
+                ..Access::default()
+            },
+        );
+    }
+
+    pub(crate) fn mark_read(&mut self, addr: u16) {
+        self.add(
+            addr,
+            Access {
+                read: true,
+                ..Access::default()
+            },
+        );
+    }
+
+    pub(crate) fn mark_written(&mut self, addr: u16) {
+        self.add(
+            addr,
+            Access {
+                written: true,
+                ..Access::default()
+            },
+        );
+    }
+}
@@ -1,13 +1,27 @@
 use log::info;
+use rand::{Rng, SeedableRng, rngs::SmallRng};
 
 use crate::{
-    constants::{NUM_KEYS, NUM_REGS, PROGRAM_START},
+    constants::{MEMORY_SIZE, NUM_KEYS, NUM_REGS, PROGRAM_START},
     display::Display,
-    error::CpuError,
+    error::{CpuError, StateError},
     input::Keypad,
     memory::Memory,
+    quirks::Quirks,
+    state::{StateReader, StateWriter},
 };
 
+// Snapshot of the CPU registers, timers and pointers, used to inspect or modify CPU state from outside the emulator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Registers {
+    pub pc: u16,           // Program Counter
+    pub sp: u16,           // Stack Pointer
+    pub v: [u8; NUM_REGS], // V Registers (V0 - VF)
+    pub i: u16,            // I Register
+    pub dt: u8,            // Delay Timer
+    pub st: u8,            // Sound Timer
+}
+
 // Holds the state of the CHIP-8 CPU, including registers, timers, and pointers.
 pub struct Cpu {
     pc: u16,               // Program Counter
@@ -16,11 +30,29 @@ pub struct Cpu {
     i_reg: u16,            // I Register (Used for indexing into RAM)
     dt: u8,                // Delay Timer
     st: u8,                // Sound Timer
+    rng: SmallRng,         // Random number generator (Used by CXNN)
+    quirks: Quirks,        // Platform specific behaviors
 }
 
 impl Cpu {
     // Create a new CPU with registers and pointers initialized.
+    // With the `std` feature the random number generator is seeded from the OS, otherwise a fixed seed is used.
     pub fn new() -> Self {
+        #[cfg(feature = "std")]
+        let rng = SmallRng::from_rng(&mut rand::rng());
+        #[cfg(not(feature = "std"))]
+        let rng = SmallRng::seed_from_u64(crate::constants::DEFAULT_SEED);
+
+        Self::with_rng(rng)
+    }
+
+    // Create a new CPU whose random number generator is seeded with the given value.
+    pub fn with_seed(seed: u64) -> Self {
+        Self::with_rng(SmallRng::seed_from_u64(seed))
+    }
+
+    // Helper function to create a CPU around an already seeded random number generator.
+    fn with_rng(rng: SmallRng) -> Self {
         Self {
             pc: PROGRAM_START,
             sp: 0,
@@ -28,6 +60,8 @@ impl Cpu {
             i_reg: 0,
             dt: 0,
             st: 0,
+            rng,
+            quirks: Quirks::default(),
         }
     }
 
@@ -52,11 +86,24 @@ impl Cpu {
         display: &mut Display,
         keypad: &mut Keypad,
     ) -> Result<(), CpuError> {
-        let op = (memory.read_byte(self.pc)? as u16) << 8 | memory.read_byte(self.pc + 1)? as u16;
-        self.pc += 2;
+        let pc = self.pc;
+        let op = memory.fetch_opcode(pc)?;
+        // `fetch_opcode` checked that `pc + 1` is in memory, so this can't overflow.
+        self.pc = pc + 2;
 
         // Execute opcode
-        self.execute(op, memory, display, keypad)
+        self.execute(pc, op, memory, display, keypad)?;
+
+        // Leaving memory (by running off its end, skipping or jumping) is reported here rather than
+        // when fetching the next instruction, so that the error names the instruction responsible.
+        if self.pc as usize >= MEMORY_SIZE {
+            return Err(CpuError::PcOutOfRange {
+                pc,
+                opcode: op,
+                target: self.pc,
+            });
+        }
+        Ok(())
     }
 
     // Decrement the delay and sound timers if they are not zero.
@@ -74,9 +121,89 @@ impl Cpu {
         self.st
     }
 
-    // Decode and execute a single opcode.
+    // Get the quirks currently in effect.
+    pub fn get_quirks(&self) -> Quirks {
+        self.quirks
+    }
+
+    // Change the quirks used when executing opcodes.
+    pub fn set_quirks(&mut self, quirks: Quirks) {
+        self.quirks = quirks;
+    }
+
+    // Get the address of the next instruction.
+    pub fn get_pc(&self) -> u16 {
+        self.pc
+    }
+
+    // After a failing instruction was skipped, make sure the next one can be fetched: a program
+    // counter outside memory (or on its last byte) wraps around to the start.
+    pub fn wrap_pc(&mut self) {
+        let pc = self.pc as usize % MEMORY_SIZE;
+        self.pc = if pc + 1 < MEMORY_SIZE { pc as u16 } else { 0 };
+    }
+
+    // Get a snapshot of the registers, pointers and timers.
+    pub fn get_registers(&self) -> Registers {
+        Registers {
+            pc: self.pc,
+            sp: self.sp,
+            v: self.v_reg,
+            i: self.i_reg,
+            dt: self.dt,
+            st: self.st,
+        }
+    }
+
+    // Overwrite the registers, pointers and timers.
+    pub fn set_registers(&mut self, registers: Registers) {
+        self.pc = registers.pc;
+        self.sp = registers.sp;
+        self.v_reg = registers.v;
+        self.i_reg = registers.i;
+        self.dt = registers.dt;
+        self.st = registers.st;
+    }
+
+    // Write the registers, pointers and timers to a save state.
+    // The random number generator state is not saved.
+    pub fn save_state(&self, writer: &mut StateWriter) {
+        writer.write_u16(self.pc);
+        writer.write_u16(self.sp);
+        writer.write_bytes(&self.v_reg);
+        writer.write_u16(self.i_reg);
+        writer.write_u8(self.dt);
+        writer.write_u8(self.st);
+    }
+
+    // Restore the registers, pointers and timers from a save state.
+    pub fn load_state(&mut self, reader: &mut StateReader) -> Result<(), StateError> {
+        self.pc = reader.read_u16()?;
+        self.sp = reader.read_u16()?;
+        self.v_reg.copy_from_slice(reader.read_bytes(NUM_REGS)?);
+        self.i_reg = reader.read_u16()?;
+        self.dt = reader.read_u8()?;
+        self.st = reader.read_u8()?;
+        Ok(())
+    }
+
+    // The address in I, if the `len` bytes starting there are all in memory.
+    fn index(&self, pc: u16, opcode: u16, len: u16) -> Result<u16, CpuError> {
+        if self.i_reg as usize + len as usize <= MEMORY_SIZE {
+            Ok(self.i_reg)
+        } else {
+            Err(CpuError::IndexOutOfRange {
+                pc,
+                opcode,
+                i: self.i_reg,
+            })
+        }
+    }
+
+    // Decode and execute a single opcode, fetched from `pc`.
     fn execute(
         &mut self,
+        pc: u16,
         op: u16,
         memory: &mut Memory,
         display: &mut Display,
@@ -98,7 +225,10 @@ impl Cpu {
 
             // 00EE -> Return from a subroutine (returns to PC on stack).
             (0, 0, 0xE, 0xE) => {
-                self.sp -= 1;
+                self.sp = self
+                    .sp
+                    .checked_sub(1)
+                    .ok_or(CpuError::ReturnWithEmptyStack { pc, opcode: op })?;
                 self.pc = memory.pop_stack(self.sp as usize)?;
             }
 
@@ -142,9 +272,21 @@ impl Cpu {
             }
 
             (8, _, _, 0) => self.v_reg[n2 as usize] = self.v_reg[n3 as usize], // 8XY0 -> Set VX to the value of VY.
-            (8, _, _, 1) => self.v_reg[n2 as usize] |= self.v_reg[n3 as usize], // 8XY1 -> Set VX to VX bitwise OR VY.
-            (8, _, _, 2) => self.v_reg[n2 as usize] &= self.v_reg[n3 as usize], // 8XY2 -> Set VX to VX bitwise AND VY.
-            (8, _, _, 3) => self.v_reg[n2 as usize] ^= self.v_reg[n3 as usize], // 8XY3 -> Set VX to VX bitwise XOR VY.
+
+            // 8XY1 / 8XY2 / 8XY3 -> Set VX to VX bitwise OR / AND / XOR VY.
+            // The original CHIP-8 also resets VF (`vf_reset` quirk).
+            (8, _, _, 1..=3) => {
+                let x = n2 as usize;
+                let y = n3 as usize;
+                match n4 {
+                    1 => self.v_reg[x] |= self.v_reg[y],
+                    2 => self.v_reg[x] &= self.v_reg[y],
+                    _ => self.v_reg[x] ^= self.v_reg[y],
+                }
+                if self.quirks.vf_reset {
+                    self.v_reg[0xF] = 0;
+                }
+            }
 
             // 8XY4 -> Set VX to VX + VY, set VF to carry.
             (8, _, _, 4) => {
@@ -165,10 +307,11 @@ impl Cpu {
             }
 
             // 8XY6 -> Store VY bitwise shifted right one into VX, set VF to least significant bit prior to shift.
-            // This is the original CHIP-8 implementation. CHIP-48 & SUPER-CHIP both expect just a simple shift on VX.
+            // This is the original CHIP-8 implementation. CHIP-48 & SUPER-CHIP both expect just a simple shift on VX
+            // (`shift_vy` quirk disabled).
             (8, _, _, 6) => {
                 let x = n2 as usize;
-                let y = n3 as usize;
+                let y = if self.quirks.shift_vy { n3 as usize } else { x };
                 let lsb = self.v_reg[y] & 1;
                 self.v_reg[x] = self.v_reg[y] >> 1;
                 self.v_reg[0xF] = lsb;
@@ -187,7 +330,7 @@ impl Cpu {
             // See above note for opcode 8XY6.
             (8, _, _, 0xE) => {
                 let x = n2 as usize;
-                let y = n3 as usize;
+                let y = if self.quirks.shift_vy { n3 as usize } else { x };
                 let msb = (self.v_reg[y] >> 7) & 1;
                 self.v_reg[x] = self.v_reg[y] << 1;
                 self.v_reg[0xF] = msb;
@@ -201,11 +344,16 @@ impl Cpu {
             }
 
             (0xA, _, _, _) => self.i_reg = nnn, // ANNN -> Store address NNN in I.
-            (0xB, _, _, _) => self.pc = nnn + self.v_reg[0] as u16, // BNNN -> Jump to address NNN + V0.
+            // BNNN -> Jump to address NNN + V0.
+            //         CHIP-48 & SUPER-CHIP jump to XNN + VX instead (`jump_vx` quirk).
+            (0xB, _, _, _) => {
+                let offset_reg = if self.quirks.jump_vx { n2 as usize } else { 0 };
+                self.pc = nnn + self.v_reg[offset_reg] as u16;
+            }
 
             // CXNN -> Set VX to a random number bitwise AND NN.
             (0xC, _, _, _) => {
-                let random = rand::random::<u8>();
+                let random = self.rng.random::<u8>();
                 self.v_reg[n2 as usize] = random & nn as u8;
             }
 
@@ -215,8 +363,9 @@ impl Cpu {
             (0xD, _, _, _) => {
                 let x = self.v_reg[n2 as usize] as usize;
                 let y = self.v_reg[n3 as usize] as usize;
-                let sprite = memory.read_bytes(self.i_reg, self.i_reg + n4);
-                let flipped = display.draw_sprite(x, y, &sprite?);
+                let start = self.index(pc, op, n4)?;
+                let sprite = memory.read_bytes(start, start + n4)?;
+                let flipped = display.draw_sprite(x, y, sprite, self.quirks.wrap_sprites);
 
                 // Populate VF register based on whether any pixels were flipped from "on" to "off".
                 self.v_reg[0xF] = flipped as u8;
@@ -273,48 +422,51 @@ impl Cpu {
             //         address specified by I, I + 1, & I + 2.
             (0xF, _, 3, 3) => {
                 // Get value in VX.
-                let vx = self.v_reg[n2 as usize] as f32;
+                let vx = self.v_reg[n2 as usize];
 
-                // Fetch the hundreds digit by dividing by 100 and tossing the decimal.
-                let hundreds = (vx / 100.0).floor() as u8;
+                // Fetch the hundreds digit by dividing by 100 (integer division tosses the remainder).
+                let hundreds = vx / 100;
 
-                // Fetch the tens digit by dividing by 10, tossing the ones digit and the decimal.
-                let tens = ((vx / 10.0) % 10.0).floor() as u8;
+                // Fetch the tens digit by dividing by 10 and tossing the ones digit.
+                let tens = (vx / 10) % 10;
 
                 // Fetch the ones digit by tossing the hundreds and the tens.
-                let ones = (vx % 10.0) as u8;
+                let ones = vx % 10;
 
                 // Store the BCD digits in memory.
-                memory.write_byte(self.i_reg, hundreds)?;
-                memory.write_byte(self.i_reg + 1, tens)?;
-                memory.write_byte(self.i_reg + 2, ones)?;
+                let start = self.index(pc, op, 3)?;
+                memory.store_bytes(start, &[hundreds, tens, ones])?;
             }
 
             // FX55 -> Store values from V0 - VX in memory starting at address specified by I.
+            //         CHIP-48 & SUPER-CHIP leave I unchanged (`increment_i` quirk disabled).
             (0xF, _, 5, 5) => {
                 let x = n2 as usize;
+                // Checked up front, so the loop neither leaves memory nor overflows I.
+                let start = self.index(pc, op, x as u16 + 1)?;
 
-                // Loop from V0 up to and including VX
-                for i in 0..=x {
-                    // Store the value of Vi into RAM at address I
-                    memory.write_byte(self.i_reg, self.v_reg[i])?;
+                // Store V0 up to and including VX at I, I + 1, ...
+                memory.store_bytes(start, &self.v_reg[..=x])?;
 
-                    // Increment I register for the next memory address.
-                    self.i_reg += 1;
+                // I is left pointing past the last register stored.
+                if self.quirks.increment_i {
+                    self.i_reg = start + x as u16 + 1;
                 }
             }
 
             // FX65 -> Load V0 - VX from memory starting at address specified by I.
+            //         See above note for opcode FX55.
             (0xF, _, 6, 5) => {
                 let x = n2 as usize;
+                // Checked up front, so the load neither leaves memory nor overflows I.
+                let start = self.index(pc, op, x as u16 + 1)?;
 
-                // Loop from V0 up to and including VX
-                for i in 0..=x {
-                    // Load the value from RAM at address I into register Vi
-                    self.v_reg[i] = memory.read_byte(self.i_reg)?;
+                // Load V0 up to and including VX from I, I + 1, ...
+                self.v_reg[..=x].copy_from_slice(memory.load_bytes(start, x + 1)?);
 
-                    // Increment I register for the next memory address.
-                    self.i_reg += 1;
+                // I is left pointing past the last register loaded.
+                if self.quirks.increment_i {
+                    self.i_reg = start + x as u16 + 1;
                 }
             }
 
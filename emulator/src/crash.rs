@@ -0,0 +1,110 @@
+use alloc::string::String;
+use core::error::Error;
+use core::fmt::{self, Write as _};
+use core::ops::Range;
+
+use crate::{cpu::Registers, disasm::disassemble, error::CpuError};
+
+// Instructions listed before and after the failing one.
+const CONTEXT_LINES: usize = 4;
+
+// A CPU error together with the state it happened in: the failing instruction, the registers and
+// a disassembly of the code around it. With the `std` feature this is a miette diagnostic that
+// shows the listing with the failing line labelled and the registers as help.
+#[derive(Debug)]
+pub struct Crash {
+    pub error: CpuError,
+    // Address of the failing instruction.
+    pub pc: u16,
+    // The failing instruction, or `None` if `pc` is outside memory.
+    pub opcode: Option<u16>,
+    // Registers after the failure (so `registers.pc` may already be past the instruction).
+    pub registers: Registers,
+    // Disassembly around `pc`, one instruction per line (`addr  opcode  instruction`).
+    pub listing: String,
+    // Byte range of the failing instruction's line in `listing`.
+    pub line: Option<Range<usize>>,
+}
+
+impl Crash {
+    pub(crate) fn new(error: CpuError, pc: u16, registers: Registers, ram: &[u8]) -> Self {
+        // Step back an even number of bytes, so the listing stays aligned with `pc`.
+        let back = (pc as usize).min(2 * CONTEXT_LINES) & !1;
+        let mut listing = String::new();
+        let mut line = None;
+        for disassembled in disassemble(ram, pc - back as u16, 2 * CONTEXT_LINES + 1) {
+            let start = listing.len();
+            let _ = write!(
+                listing,
+                "{:03X}  {:04X}  {}",
+                disassembled.addr, disassembled.opcode, disassembled.instruction
+            );
+            if disassembled.addr == pc {
+                line = Some(start..listing.len());
+            }
+            listing.push('\n');
+        }
+
+        let opcode = match (ram.get(pc as usize), ram.get(pc as usize + 1)) {
+            (Some(&high), Some(&low)) => Some(u16::from_be_bytes([high, low])),
+            _ => None,
+        };
+        Self {
+            error,
+            pc,
+            opcode,
+            registers,
+            listing,
+            line,
+        }
+    }
+
+    // The registers on one line, e.g. `V0=00 V1=3F ... VF=01  I=0x2A0  SP=1  DT=0  ST=0`.
+    pub fn registers_summary(&self) -> String {
+        let registers = &self.registers;
+        let mut summary = String::new();
+        for (idx, value) in registers.v.iter().enumerate() {
+            let _ = write!(summary, "V{idx:X}={value:02X} ");
+        }
+        let _ = write!(
+            summary,
+            " I={:#05X}  SP={}  DT={}  ST={}",
+            registers.i, registers.sp, registers.dt, registers.st
+        );
+        summary
+    }
+}
+
+// Displayed as the CPU error itself, with the same sources.
+impl fmt::Display for Crash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.error.fmt(f)
+    }
+}
+
+impl Error for Crash {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.error.source()
+    }
+}
+
+#[cfg(feature = "std")]
+impl miette::Diagnostic for Crash {
+    fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        self.error.code()
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        Some(Box::new(self.registers_summary()))
+    }
+
+    fn source_code(&self) -> Option<&dyn miette::SourceCode> {
+        Some(&self.listing)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = miette::LabeledSpan> + '_>> {
+        let line = self.line.clone()?;
+        let label = miette::LabeledSpan::at(line, "failed here");
+        Some(Box::new(core::iter::once(label)))
+    }
+}
@@ -0,0 +1,268 @@
+use core::fmt;
+
+use alloc::vec::Vec;
+
+// A decoded CHIP-8 instruction. Besides the CHIP-8 instruction set this also decodes the
+// SUPER-CHIP and XO-CHIP extensions, so ROMs for those platforms can be disassembled (and
+// recognized) even though the emulator only executes CHIP-8.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    // CHIP-8
+    Nop,             // 0000
+    Sys(u16),        // 0NNN
+    Cls,             // 00E0
+    Ret,             // 00EE
+    Jp(u16),         // 1NNN
+    Call(u16),       // 2NNN
+    SeByte(u8, u8),  // 3XNN
+    SneByte(u8, u8), // 4XNN
+    SeReg(u8, u8),   // 5XY0
+    LdByte(u8, u8),  // 6XNN
+    AddByte(u8, u8), // 7XNN
+    LdReg(u8, u8),   // 8XY0
+    Or(u8, u8),      // 8XY1
+    And(u8, u8),     // 8XY2
+    Xor(u8, u8),     // 8XY3
+    AddReg(u8, u8),  // 8XY4
+    Sub(u8, u8),     // 8XY5
+    Shr(u8, u8),     // 8XY6
+    Subn(u8, u8),    // 8XY7
+    Shl(u8, u8),     // 8XYE
+    SneReg(u8, u8),  // 9XY0
+    LdI(u16),        // ANNN
+    JpV0(u16),       // BNNN
+    Rnd(u8, u8),     // CXNN
+    Drw(u8, u8, u8), // DXYN
+    Skp(u8),         // EX9E
+    Sknp(u8),        // EXA1
+    LdFromDt(u8),    // FX07
+    LdKey(u8),       // FX0A
+    LdDt(u8),        // FX15
+    LdSt(u8),        // FX18
+    AddI(u8),        // FX1E
+    LdFont(u8),      // FX29
+    Bcd(u8),         // FX33
+    Store(u8),       // FX55
+    Load(u8),        // FX65
+
+    // SUPER-CHIP
+    ScrollDown(u8), // 00CN
+    ScrollRight,    // 00FB
+    ScrollLeft,     // 00FC
+    Exit,           // 00FD
+    Low,            // 00FE
+    High,           // 00FF
+    LdBigFont(u8),  // FX30
+    StoreFlags(u8), // FX75
+    LoadFlags(u8),  // FX85
+
+    // XO-CHIP
+    ScrollUp(u8),       // 00DN
+    StoreRange(u8, u8), // 5XY2
+    LoadRange(u8, u8),  // 5XY3
+    LdILong,            // F000 NNNN (the address is in the following two bytes)
+    Plane(u8),          // FN01
+    Audio,              // F002
+    Pitch(u8),          // FX3A
+
+    Unknown(u16),
+}
+
+impl Instruction {
+    // Decode an opcode into an instruction.
+    pub fn decode(op: u16) -> Self {
+        let n1 = (op & 0xF000) >> 12;
+        let x = ((op & 0x0F00) >> 8) as u8;
+        let y = ((op & 0x00F0) >> 4) as u8;
+        let n = (op & 0x000F) as u8;
+        let nn = (op & 0x00FF) as u8;
+        let nnn = op & 0x0FFF;
+
+        match (n1, x, y, n) {
+            (0, 0, 0, 0) => Self::Nop,
+            (0, 0, 0xC, _) => Self::ScrollDown(n),
+            (0, 0, 0xD, _) => Self::ScrollUp(n),
+            (0, 0, 0xE, 0) => Self::Cls,
+            (0, 0, 0xE, 0xE) => Self::Ret,
+            (0, 0, 0xF, 0xB) => Self::ScrollRight,
+            (0, 0, 0xF, 0xC) => Self::ScrollLeft,
+            (0, 0, 0xF, 0xD) => Self::Exit,
+            (0, 0, 0xF, 0xE) => Self::Low,
+            (0, 0, 0xF, 0xF) => Self::High,
+            (0, _, _, _) => Self::Sys(nnn),
+            (1, _, _, _) => Self::Jp(nnn),
+            (2, _, _, _) => Self::Call(nnn),
+            (3, _, _, _) => Self::SeByte(x, nn),
+            (4, _, _, _) => Self::SneByte(x, nn),
+            (5, _, _, 0) => Self::SeReg(x, y),
+            (5, _, _, 2) => Self::StoreRange(x, y),
+            (5, _, _, 3) => Self::LoadRange(x, y),
+            (6, _, _, _) => Self::LdByte(x, nn),
+            (7, _, _, _) => Self::AddByte(x, nn),
+            (8, _, _, 0) => Self::LdReg(x, y),
+            (8, _, _, 1) => Self::Or(x, y),
+            (8, _, _, 2) => Self::And(x, y),
+            (8, _, _, 3) => Self::Xor(x, y),
+            (8, _, _, 4) => Self::AddReg(x, y),
+            (8, _, _, 5) => Self::Sub(x, y),
+            (8, _, _, 6) => Self::Shr(x, y),
+            (8, _, _, 7) => Self::Subn(x, y),
+            (8, _, _, 0xE) => Self::Shl(x, y),
+            (9, _, _, 0) => Self::SneReg(x, y),
+            (0xA, _, _, _) => Self::LdI(nnn),
+            (0xB, _, _, _) => Self::JpV0(nnn),
+            (0xC, _, _, _) => Self::Rnd(x, nn),
+            (0xD, _, _, _) => Self::Drw(x, y, n),
+            (0xE, _, 9, 0xE) => Self::Skp(x),
+            (0xE, _, 0xA, 1) => Self::Sknp(x),
+            (0xF, 0, 0, 0) => Self::LdILong,
+            (0xF, _, 0, 1) => Self::Plane(x),
+            (0xF, 0, 0, 2) => Self::Audio,
+            (0xF, _, 0, 7) => Self::LdFromDt(x),
+            (0xF, _, 0, 0xA) => Self::LdKey(x),
+            (0xF, _, 1, 5) => Self::LdDt(x),
+            (0xF, _, 1, 8) => Self::LdSt(x),
+            (0xF, _, 1, 0xE) => Self::AddI(x),
+            (0xF, _, 2, 9) => Self::LdFont(x),
+            (0xF, _, 3, 0) => Self::LdBigFont(x),
+            (0xF, _, 3, 3) => Self::Bcd(x),
+            (0xF, _, 3, 0xA) => Self::Pitch(x),
+            (0xF, _, 5, 5) => Self::Store(x),
+            (0xF, _, 6, 5) => Self::Load(x),
+            (0xF, _, 7, 5) => Self::StoreFlags(x),
+            (0xF, _, 8, 5) => Self::LoadFlags(x),
+            _ => Self::Unknown(op),
+        }
+    }
+
+    // Size of the instruction in bytes (F000 NNNN is the only 4 byte instruction).
+    pub fn size(&self) -> u16 {
+        match self {
+            Self::LdILong => 4,
+            _ => 2,
+        }
+    }
+
+    // Whether the instruction is part of the original CHIP-8 instruction set (which the emulator executes).
+    pub fn is_chip8(&self) -> bool {
+        !self.is_super_chip() && !self.is_xo_chip() && !matches!(self, Self::Unknown(_))
+    }
+
+    // Whether the instruction was introduced by SUPER-CHIP.
+    pub fn is_super_chip(&self) -> bool {
+        matches!(
+            self,
+            Self::ScrollDown(_)
+                | Self::ScrollRight
+                | Self::ScrollLeft
+                | Self::Exit
+                | Self::Low
+                | Self::High
+                | Self::LdBigFont(_)
+                | Self::StoreFlags(_)
+                | Self::LoadFlags(_)
+        )
+    }
+
+    // Whether the instruction was introduced by XO-CHIP.
+    pub fn is_xo_chip(&self) -> bool {
+        matches!(
+            self,
+            Self::ScrollUp(_)
+                | Self::StoreRange(..)
+                | Self::LoadRange(..)
+                | Self::LdILong
+                | Self::Plane(_)
+                | Self::Audio
+                | Self::Pitch(_)
+        )
+    }
+}
+
+// Formats instructions with the classic (Cowgod) CHIP-8 mnemonics, e.g. `LD V0, 0x05` or `DRW V0, V1, 5`.
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Nop => write!(f, "NOP"),
+            Self::Sys(nnn) => write!(f, "SYS {nnn:#05X}"),
+            Self::Cls => write!(f, "CLS"),
+            Self::Ret => write!(f, "RET"),
+            Self::Jp(nnn) => write!(f, "JP {nnn:#05X}"),
+            Self::Call(nnn) => write!(f, "CALL {nnn:#05X}"),
+            Self::SeByte(x, nn) => write!(f, "SE V{x:X}, {nn:#04X}"),
+            Self::SneByte(x, nn) => write!(f, "SNE V{x:X}, {nn:#04X}"),
+            Self::SeReg(x, y) => write!(f, "SE V{x:X}, V{y:X}"),
+            Self::LdByte(x, nn) => write!(f, "LD V{x:X}, {nn:#04X}"),
+            Self::AddByte(x, nn) => write!(f, "ADD V{x:X}, {nn:#04X}"),
+            Self::LdReg(x, y) => write!(f, "LD V{x:X}, V{y:X}"),
+            Self::Or(x, y) => write!(f, "OR V{x:X}, V{y:X}"),
+            Self::And(x, y) => write!(f, "AND V{x:X}, V{y:X}"),
+            Self::Xor(x, y) => write!(f, "XOR V{x:X}, V{y:X}"),
+            Self::AddReg(x, y) => write!(f, "ADD V{x:X}, V{y:X}"),
+            Self::Sub(x, y) => write!(f, "SUB V{x:X}, V{y:X}"),
+            Self::Shr(x, y) => write!(f, "SHR V{x:X}, V{y:X}"),
+            Self::Subn(x, y) => write!(f, "SUBN V{x:X}, V{y:X}"),
+            Self::Shl(x, y) => write!(f, "SHL V{x:X}, V{y:X}"),
+            Self::SneReg(x, y) => write!(f, "SNE V{x:X}, V{y:X}"),
+            Self::LdI(nnn) => write!(f, "LD I, {nnn:#05X}"),
+            Self::JpV0(nnn) => write!(f, "JP V0, {nnn:#05X}"),
+            Self::Rnd(x, nn) => write!(f, "RND V{x:X}, {nn:#04X}"),
+            Self::Drw(x, y, n) => write!(f, "DRW V{x:X}, V{y:X}, {n}"),
+            Self::Skp(x) => write!(f, "SKP V{x:X}"),
+            Self::Sknp(x) => write!(f, "SKNP V{x:X}"),
+            Self::LdFromDt(x) => write!(f, "LD V{x:X}, DT"),
+            Self::LdKey(x) => write!(f, "LD V{x:X}, K"),
+            Self::LdDt(x) => write!(f, "LD DT, V{x:X}"),
+            Self::LdSt(x) => write!(f, "LD ST, V{x:X}"),
+            Self::AddI(x) => write!(f, "ADD I, V{x:X}"),
+            Self::LdFont(x) => write!(f, "LD F, V{x:X}"),
+            Self::Bcd(x) => write!(f, "LD B, V{x:X}"),
+            Self::Store(x) => write!(f, "LD [I], V{x:X}"),
+            Self::Load(x) => write!(f, "LD V{x:X}, [I]"),
+            Self::ScrollDown(n) => write!(f, "SCD {n}"),
+            Self::ScrollRight => write!(f, "SCR"),
+            Self::ScrollLeft => write!(f, "SCL"),
+            Self::Exit => write!(f, "EXIT"),
+            Self::Low => write!(f, "LOW"),
+            Self::High => write!(f, "HIGH"),
+            Self::LdBigFont(x) => write!(f, "LD HF, V{x:X}"),
+            Self::StoreFlags(x) => write!(f, "LD R, V{x:X}"),
+            Self::LoadFlags(x) => write!(f, "LD V{x:X}, R"),
+            Self::ScrollUp(n) => write!(f, "SCU {n}"),
+            Self::StoreRange(x, y) => write!(f, "SAVE V{x:X} - V{y:X}"),
+            Self::LoadRange(x, y) => write!(f, "LOAD V{x:X} - V{y:X}"),
+            Self::LdILong => write!(f, "LD I, LONG"),
+            Self::Plane(n) => write!(f, "PLANE {n}"),
+            Self::Audio => write!(f, "AUDIO"),
+            Self::Pitch(x) => write!(f, "PITCH V{x:X}"),
+            Self::Unknown(op) => write!(f, "DW {op:#06X}"),
+        }
+    }
+}
+
+// A single line of a disassembly listing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DisassembledLine {
+    pub addr: u16,
+    pub opcode: u16,
+    pub instruction: Instruction,
+}
+
+// Linearly disassemble `count` instructions from `memory`, starting at `start`.
+// Stops early at the end of memory.
+pub fn disassemble(memory: &[u8], start: u16, count: usize) -> Vec<DisassembledLine> {
+    let mut lines = Vec::with_capacity(count.min(memory.len() / 2));
+    let mut addr = start as usize;
+
+    while lines.len() < count && addr + 1 < memory.len() {
+        let opcode = u16::from_be_bytes([memory[addr], memory[addr + 1]]);
+        let instruction = Instruction::decode(opcode);
+        lines.push(DisassembledLine {
+            addr: addr as u16,
+            opcode,
+            instruction,
+        });
+        addr += instruction.size() as usize;
+    }
+    lines
+}
@@ -1,6 +1,27 @@
+use alloc::vec::Vec;
 use log::{debug, warn};
 
-use crate::constants::{DISPLAY_HEIGHT, DISPLAY_WIDTH};
+use crate::{
+    constants::{DISPLAY_HEIGHT, DISPLAY_WIDTH},
+    error::StateError,
+    state::{StateReader, StateWriter},
+};
+
+// Colors (RGB) of lit and unlit pixels when rendering the display to an image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Palette {
+    pub foreground: [u8; 3],
+    pub background: [u8; 3],
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self {
+            foreground: [0xFF, 0xFF, 0xFF],
+            background: [0x00, 0x00, 0x00],
+        }
+    }
+}
 
 // Holds the display buffer for the CHIP-8 emulator as a 2D array.
 pub struct Display {
@@ -26,9 +47,58 @@ impl Display {
         &self.display_buffer
     }
 
-    // Draw a sprite at (x, y) onto the display buffer.
+    // Render the display as RGBA bytes (row by row, 4 bytes per pixel), with every CHIP-8 pixel
+    // drawn as a `scale` x `scale` square.
+    pub fn to_rgba(&self, palette: &Palette, scale: usize) -> Vec<u8> {
+        let [fr, fg, fb] = palette.foreground;
+        let [br, bg, bb] = palette.background;
+        let (on, off) = ([fr, fg, fb, 0xFF], [br, bg, bb, 0xFF]);
+
+        let mut image = Vec::with_capacity(DISPLAY_WIDTH * DISPLAY_HEIGHT * scale * scale * 4);
+        for row in &self.display_buffer {
+            let start = image.len();
+            for &pixel in row {
+                for _ in 0..scale {
+                    image.extend_from_slice(if pixel { &on } else { &off });
+                }
+            }
+            let end = image.len();
+            for _ in 1..scale {
+                image.extend_from_within(start..end);
+            }
+        }
+        image
+    }
+
+    // Write the display buffer to a save state, packed as 8 pixels per byte (MSB first).
+    pub fn save_state(&self, writer: &mut StateWriter) {
+        for row in &self.display_buffer {
+            for pixels in row.chunks_exact(8) {
+                let byte = pixels
+                    .iter()
+                    .fold(0u8, |byte, &pixel| (byte << 1) | pixel as u8);
+                writer.write_u8(byte);
+            }
+        }
+    }
+
+    // Restore the display buffer from a save state.
+    pub fn load_state(&mut self, reader: &mut StateReader) -> Result<(), StateError> {
+        for row in &mut self.display_buffer {
+            for pixels in row.chunks_exact_mut(8) {
+                let byte = reader.read_u8()?;
+                for (bit, pixel) in pixels.iter_mut().enumerate() {
+                    *pixel = byte & (0b1000_0000 >> bit) != 0;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Draw a sprite at (x, y) onto the display buffer. Parts of the sprite past the screen edges are
+    // clipped, or wrapped around to the other side if `wrap` is set.
     // Returns true if any pixels were flipped from set to unset (collision).
-    pub fn draw_sprite(&mut self, x: usize, y: usize, sprite: &[u8]) -> bool {
+    pub fn draw_sprite(&mut self, x: usize, y: usize, sprite: &[u8], wrap: bool) -> bool {
         // Handle sprite possibly being empty.
         if sprite.is_empty() {
             warn!("Attempted to draw empty sprite at ({}, {})", x, y);
@@ -46,26 +116,22 @@ impl Display {
         }
 
         let mut flipped = false;
-        let height = sprite.len();
 
         // Wrap starting coordinates.
         let x_coord = x % DISPLAY_WIDTH;
         let y_coord = y % DISPLAY_HEIGHT;
 
-        // Iterate over each row of the sprite.
-        for y_offset in 0..height {
+        // Iterate over each row of the sprite (the pixels for that row are held in `byte`).
+        for (y_offset, &byte) in sprite.iter().enumerate() {
             // Calculate the actual Y coordinate on the screen for the current row.
             let screen_y = y_coord + y_offset;
 
             // If the current sprite row would be drawn off the bottom edge of the screen,
             // stop drawing the rest of the sprite.
-            if screen_y >= DISPLAY_HEIGHT {
+            if screen_y >= DISPLAY_HEIGHT && !wrap {
                 break;
             }
 
-            // Get the pixels for the current row of the sprite.
-            let byte = sprite[y_offset];
-
             // Iterate over each column (bit) within the current sprite row (8 pixels wide).
             for x_offset in 0..8 {
                 // Calculate the actual X coordinate on the screen for the current pixel.
@@ -73,7 +139,7 @@ impl Display {
 
                 // If the current sprite pixel would be drawn off the right edge of the screen,
                 // stop drawing the rest of this row.
-                if screen_x >= DISPLAY_WIDTH {
+                if screen_x >= DISPLAY_WIDTH && !wrap {
                     break;
                 }
 
@@ -1,10 +1,21 @@
+use alloc::{boxed::Box, vec::Vec};
+
+use log::warn;
+
 use crate::{
-    constants::{DISPLAY_HEIGHT, DISPLAY_WIDTH, PROGRAM_START},
-    cpu::Cpu,
-    display::Display,
+    constants::{DISPLAY_HEIGHT, DISPLAY_WIDTH, MEMORY_SIZE, PROGRAM_START},
+    coverage::Coverage,
+    cpu::{Cpu, Registers},
+    crash::Crash,
+    display::{Display, Palette},
     error::EmulatorError,
     input::Keypad,
     memory::Memory,
+    policy::{ErrorAction, ErrorCounts, ErrorKind, ErrorPolicy, FrameReport},
+    profiler::Profile,
+    quirks::Quirks,
+    regions::{Region, WriteProtection},
+    state::{StateReader, StateWriter},
 };
 
 // Holds the main components of the CHIP-8 emulator (CPU, memory, display, keypad).
@@ -13,6 +24,17 @@ pub struct Emulator {
     memory: Memory,
     display: Display,
     keypad: Keypad,
+    profile: Option<Profile>,
+    error_policy: ErrorPolicy,
+    // Errors skipped since the last reset.
+    skipped: ErrorCounts,
+    frame_report: FrameReport,
+}
+
+impl Default for Emulator {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Emulator {
@@ -23,6 +45,25 @@ impl Emulator {
             memory: Memory::new(),
             display: Display::new(),
             keypad: Keypad::new(),
+            profile: None,
+            error_policy: ErrorPolicy::default(),
+            skipped: ErrorCounts::default(),
+            frame_report: FrameReport::default(),
+        }
+    }
+
+    // Create a new emulator whose random number generator (used by CXNN) is seeded with the given value.
+    // Useful for reproducible runs, and for no_std targets without an entropy source.
+    pub fn with_seed(seed: u64) -> Self {
+        Self {
+            cpu: Cpu::with_seed(seed),
+            memory: Memory::new(),
+            display: Display::new(),
+            keypad: Keypad::new(),
+            profile: None,
+            error_policy: ErrorPolicy::default(),
+            skipped: ErrorCounts::default(),
+            frame_report: FrameReport::default(),
         }
     }
 
@@ -32,20 +73,111 @@ impl Emulator {
         self.memory.reset();
         self.keypad.reset();
         self.display.clear();
+        self.skipped = ErrorCounts::default();
+        self.frame_report = FrameReport::default();
+        if let Some(profile) = &mut self.profile {
+            profile.restart();
+        }
     }
 
-    // Perform a single CPU cycle (fetch, decode, execute).
+    // Perform a single CPU cycle (fetch, decode, execute). Errors the error policy doesn't halt on
+    // are counted and the failing instruction is skipped.
     pub fn cycle(&mut self) -> Result<(), EmulatorError> {
-        self.cpu
+        if let Some(profile) = &mut self.profile {
+            let pc = self.cpu.get_registers().pc as usize;
+            let ram = self.memory.get_ram();
+            if let (Some(&high), Some(&low)) = (ram.get(pc), ram.get(pc + 1)) {
+                profile.record(pc as u16, u16::from_be_bytes([high, low]));
+            }
+        }
+        let pc = self.cpu.get_pc();
+        let Err(error) = self
+            .cpu
             .cycle(&mut self.memory, &mut self.display, &mut self.keypad)
-            .map_err(|result| EmulatorError::Cpu { source: result })
+        else {
+            return Ok(());
+        };
+
+        let kind = ErrorKind::of(&error);
+        match self.error_policy.action(kind) {
+            ErrorAction::Halt => {
+                let registers = self.cpu.get_registers();
+                let crash = Crash::new(error, pc, registers, self.memory.get_ram());
+                return Err(EmulatorError::Cpu {
+                    source: Box::new(crash),
+                });
+            }
+            ErrorAction::Log => warn!("Skipped instruction at {pc:#05X}: {error}"),
+            ErrorAction::Skip => {}
+        }
+        self.skipped.add(kind);
+        self.cpu.wrap_pc();
+        Ok(())
+    }
+
+    // Run a single display frame: perform the given number of CPU cycles, then tick the timers once.
+    // What happened is available from `frame_report` afterwards, also if the frame halted.
+    pub fn run_frame(&mut self, steps: usize) -> Result<(), EmulatorError> {
+        let before = self.skipped;
+        let mut cycles = 0;
+        let result = (0..steps).try_for_each(|_| {
+            cycles += 1;
+            self.cycle()
+        });
+        self.frame_report = FrameReport {
+            cycles,
+            skipped: self.skipped.since(&before),
+            total_skipped: self.skipped,
+        };
+        result?;
+        self.tick_timers();
+        Ok(())
+    }
+
+    // Get the report of the last `run_frame`.
+    pub fn frame_report(&self) -> FrameReport {
+        self.frame_report
+    }
+
+    // Get the errors skipped since the last reset (including those skipped by `cycle` outside of
+    // `run_frame`).
+    pub fn get_skipped_errors(&self) -> ErrorCounts {
+        self.skipped
+    }
+
+    // Get the policy deciding which errors halt the emulator.
+    pub fn get_error_policy(&self) -> ErrorPolicy {
+        self.error_policy
+    }
+
+    // Change what happens when an instruction fails. Kept across resets.
+    pub fn set_error_policy(&mut self, policy: ErrorPolicy) {
+        self.error_policy = policy;
     }
 
     // Load a ROM into memory starting at the program start address.
     pub fn load_rom(&mut self, data: &[u8]) -> Result<(), EmulatorError> {
         self.memory
             .write_bytes(PROGRAM_START, data)
-            .map_err(|result| EmulatorError::Memory { source: result })
+            .map_err(|result| EmulatorError::Memory { source: result })?;
+        self.memory.set_program_len(data.len());
+        Ok(())
+    }
+
+    // Get the regions of RAM (font, reserved, program, free) for the loaded ROM.
+    pub fn memory_map(&self) -> Vec<Region> {
+        self.memory.memory_map()
+    }
+
+    // Get what happens when the program writes to the font or the reserved area.
+    pub fn get_write_protection(&self) -> WriteProtection {
+        self.memory.get_write_protection()
+    }
+
+    // Change what happens when the program writes to the font or the reserved area. Writes with
+    // `write_ram` are never refused. Kept across resets.
+    pub fn set_write_protection(&mut self, protection: WriteProtection) {
+        self.memory.set_write_protection(protection);
     }
 
     // Tick (decrement) the CPU timers.
@@ -54,15 +186,84 @@ impl Emulator {
     }
 
     // Get the current value of the sound timer.
-    pub fn get_st(&mut self) -> u8 {
+    pub fn get_st(&self) -> u8 {
         self.cpu.get_st()
     }
 
     // Get a reference to the display buffer.
-    pub fn get_display_buffer(&mut self) -> &[[bool; DISPLAY_WIDTH]; DISPLAY_HEIGHT] {
+    pub fn get_display_buffer(&self) -> &[[bool; DISPLAY_WIDTH]; DISPLAY_HEIGHT] {
         self.display.get_buffer()
     }
 
+    // Render the display as an RGBA image of `DISPLAY_WIDTH * scale` x `DISPLAY_HEIGHT * scale`
+    // pixels, e.g. for screenshots.
+    pub fn to_rgba(&self, palette: &Palette, scale: usize) -> Vec<u8> {
+        self.display.to_rgba(palette, scale)
+    }
+
+    // Get a reference to RAM.
+    pub fn get_ram(&self) -> &[u8; MEMORY_SIZE] {
+        self.memory.get_ram()
+    }
+
+    // Write a slice of bytes to RAM starting at the given address.
+    pub fn write_ram(&mut self, start: u16, data: &[u8]) -> Result<(), EmulatorError> {
+        self.memory
+            .write_bytes(start, data)
+            .map_err(|result| EmulatorError::Memory { source: result })
+    }
+
+    // Get the quirks currently in effect.
+    pub fn get_quirks(&self) -> Quirks {
+        self.cpu.get_quirks()
+    }
+
+    // Change the quirks (platform specific behaviors) used when executing opcodes.
+    // Quirks are configuration, so they are kept across `reset` and not included in save states.
+    pub fn set_quirks(&mut self, quirks: Quirks) {
+        self.cpu.set_quirks(quirks);
+    }
+
+    // Start (or stop and discard) recording which RAM addresses the program executes, reads and
+    // writes. Coverage is kept across `reset` and not included in save states.
+    pub fn set_coverage_enabled(&mut self, enabled: bool) {
+        self.memory.set_coverage_enabled(enabled);
+    }
+
+    // The coverage recorded so far, if enabled.
+    pub fn get_coverage(&self) -> Option<&Coverage> {
+        self.memory.get_coverage()
+    }
+
+    pub fn get_coverage_mut(&mut self) -> Option<&mut Coverage> {
+        self.memory.get_coverage_mut()
+    }
+
+    // Start (or stop and discard) counting executions per address and per call stack.
+    // The counts are kept across `reset` and not included in save states.
+    pub fn set_profiling_enabled(&mut self, enabled: bool) {
+        match (enabled, self.profile.is_some()) {
+            (true, false) => self.profile = Some(Profile::new()),
+            (false, true) => self.profile = None,
+            _ => {}
+        }
+    }
+
+    // The profile collected so far, if enabled.
+    pub fn get_profile(&self) -> Option<&Profile> {
+        self.profile.as_ref()
+    }
+
+    // Get a snapshot of the CPU registers, pointers and timers.
+    pub fn get_registers(&self) -> Registers {
+        self.cpu.get_registers()
+    }
+
+    // Overwrite the CPU registers, pointers and timers.
+    pub fn set_registers(&mut self, registers: Registers) {
+        self.cpu.set_registers(registers);
+    }
+
     // Mark the key at the given index as pressed (true).
     pub fn press_key(&mut self, idx: usize) -> Result<(), EmulatorError> {
         self.keypad
@@ -76,4 +277,29 @@ impl Emulator {
             .release_key(idx)
             .map_err(|result| EmulatorError::Keypad { source: result })
     }
+
+    // Capture the full emulator state (CPU, memory, display and keypad) as a save state
+    // of `STATE_SIZE` bytes. The random number generator state is not included.
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut writer = StateWriter::new();
+        self.cpu.save_state(&mut writer);
+        self.memory.save_state(&mut writer);
+        self.display.save_state(&mut writer);
+        self.keypad.save_state(&mut writer);
+        writer.finish()
+    }
+
+    // Restore the emulator state from a save state created by `save_state`.
+    // The state is validated before any component is modified.
+    pub fn load_state(&mut self, data: &[u8]) -> Result<(), EmulatorError> {
+        let mut reader = StateReader::new(data)?;
+        self.cpu.load_state(&mut reader)?;
+        self.memory.load_state(&mut reader)?;
+        self.display.load_state(&mut reader)?;
+        self.keypad.load_state(&mut reader)?;
+        if let Some(profile) = &mut self.profile {
+            profile.restart();
+        }
+        Ok(())
+    }
 }
@@ -1,7 +1,12 @@
+use alloc::{boxed::Box, string::String};
+#[cfg(feature = "std")]
 use miette::Diagnostic;
 use thiserror::Error;
 
-#[derive(Debug, Error, Diagnostic)]
+use crate::{crash::Crash, regions::RegionKind};
+
+#[derive(Debug, Error)]
+#[cfg_attr(feature = "std", derive(Diagnostic))]
 pub enum MemoryError {
     #[error("Memory write out of bounds at address {addr:#06X}")]
     OutOfBoundsWrite { addr: u16 },
@@ -15,25 +20,92 @@ pub enum MemoryError {
     #[error("Memory read out of bounds: start={start:#06X}, end={end:#06X}")]
     OutOfBoundsReadRange { start: u16, end: u16 },
 
+    #[error("Write to {region} memory at address {addr:#05X}")]
+    #[cfg_attr(
+        feature = "std",
+        diagnostic(
+            code(emulator::memory::protected_write),
+            help("the font and the area below 0x200 are write protected")
+        )
+    )]
+    ProtectedWrite { addr: u16, region: RegionKind },
+
     #[error("Stack overflow at stack pointer {sp}")]
-    #[diagnostic(code(emulator::memory::stack_overflow))]
+    #[cfg_attr(feature = "std", diagnostic(code(emulator::memory::stack_overflow)))]
     StackOverflow { sp: usize },
 
     #[error("Stack underflow at stack pointer {sp}")]
-    #[diagnostic(code(emulator::memory::stack_underflow))]
+    #[cfg_attr(feature = "std", diagnostic(code(emulator::memory::stack_underflow)))]
     StackUnderflow { sp: usize },
 }
 
-#[derive(Debug, Error, Diagnostic)]
+#[derive(Debug, Error)]
+#[cfg_attr(feature = "std", derive(Diagnostic))]
 pub enum KeypadError {
     #[error("Keypad index out of bounds: {idx}")]
     OutOfBoundsKeyIndex { idx: usize },
 }
 
-#[derive(Debug, Error, Diagnostic)]
+#[derive(Debug, Error)]
+#[cfg_attr(feature = "std", derive(Diagnostic))]
+pub enum StateError {
+    #[error("Save state has invalid length: expected {expected} bytes, got {actual}")]
+    #[cfg_attr(feature = "std", diagnostic(code(emulator::state::invalid_length)))]
+    InvalidLength { expected: usize, actual: usize },
+
+    #[error("Save state has invalid magic bytes")]
+    #[cfg_attr(feature = "std", diagnostic(code(emulator::state::invalid_magic)))]
+    InvalidMagic,
+
+    #[error("Unsupported save state version: {version}")]
+    #[cfg_attr(
+        feature = "std",
+        diagnostic(code(emulator::state::unsupported_version))
+    )]
+    UnsupportedVersion { version: u8 },
+
+    #[error("Save state ended unexpectedly")]
+    #[cfg_attr(feature = "std", diagnostic(code(emulator::state::unexpected_end)))]
+    UnexpectedEnd,
+}
+
+#[derive(Debug, Error)]
+#[cfg_attr(feature = "std", derive(Diagnostic))]
+pub enum SymbolError {
+    #[error("Invalid symbol on line {line}")]
+    #[cfg_attr(
+        feature = "std",
+        diagnostic(
+            code(emulator::symbols::invalid_line),
+            help("symbols are written as `name 0x2A0`, `name = 0x2A0` or `0x2A0 name`")
+        )
+    )]
+    InvalidLine { line: usize },
+
+    #[error("Symbol '{name}' is defined twice (line {line})")]
+    #[cfg_attr(feature = "std", diagnostic(code(emulator::symbols::duplicate_name)))]
+    DuplicateName { name: String, line: usize },
+
+    #[error("Unknown symbol '{name}'")]
+    #[cfg_attr(feature = "std", diagnostic(code(emulator::symbols::unknown_symbol)))]
+    UnknownSymbol { name: String },
+
+    #[error("Invalid location '{location}'")]
+    #[cfg_attr(
+        feature = "std",
+        diagnostic(
+            code(emulator::symbols::invalid_location),
+            help("locations are addresses (`0x2A4`), symbols (`draw`) or offsets (`draw+0x4`)")
+        )
+    )]
+    InvalidLocation { location: String },
+}
+
+#[derive(Debug, Error)]
+#[cfg_attr(feature = "std", derive(Diagnostic))]
 pub enum CpuError {
     #[error("Memory error")]
-    #[diagnostic(transparent)]
+    #[cfg_attr(feature = "std", diagnostic(transparent))]
     Memory {
         #[from]
         #[source]
@@ -41,7 +113,7 @@ pub enum CpuError {
     },
 
     #[error("Keypad error")]
-    #[diagnostic(transparent)]
+    #[cfg_attr(feature = "std", diagnostic(transparent))]
     Keypad {
         #[from]
         #[source]
@@ -49,14 +121,31 @@ pub enum CpuError {
     },
 
     #[error("Unimplemented opcode: {opcode:#06X}")]
-    #[diagnostic(code(emulator::cpu::unimplemented_opcode))]
+    #[cfg_attr(feature = "std", diagnostic(code(emulator::cpu::unimplemented_opcode)))]
     UnimplementedOpcode { opcode: u16 },
+
+    // `pc` and `opcode` are the address and opcode of the instruction that failed.
+    #[error("{opcode:04X} at {pc:#05X} moved the program counter out of memory, to {target:#06X}")]
+    #[cfg_attr(feature = "std", diagnostic(code(emulator::cpu::pc_out_of_range)))]
+    PcOutOfRange { pc: u16, opcode: u16, target: u16 },
+
+    #[error("{opcode:04X} at {pc:#05X} accesses memory past the end from I = {i:#06X}")]
+    #[cfg_attr(feature = "std", diagnostic(code(emulator::cpu::index_out_of_range)))]
+    IndexOutOfRange { pc: u16, opcode: u16, i: u16 },
+
+    #[error("{opcode:04X} at {pc:#05X} returns with an empty stack")]
+    #[cfg_attr(
+        feature = "std",
+        diagnostic(code(emulator::cpu::return_with_empty_stack))
+    )]
+    ReturnWithEmptyStack { pc: u16, opcode: u16 },
 }
 
-#[derive(Debug, Error, Diagnostic)]
+#[derive(Debug, Error)]
+#[cfg_attr(feature = "std", derive(Diagnostic))]
 pub enum EmulatorError {
     #[error("Memory error")]
-    #[diagnostic(transparent)]
+    #[cfg_attr(feature = "std", diagnostic(transparent))]
     Memory {
         #[from]
         #[source]
@@ -64,7 +153,7 @@ pub enum EmulatorError {
     },
 
     #[error("Keypad error")]
-    #[diagnostic(transparent)]
+    #[cfg_attr(feature = "std", diagnostic(transparent))]
     Keypad {
         #[from]
         #[source]
@@ -72,10 +161,17 @@ pub enum EmulatorError {
     },
 
     #[error("CPU execution error")]
-    #[diagnostic(transparent)]
+    #[cfg_attr(feature = "std", diagnostic(transparent))]
     Cpu {
+        #[source]
+        source: Box<Crash>,
+    },
+
+    #[error("Save state error")]
+    #[cfg_attr(feature = "std", diagnostic(transparent))]
+    State {
         #[from]
         #[source]
-        source: CpuError,
+        source: StateError,
     },
 }
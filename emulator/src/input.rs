@@ -1,6 +1,10 @@
 use log::{info, warn};
 
-use crate::{constants::NUM_KEYS, error::KeypadError};
+use crate::{
+    constants::NUM_KEYS,
+    error::{KeypadError, StateError},
+    state::{StateReader, StateWriter},
+};
 
 // Holds the state of the CHIP-8 keypad (16 keys).
 // CHIP-8    QWERTY
@@ -36,6 +40,25 @@ impl Keypad {
         }
     }
 
+    // Write the key states to a save state, as a bitmask (bit N set if key N is pressed).
+    pub fn save_state(&self, writer: &mut StateWriter) {
+        let mask = self
+            .keys
+            .iter()
+            .enumerate()
+            .fold(0u16, |mask, (idx, &pressed)| mask | (pressed as u16) << idx);
+        writer.write_u16(mask);
+    }
+
+    // Restore the key states from a save state.
+    pub fn load_state(&mut self, reader: &mut StateReader) -> Result<(), StateError> {
+        let mask = reader.read_u16()?;
+        for (idx, key) in self.keys.iter_mut().enumerate() {
+            *key = mask & (1 << idx) != 0;
+        }
+        Ok(())
+    }
+
     // Mark the key at the given index as pressed (true).
     pub fn press_key(&mut self, idx: usize) -> Result<(), KeypadError> {
         if idx < NUM_KEYS {
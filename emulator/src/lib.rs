@@ -1,9 +1,40 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+mod analysis;
+mod cfg;
 pub mod constants;
+mod coverage;
 mod cpu;
+mod crash;
+mod disasm;
 mod display;
 mod emulator;
 mod error;
 mod input;
 mod memory;
+mod policy;
+mod profiler;
+mod quirks;
+mod regions;
+mod state;
+mod symbols;
 
+pub use analysis::{RomAnalysis, analyze_rom, reachable_instructions};
+pub use cfg::{BasicBlock, CfgWarning, ControlFlowGraph, Edge, EdgeKind};
+pub use coverage::{Access, Coverage};
+pub use cpu::Registers;
+pub use crash::Crash;
+pub use disasm::{DisassembledLine, Instruction, disassemble};
+pub use display::Palette;
 pub use emulator::Emulator;
+pub use error::{CpuError, EmulatorError, KeypadError, MemoryError, StateError, SymbolError};
+pub use policy::{
+    ErrorAction, ErrorCounts, ErrorKind, ErrorPolicy, FrameReport, UnknownErrorAction,
+    UnknownErrorKind,
+};
+pub use profiler::Profile;
+pub use quirks::{Platform, Quirks, UnknownPlatform};
+pub use regions::{Region, RegionKind, UnknownWriteProtection, WriteProtection, memory_map};
+pub use symbols::{SymbolTable, SymbolicInstruction};
@@ -1,14 +1,25 @@
-use log::{debug, info};
+use alloc::vec::Vec;
+
+use log::{debug, info, warn};
 
 use crate::{
-    constants::{FONTSET, FONTSET_SIZE, MEMORY_SIZE, STACK_SIZE},
-    error::MemoryError,
+    constants::{FONTSET, FONTSET_SIZE, MEMORY_SIZE, PROGRAM_START, STACK_SIZE},
+    coverage::Coverage,
+    error::{MemoryError, StateError},
+    regions::{Region, RegionKind, WriteProtection, memory_map},
+    state::{StateReader, StateWriter},
 };
 
 // Holds RAM and stack for the CHIP-8 emulator.
 pub struct Memory {
     ram: [u8; MEMORY_SIZE],
     stack: [u16; STACK_SIZE],
+    // Records how the program uses RAM, when enabled. Loading a ROM and frontend writes through
+    // `write_bytes` are not recorded.
+    coverage: Option<Coverage>,
+    // Size of the loaded ROM, for the memory map.
+    program_len: usize,
+    write_protection: WriteProtection,
 }
 
 impl Memory {
@@ -17,27 +28,73 @@ impl Memory {
         let mut mem = Self {
             ram: [0; MEMORY_SIZE],
             stack: [0; STACK_SIZE],
+            coverage: None,
+            program_len: 0,
+            write_protection: WriteProtection::default(),
         };
         mem.load_fontset();
         mem
     }
 
-    // Reset memory and stack to initial state, reload fontset.
+    // Reset memory and stack to initial state, reload fontset. Coverage and write protection are
+    // kept, so they can span several runs of a ROM.
     pub fn reset(&mut self) {
         self.ram = [0; MEMORY_SIZE];
         self.stack = [0; STACK_SIZE];
+        self.program_len = 0;
         self.load_fontset();
         info!("Memory reset and fontset reloaded.")
     }
 
-    // Write a single byte to RAM at the given address.
-    pub fn write_byte(&mut self, addr: u16, value: u8) -> Result<(), MemoryError> {
-        if (addr as usize) < MEMORY_SIZE {
-            self.ram[addr as usize] = value;
-            Ok(())
-        } else {
-            Err(MemoryError::OutOfBoundsWrite { addr })
+    // Write bytes for the program (FX33, FX55): unlike `write_bytes`, the write is subject to
+    // write protection and recorded in coverage. Nothing is written if any byte is refused.
+    pub fn store_bytes(&mut self, start: u16, data: &[u8]) -> Result<(), MemoryError> {
+        let end = start as usize + data.len();
+        if end > MEMORY_SIZE {
+            return Err(MemoryError::OutOfBoundsWriteRange {
+                start,
+                len: data.len(),
+            });
+        }
+        // The protected regions are at the bottom of RAM, so the first byte decides.
+        let region = RegionKind::at(start, self.program_len);
+        if region.is_protected() {
+            match self.write_protection {
+                WriteProtection::Off => {}
+                WriteProtection::Warn => {
+                    warn!("Program wrote to {region} memory at {start:#05X}");
+                }
+                WriteProtection::Error => {
+                    return Err(MemoryError::ProtectedWrite {
+                        addr: start,
+                        region,
+                    });
+                }
+            }
+        }
+
+        self.ram[start as usize..end].copy_from_slice(data);
+        if let Some(coverage) = &mut self.coverage {
+            (start..end as u16).for_each(|addr| coverage.mark_written(addr));
+        }
+        Ok(())
+    }
+
+    // Read bytes for the program (FX65), the counterpart of `store_bytes`: the read is recorded in
+    // coverage.
+    pub fn load_bytes(&mut self, start: u16, len: usize) -> Result<&[u8], MemoryError> {
+        let end = start as usize + len;
+        if end > MEMORY_SIZE {
+            return Err(MemoryError::OutOfBoundsReadRange {
+                start,
+                end: end.min(u16::MAX as usize) as u16,
+            });
+        }
+
+        if let Some(coverage) = &mut self.coverage {
+            (start..end as u16).for_each(|addr| coverage.mark_read(addr));
         }
+        Ok(&self.ram[start as usize..end])
     }
 
     // Write a slice of bytes to RAM starting at the given address.
@@ -47,27 +104,23 @@ impl Memory {
             Ok(())
         } else {
             Err(MemoryError::OutOfBoundsWriteRange {
-                start: start,
+                start,
                 len: data.len(),
             })
         }
     }
 
-    // Read a single byte from RAM at the given address.
-    pub fn read_byte(&mut self, addr: u16) -> Result<u8, MemoryError> {
-        if (addr as usize) < MEMORY_SIZE {
-            Ok(self.ram[addr as usize])
-        } else {
-            Err(MemoryError::OutOfBoundsRead { addr })
-        }
-    }
-
     // Read a range of bytes from RAM (start inclusive, end exclusive).
-    pub fn read_bytes(&self, start: u16, end: u16) -> Result<Vec<u8>, MemoryError> {
+    pub fn read_bytes(&mut self, start: u16, end: u16) -> Result<&[u8], MemoryError> {
+        if let Some(coverage) = &mut self.coverage
+            && start <= end
+        {
+            (start..end).for_each(|addr| coverage.mark_read(addr));
+        }
         let start = start as usize;
         let end = end as usize;
         if start <= end && end <= MEMORY_SIZE {
-            Ok(self.ram[start..end].to_vec())
+            Ok(&self.ram[start..end])
         } else {
             Err(MemoryError::OutOfBoundsReadRange {
                 start: start as u16,
@@ -76,6 +129,67 @@ impl Memory {
         }
     }
 
+    // Read the opcode at the given address.
+    pub fn fetch_opcode(&mut self, addr: u16) -> Result<u16, MemoryError> {
+        if (addr as usize) + 1 < MEMORY_SIZE {
+            if let Some(coverage) = &mut self.coverage {
+                coverage.mark_executed(addr);
+                coverage.mark_executed(addr + 1);
+            }
+            Ok(u16::from_be_bytes([
+                self.ram[addr as usize],
+                self.ram[addr as usize + 1],
+            ]))
+        } else {
+            // The first byte outside RAM.
+            let addr = if (addr as usize) < MEMORY_SIZE {
+                addr + 1
+            } else {
+                addr
+            };
+            Err(MemoryError::OutOfBoundsRead { addr })
+        }
+    }
+
+    // Start (or stop) recording RAM coverage.
+    pub fn set_coverage_enabled(&mut self, enabled: bool) {
+        match (enabled, self.coverage.is_some()) {
+            (true, false) => self.coverage = Some(Coverage::new()),
+            (false, true) => self.coverage = None,
+            _ => {}
+        }
+    }
+
+    pub fn get_coverage(&self) -> Option<&Coverage> {
+        self.coverage.as_ref()
+    }
+
+    pub fn get_coverage_mut(&mut self) -> Option<&mut Coverage> {
+        self.coverage.as_mut()
+    }
+
+    // Remember the size of the ROM just loaded, for the memory map.
+    pub fn set_program_len(&mut self, len: usize) {
+        self.program_len = len;
+    }
+
+    pub fn memory_map(&self) -> Vec<Region> {
+        memory_map(self.program_len)
+    }
+
+    pub fn get_write_protection(&self) -> WriteProtection {
+        self.write_protection
+    }
+
+    pub fn set_write_protection(&mut self, protection: WriteProtection) {
+        self.write_protection = protection;
+    }
+
+    // Get a reference to the whole of RAM.
+    pub fn get_ram(&self) -> &[u8; MEMORY_SIZE] {
+        &self.ram
+    }
+
     // Push a value onto the stack at the given stack pointer index.
     // Caller should increment sp **after** calling.
     pub fn push_stack(&mut self, sp: usize, value: u16) -> Result<(), MemoryError> {
@@ -97,6 +211,26 @@ impl Memory {
         }
     }
 
+    // Write RAM, the stack and the length of the loaded program to a save state.
+    pub fn save_state(&self, writer: &mut StateWriter) {
+        writer.write_bytes(&self.ram);
+        for &value in &self.stack {
+            writer.write_u16(value);
+        }
+        writer.write_u16(self.program_len as u16);
+    }
+
+    // Restore RAM, the stack and the length of the loaded program from a save state.
+    pub fn load_state(&mut self, reader: &mut StateReader) -> Result<(), StateError> {
+        self.ram.copy_from_slice(reader.read_bytes(MEMORY_SIZE)?);
+        for value in &mut self.stack {
+            *value = reader.read_u16()?;
+        }
+        // A program can't extend past the end of RAM.
+        self.program_len = (reader.read_u16()? as usize).min(MEMORY_SIZE - PROGRAM_START as usize);
+        Ok(())
+    }
+
     // Helper function to load the CHIP-8 fontset into the beginning of RAM.
     fn load_fontset(&mut self) {
         self.ram[..FONTSET_SIZE].copy_from_slice(&FONTSET);
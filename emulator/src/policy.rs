@@ -0,0 +1,229 @@
+use core::{fmt, str::FromStr};
+
+use crate::error::{CpuError, MemoryError};
+
+// What to do when an instruction fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ErrorAction {
+    // Stop: `Emulator::cycle` returns the error.
+    #[default]
+    Halt,
+    // Carry on with the next instruction, as if the failing one were a NOP.
+    Skip,
+    // Like `Skip`, but also log a warning.
+    Log,
+}
+
+impl ErrorAction {
+    pub const ALL: [ErrorAction; 3] = [ErrorAction::Halt, ErrorAction::Skip, ErrorAction::Log];
+
+    // Short name used in configuration files and on the command line.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ErrorAction::Halt => "halt",
+            ErrorAction::Skip => "skip",
+            ErrorAction::Log => "log",
+        }
+    }
+}
+
+impl fmt::Display for ErrorAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+// Error returned when parsing an unknown error action.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownErrorAction;
+
+impl fmt::Display for UnknownErrorAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unknown error action (expected halt, skip or log)")
+    }
+}
+
+impl core::error::Error for UnknownErrorAction {}
+
+impl FromStr for ErrorAction {
+    type Err = UnknownErrorAction;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ErrorAction::ALL
+            .into_iter()
+            .find(|action| action.name().eq_ignore_ascii_case(s))
+            .ok_or(UnknownErrorAction)
+    }
+}
+
+// The kinds of CPU error an `ErrorPolicy` distinguishes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    // An opcode the CPU doesn't implement.
+    UnknownOpcode,
+    // A call with a full stack, or a return with an empty one.
+    Stack,
+    // A fetch, jump or I-relative access outside of RAM.
+    Memory,
+    // EX9E / EXA1 with a key index above F.
+    Key,
+}
+
+impl ErrorKind {
+    pub const ALL: [ErrorKind; 4] = [
+        ErrorKind::UnknownOpcode,
+        ErrorKind::Stack,
+        ErrorKind::Memory,
+        ErrorKind::Key,
+    ];
+
+    // Short name used in configuration files and on the command line.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ErrorKind::UnknownOpcode => "unknown-opcode",
+            ErrorKind::Stack => "stack",
+            ErrorKind::Memory => "memory",
+            ErrorKind::Key => "key",
+        }
+    }
+
+    // The kind of a CPU error.
+    pub fn of(error: &CpuError) -> Self {
+        match error {
+            CpuError::UnimplementedOpcode { .. } => ErrorKind::UnknownOpcode,
+            CpuError::ReturnWithEmptyStack { .. }
+            | CpuError::Memory {
+                source: MemoryError::StackOverflow { .. } | MemoryError::StackUnderflow { .. },
+            } => ErrorKind::Stack,
+            CpuError::Memory { .. }
+            | CpuError::PcOutOfRange { .. }
+            | CpuError::IndexOutOfRange { .. } => ErrorKind::Memory,
+            CpuError::Keypad { .. } => ErrorKind::Key,
+        }
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+// Error returned when parsing an unknown error kind.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownErrorKind;
+
+impl fmt::Display for UnknownErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unknown error kind (expected unknown-opcode, stack, memory or key)")
+    }
+}
+
+impl core::error::Error for UnknownErrorKind {}
+
+impl FromStr for ErrorKind {
+    type Err = UnknownErrorKind;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ErrorKind::ALL
+            .into_iter()
+            .find(|kind| kind.name().eq_ignore_ascii_case(s))
+            .ok_or(UnknownErrorKind)
+    }
+}
+
+// What to do for each kind of CPU error. Some ROMs run into data by accident or use opcodes of
+// other platforms, and keep working if those instructions are skipped. The default halts on every
+// error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ErrorPolicy {
+    pub unknown_opcode: ErrorAction,
+    pub stack: ErrorAction,
+    pub memory: ErrorAction,
+    pub key: ErrorAction,
+}
+
+impl ErrorPolicy {
+    // The same action for every kind of error.
+    pub fn all(action: ErrorAction) -> Self {
+        Self {
+            unknown_opcode: action,
+            stack: action,
+            memory: action,
+            key: action,
+        }
+    }
+
+    pub fn action(&self, kind: ErrorKind) -> ErrorAction {
+        match kind {
+            ErrorKind::UnknownOpcode => self.unknown_opcode,
+            ErrorKind::Stack => self.stack,
+            ErrorKind::Memory => self.memory,
+            ErrorKind::Key => self.key,
+        }
+    }
+
+    pub fn set_action(&mut self, kind: ErrorKind, action: ErrorAction) {
+        match kind {
+            ErrorKind::UnknownOpcode => self.unknown_opcode = action,
+            ErrorKind::Stack => self.stack = action,
+            ErrorKind::Memory => self.memory = action,
+            ErrorKind::Key => self.key = action,
+        }
+    }
+}
+
+// Number of errors of each kind that were skipped (or logged) instead of halting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ErrorCounts {
+    pub unknown_opcode: u64,
+    pub stack: u64,
+    pub memory: u64,
+    pub key: u64,
+}
+
+impl ErrorCounts {
+    pub fn get(&self, kind: ErrorKind) -> u64 {
+        match kind {
+            ErrorKind::UnknownOpcode => self.unknown_opcode,
+            ErrorKind::Stack => self.stack,
+            ErrorKind::Memory => self.memory,
+            ErrorKind::Key => self.key,
+        }
+    }
+
+    pub fn total(&self) -> u64 {
+        self.unknown_opcode + self.stack + self.memory + self.key
+    }
+
+    pub(crate) fn add(&mut self, kind: ErrorKind) {
+        let count = match kind {
+            ErrorKind::UnknownOpcode => &mut self.unknown_opcode,
+            ErrorKind::Stack => &mut self.stack,
+            ErrorKind::Memory => &mut self.memory,
+            ErrorKind::Key => &mut self.key,
+        };
+        *count += 1;
+    }
+
+    // The counts added since `earlier` was taken.
+    pub(crate) fn since(&self, earlier: &ErrorCounts) -> ErrorCounts {
+        ErrorCounts {
+            unknown_opcode: self.unknown_opcode - earlier.unknown_opcode,
+            stack: self.stack - earlier.stack,
+            memory: self.memory - earlier.memory,
+            key: self.key - earlier.key,
+        }
+    }
+}
+
+// What happened during the last `Emulator::run_frame`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FrameReport {
+    // Instructions executed, including skipped ones and the one that halted the frame.
+    pub cycles: usize,
+    // Errors skipped during the frame.
+    pub skipped: ErrorCounts,
+    // Errors skipped since the emulator was created or last reset.
+    pub total_skipped: ErrorCounts,
+}
@@ -0,0 +1,89 @@
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+use crate::constants::{MEMORY_SIZE, PROGRAM_START, STACK_SIZE};
+
+// Execution counts per address and per call stack, collected while profiling is enabled.
+//
+// Call stacks are tracked from the 2NNN / 00EE opcodes as lists of subroutine entry addresses,
+// starting with the program start, e.g. `[0x200, 0x2A0, 0x2B4]` for code in a subroutine at
+// 0x2B4 called from one at 0x2A0.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profile {
+    counts: Vec<u64>,
+    stacks: BTreeMap<Vec<u16>, u64>,
+    stack: Vec<u16>,
+    total: u64,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Profile {
+    pub fn new() -> Self {
+        Self {
+            counts: alloc::vec![0; MEMORY_SIZE],
+            stacks: BTreeMap::new(),
+            stack: alloc::vec![PROGRAM_START],
+            total: 0,
+        }
+    }
+
+    // Number of instructions executed while profiling.
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    // How often the instruction at `addr` was executed.
+    pub fn count(&self, addr: u16) -> u64 {
+        self.counts.get(addr as usize).copied().unwrap_or(0)
+    }
+
+    // Addresses that were executed, with their counts.
+    pub fn address_counts(&self) -> impl Iterator<Item = (u16, u64)> + '_ {
+        self.counts
+            .iter()
+            .enumerate()
+            .filter(|&(_, &count)| count > 0)
+            .map(|(addr, &count)| (addr as u16, count))
+    }
+
+    // Instructions executed with each call stack (outermost first), not counting those executed
+    // in deeper calls.
+    pub fn stack_counts(&self) -> impl Iterator<Item = (&[u16], u64)> + '_ {
+        self.stacks
+            .iter()
+            .map(|(stack, &count)| (stack.as_slice(), count))
+    }
+
+    // Record the execution of `opcode` at `addr`.
+    pub(crate) fn record(&mut self, addr: u16, opcode: u16) {
+        self.total += 1;
+        if let Some(count) = self.counts.get_mut(addr as usize) {
+            *count += 1;
+        }
+        match self.stacks.get_mut(self.stack.as_slice()) {
+            Some(count) => *count += 1,
+            None => {
+                self.stacks.insert(self.stack.clone(), 1);
+            }
+        }
+
+        // The CPU stops at STACK_SIZE nested calls, so deeper stacks are never executed.
+        match opcode & 0xF000 {
+            0x2000 if self.stack.len() <= STACK_SIZE => self.stack.push(opcode & 0x0FFF),
+            0x0000 if opcode == 0x00EE && self.stack.len() > 1 => {
+                self.stack.pop();
+            }
+            _ => {}
+        }
+    }
+
+    // Forget the current call stack (after a reset or when loading a state), keeping the counts.
+    pub(crate) fn restart(&mut self) {
+        self.stack.truncate(1);
+    }
+}
@@ -0,0 +1,120 @@
+use core::{fmt, str::FromStr};
+
+// Behaviors that differ between CHIP-8 interpreters. ROMs written for one platform often
+// misbehave on another, so these can be switched per ROM.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quirks {
+    // 8XY6 / 8XYE shift VY into VX (otherwise VX is shifted in place).
+    pub shift_vy: bool,
+    // FX55 / FX65 leave I pointing past the last register (otherwise I is unchanged).
+    pub increment_i: bool,
+    // 8XY1 / 8XY2 / 8XY3 reset VF to 0.
+    pub vf_reset: bool,
+    // BXNN jumps to XNN + VX (otherwise BNNN jumps to NNN + V0).
+    pub jump_vx: bool,
+    // Sprites wrap around the screen edges (otherwise they are clipped).
+    pub wrap_sprites: bool,
+}
+
+// The default quirks are the behavior this emulator has always had.
+impl Default for Quirks {
+    fn default() -> Self {
+        Self {
+            shift_vy: true,
+            increment_i: true,
+            vf_reset: false,
+            jump_vx: false,
+            wrap_sprites: false,
+        }
+    }
+}
+
+// Platforms with well known quirk sets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Platform {
+    // The original COSMAC VIP interpreter.
+    #[default]
+    Chip8,
+    // SUPER-CHIP 1.1 on the HP 48.
+    SuperChip,
+    // Octo's XO-CHIP.
+    XoChip,
+}
+
+impl Platform {
+    pub const ALL: [Platform; 3] = [Platform::Chip8, Platform::SuperChip, Platform::XoChip];
+
+    // Short name used in configuration files and on the command line.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Platform::Chip8 => "chip8",
+            Platform::SuperChip => "superchip",
+            Platform::XoChip => "xochip",
+        }
+    }
+
+    // Name as the platform is usually written, e.g. `SUPER-CHIP`.
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Platform::Chip8 => "CHIP-8",
+            Platform::SuperChip => "SUPER-CHIP",
+            Platform::XoChip => "XO-CHIP",
+        }
+    }
+
+    // The quirks expected by ROMs written for this platform.
+    pub fn quirks(&self) -> Quirks {
+        match self {
+            Platform::Chip8 => Quirks {
+                shift_vy: true,
+                increment_i: true,
+                vf_reset: true,
+                jump_vx: false,
+                wrap_sprites: false,
+            },
+            Platform::SuperChip => Quirks {
+                shift_vy: false,
+                increment_i: false,
+                vf_reset: false,
+                jump_vx: true,
+                wrap_sprites: false,
+            },
+            Platform::XoChip => Quirks {
+                shift_vy: true,
+                increment_i: true,
+                vf_reset: false,
+                jump_vx: false,
+                wrap_sprites: true,
+            },
+        }
+    }
+}
+
+impl fmt::Display for Platform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+// Error returned when parsing an unknown platform name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownPlatform;
+
+impl fmt::Display for UnknownPlatform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unknown platform (expected chip8, superchip or xochip)")
+    }
+}
+
+impl core::error::Error for UnknownPlatform {}
+
+impl FromStr for Platform {
+    type Err = UnknownPlatform;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Platform::ALL
+            .into_iter()
+            .find(|platform| platform.name().eq_ignore_ascii_case(s))
+            .ok_or(UnknownPlatform)
+    }
+}
@@ -0,0 +1,151 @@
+use alloc::vec::Vec;
+use core::{fmt, str::FromStr};
+
+use crate::constants::{FONTSET_SIZE, MEMORY_SIZE, PROGRAM_START};
+
+// The parts RAM is divided into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegionKind {
+    // The hex digit sprites, from 0x000.
+    Font,
+    // The rest of the area below 0x200, where the original interpreter lived.
+    Reserved,
+    // The loaded ROM.
+    Program,
+    // Everything after the ROM.
+    Free,
+}
+
+impl RegionKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            RegionKind::Font => "font",
+            RegionKind::Reserved => "reserved",
+            RegionKind::Program => "program",
+            RegionKind::Free => "free",
+        }
+    }
+
+    // Whether write protection applies to the region (the font and the interpreter area).
+    pub fn is_protected(&self) -> bool {
+        matches!(self, RegionKind::Font | RegionKind::Reserved)
+    }
+
+    // The kind of region an address is in, with a ROM of `program_len` bytes loaded.
+    pub fn at(addr: u16, program_len: usize) -> Self {
+        let addr = addr as usize;
+        if addr < FONTSET_SIZE {
+            RegionKind::Font
+        } else if addr < PROGRAM_START as usize {
+            RegionKind::Reserved
+        } else if addr < PROGRAM_START as usize + program_len {
+            RegionKind::Program
+        } else {
+            RegionKind::Free
+        }
+    }
+}
+
+impl fmt::Display for RegionKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+// A range of RAM (`start` inclusive, `end` exclusive).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Region {
+    pub kind: RegionKind,
+    pub start: u16,
+    pub end: u16,
+}
+
+impl Region {
+    pub fn len(&self) -> usize {
+        (self.end - self.start) as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+}
+
+// The regions of RAM in address order, with a ROM of `program_len` bytes loaded. Empty regions
+// (no ROM, or a ROM filling the rest of RAM) are left out.
+pub fn memory_map(program_len: usize) -> Vec<Region> {
+    let program_end = (PROGRAM_START as usize + program_len).min(MEMORY_SIZE);
+    let bounds = [
+        (RegionKind::Font, 0, FONTSET_SIZE),
+        (RegionKind::Reserved, FONTSET_SIZE, PROGRAM_START as usize),
+        (RegionKind::Program, PROGRAM_START as usize, program_end),
+        (RegionKind::Free, program_end, MEMORY_SIZE),
+    ];
+    bounds
+        .into_iter()
+        .map(|(kind, start, end)| Region {
+            kind,
+            start: start as u16,
+            end: end as u16,
+        })
+        .filter(|region| !region.is_empty())
+        .collect()
+}
+
+// What happens when the program writes to the font or the reserved area below 0x200 (with FX33 or
+// FX55). Some ROMs do this on purpose, so it is allowed by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WriteProtection {
+    // Allow the write.
+    #[default]
+    Off,
+    // Allow the write, and log a warning.
+    Warn,
+    // Refuse the write with `MemoryError::ProtectedWrite`.
+    Error,
+}
+
+impl WriteProtection {
+    pub const ALL: [WriteProtection; 3] = [
+        WriteProtection::Off,
+        WriteProtection::Warn,
+        WriteProtection::Error,
+    ];
+
+    // Short name used in configuration files and on the command line.
+    pub fn name(&self) -> &'static str {
+        match self {
+            WriteProtection::Off => "off",
+            WriteProtection::Warn => "warn",
+            WriteProtection::Error => "error",
+        }
+    }
+}
+
+impl fmt::Display for WriteProtection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+// Error returned when parsing an unknown write protection mode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownWriteProtection;
+
+impl fmt::Display for UnknownWriteProtection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unknown write protection (expected off, warn or error)")
+    }
+}
+
+impl core::error::Error for UnknownWriteProtection {}
+
+impl FromStr for WriteProtection {
+    type Err = UnknownWriteProtection;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        WriteProtection::ALL
+            .into_iter()
+            .find(|protection| protection.name().eq_ignore_ascii_case(s))
+            .ok_or(UnknownWriteProtection)
+    }
+}
@@ -0,0 +1,87 @@
+use alloc::vec::Vec;
+
+use crate::{constants::STATE_SIZE, error::StateError};
+
+// Magic bytes and format version at the start of every save state.
+pub const STATE_MAGIC: [u8; 4] = *b"CH8S";
+pub const STATE_VERSION: u8 = 2;
+
+// Serializes emulator components into a save state buffer (big-endian).
+pub struct StateWriter {
+    buf: Vec<u8>,
+}
+
+impl StateWriter {
+    // Create a new writer, with the magic bytes and version already written.
+    pub fn new() -> Self {
+        let mut buf = Vec::with_capacity(STATE_SIZE);
+        buf.extend_from_slice(&STATE_MAGIC);
+        buf.push(STATE_VERSION);
+        Self { buf }
+    }
+
+    pub fn write_u8(&mut self, value: u8) {
+        self.buf.push(value);
+    }
+
+    pub fn write_u16(&mut self, value: u16) {
+        self.buf.extend_from_slice(&value.to_be_bytes());
+    }
+
+    pub fn write_bytes(&mut self, data: &[u8]) {
+        self.buf.extend_from_slice(data);
+    }
+
+    // Consume the writer and return the finished save state.
+    pub fn finish(self) -> Vec<u8> {
+        debug_assert_eq!(self.buf.len(), STATE_SIZE);
+        self.buf
+    }
+}
+
+// Deserializes emulator components from a save state buffer.
+pub struct StateReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> StateReader<'a> {
+    // Create a new reader, validating the length, magic bytes and version of the save state.
+    pub fn new(data: &'a [u8]) -> Result<Self, StateError> {
+        if data.len() != STATE_SIZE {
+            return Err(StateError::InvalidLength {
+                expected: STATE_SIZE,
+                actual: data.len(),
+            });
+        }
+
+        let mut reader = Self { data };
+        if reader.read_bytes(STATE_MAGIC.len())? != STATE_MAGIC {
+            return Err(StateError::InvalidMagic);
+        }
+
+        let version = reader.read_u8()?;
+        if version != STATE_VERSION {
+            return Err(StateError::UnsupportedVersion { version });
+        }
+
+        Ok(reader)
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, StateError> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    pub fn read_u16(&mut self) -> Result<u16, StateError> {
+        let bytes = self.read_bytes(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    pub fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], StateError> {
+        if len > self.data.len() {
+            return Err(StateError::UnexpectedEnd);
+        }
+        let (bytes, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(bytes)
+    }
+}
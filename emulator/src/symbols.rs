@@ -0,0 +1,151 @@
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use core::fmt;
+
+use crate::disasm::Instruction;
+use crate::error::SymbolError;
+
+// Labels for addresses, e.g. from an assembler's symbol output, used to print `draw_paddle+0x4`
+// instead of raw addresses and to look up addresses by name.
+//
+// The text format has one symbol per line, as `name 0x2A0`, `name = 0x2A0` or `0x2A0 name`
+// (addresses in hex with `0x` or in decimal). `#` and `;` start comments.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SymbolTable {
+    names: BTreeMap<String, u16>,
+    addresses: BTreeMap<u16, String>,
+}
+
+impl SymbolTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn parse(text: &str) -> Result<Self, SymbolError> {
+        let mut symbols = Self::new();
+        for (idx, line) in text.lines().enumerate() {
+            let line = line.split(['#', ';']).next().unwrap_or_default();
+            let mut fields = line
+                .split(|c: char| c.is_whitespace() || c == '=')
+                .filter(|field| !field.is_empty());
+            let (Some(first), Some(second), None) = (fields.next(), fields.next(), fields.next())
+            else {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                return Err(SymbolError::InvalidLine { line: idx + 1 });
+            };
+
+            let (name, addr) = match (parse_address(first), parse_address(second)) {
+                (None, Some(addr)) => (first, addr),
+                (Some(addr), None) => (second, addr),
+                _ => return Err(SymbolError::InvalidLine { line: idx + 1 }),
+            };
+            if symbols.names.contains_key(name) {
+                return Err(SymbolError::DuplicateName {
+                    name: name.to_string(),
+                    line: idx + 1,
+                });
+            }
+            symbols.insert(name, addr);
+        }
+        Ok(symbols)
+    }
+
+    // Add a symbol. If an address has several names, the first one is used when printing it.
+    pub fn insert(&mut self, name: &str, addr: u16) {
+        self.names.insert(name.to_string(), addr);
+        self.addresses
+            .entry(addr)
+            .or_insert_with(|| name.to_string());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn address(&self, name: &str) -> Option<u16> {
+        self.names.get(name).copied()
+    }
+
+    // The name of the symbol at exactly `addr`.
+    pub fn name(&self, addr: u16) -> Option<&str> {
+        self.addresses.get(&addr).map(String::as_str)
+    }
+
+    // `addr` relative to the closest symbol at or before it (`draw_paddle+0x4`), or in hex if
+    // there is none.
+    pub fn describe(&self, addr: u16) -> String {
+        match self.addresses.range(..=addr).next_back() {
+            Some((&base, name)) if base == addr => name.clone(),
+            Some((&base, name)) => format!("{name}+{:#X}", addr - base),
+            None => format!("{addr:#05X}"),
+        }
+    }
+
+    // Parse a location given as an address (`0x2A4`), a symbol (`draw_paddle`) or a symbol
+    // with an offset (`draw_paddle+0x4`).
+    pub fn resolve(&self, location: &str) -> Result<u16, SymbolError> {
+        if let Some(addr) = parse_address(location) {
+            return Ok(addr);
+        }
+        let (name, offset) = match location.split_once('+') {
+            Some((name, offset)) => (
+                name,
+                parse_address(offset).ok_or_else(|| SymbolError::InvalidLocation {
+                    location: location.to_string(),
+                })?,
+            ),
+            None => (location, 0),
+        };
+        let addr = self
+            .address(name)
+            .ok_or_else(|| SymbolError::UnknownSymbol {
+                name: name.to_string(),
+            })?;
+        addr.checked_add(offset)
+            .ok_or_else(|| SymbolError::InvalidLocation {
+                location: location.to_string(),
+            })
+    }
+
+    // Format an instruction with its address operand (if any) described by symbols.
+    pub fn instruction(&self, instruction: Instruction) -> SymbolicInstruction<'_> {
+        SymbolicInstruction {
+            instruction,
+            symbols: self,
+        }
+    }
+}
+
+// An instruction displayed with symbols, see `SymbolTable::instruction`.
+pub struct SymbolicInstruction<'a> {
+    instruction: Instruction,
+    symbols: &'a SymbolTable,
+}
+
+impl fmt::Display for SymbolicInstruction<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let describe = |addr| self.symbols.describe(addr);
+        match self.instruction {
+            Instruction::Sys(nnn) => write!(f, "SYS {}", describe(nnn)),
+            Instruction::Jp(nnn) => write!(f, "JP {}", describe(nnn)),
+            Instruction::Call(nnn) => write!(f, "CALL {}", describe(nnn)),
+            Instruction::LdI(nnn) => write!(f, "LD I, {}", describe(nnn)),
+            Instruction::JpV0(nnn) => write!(f, "JP V0, {}", describe(nnn)),
+            instruction => write!(f, "{instruction}"),
+        }
+    }
+}
+
+// An address in hex (`0x2A0`) or decimal (`672`).
+fn parse_address(value: &str) -> Option<u16> {
+    match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => u16::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    }
+}
@@ -0,0 +1,309 @@
+// Property tests for the CPU: random states and opcodes are run both on the emulator and on the
+// reference interpreter in `reference/`, which must agree on registers, RAM and display after
+// every instruction, plus invariants of single instructions (carry, BCD, register dumps, calls).
+//
+// A failing case is shrunk and printed by proptest; failures are also saved to
+// `tests/cpu.proptest-regressions` so they are tried first on the next run.
+mod reference;
+
+use emulator::{
+    Emulator, Quirks, Registers,
+    constants::{DISPLAY_HEIGHT, DISPLAY_WIDTH, MEMORY_SIZE, STACK_SIZE},
+};
+use proptest::prelude::*;
+use reference::Reference;
+
+// Instructions run per case of the differential test.
+const MAX_STEPS: usize = 32;
+
+// (opcode, mask of the operand bits) of every instruction, so that most random opcodes are valid.
+const OPCODES: &[(u16, u16)] = &[
+    (0x00E0, 0x000),
+    (0x00EE, 0x000),
+    (0x1000, 0xFFF),
+    (0x2000, 0xFFF),
+    (0x3000, 0xFFF),
+    (0x4000, 0xFFF),
+    (0x5000, 0xFF0),
+    (0x6000, 0xFFF),
+    (0x7000, 0xFFF),
+    (0x8000, 0xFF0),
+    (0x8001, 0xFF0),
+    (0x8002, 0xFF0),
+    (0x8003, 0xFF0),
+    (0x8004, 0xFF0),
+    (0x8005, 0xFF0),
+    (0x8006, 0xFF0),
+    (0x8007, 0xFF0),
+    (0x800E, 0xFF0),
+    (0x9000, 0xFF0),
+    (0xA000, 0xFFF),
+    (0xB000, 0xFFF),
+    (0xC000, 0xFFF),
+    (0xD000, 0xFFF),
+    (0xE09E, 0xF00),
+    (0xE0A1, 0xF00),
+    (0xF007, 0xF00),
+    (0xF00A, 0xF00),
+    (0xF015, 0xF00),
+    (0xF018, 0xF00),
+    (0xF01E, 0xF00),
+    (0xF029, 0xF00),
+    (0xF033, 0xF00),
+    (0xF055, 0xF00),
+    (0xF065, 0xF00),
+];
+
+#[derive(Debug, Clone)]
+struct State {
+    quirks: Quirks,
+    registers: Registers,
+    ram: Vec<u8>,
+    keys: [bool; 16],
+}
+
+fn quirks() -> impl Strategy<Value = Quirks> {
+    any::<[bool; 5]>().prop_map(
+        |[shift_vy, increment_i, vf_reset, jump_vx, wrap_sprites]| Quirks {
+            shift_vy,
+            increment_i,
+            vf_reset,
+            jump_vx,
+            wrap_sprites,
+        },
+    )
+}
+
+// A random instruction, or now and then any 16-bit value.
+fn opcode() -> impl Strategy<Value = u16> {
+    prop_oneof![
+        4 => (proptest::sample::select(OPCODES), any::<u16>())
+            .prop_map(|((opcode, mask), operands)| opcode | operands & mask),
+        1 => any::<u16>(),
+    ]
+}
+
+// A random CPU state with `program` (big-endian opcodes) at the program counter.
+fn state() -> impl Strategy<Value = State> {
+    (
+        quirks(),
+        (0x200_u16..0xF00).prop_map(|pc| pc & !1),
+        0..=STACK_SIZE as u16,
+        any::<[u8; 16]>(),
+        0_u16..MEMORY_SIZE as u16,
+        any::<(u8, u8)>(),
+        proptest::collection::vec(any::<u8>(), MEMORY_SIZE),
+        any::<[bool; 16]>(),
+        proptest::collection::vec(opcode(), 1..=MAX_STEPS),
+    )
+        .prop_map(|(quirks, pc, sp, v, i, (dt, st), mut ram, keys, program)| {
+            for (idx, op) in program.iter().enumerate() {
+                let addr = pc as usize + idx * 2;
+                ram[addr..addr + 2].copy_from_slice(&op.to_be_bytes());
+            }
+            let registers = Registers {
+                pc,
+                sp,
+                v,
+                i,
+                dt,
+                st,
+            };
+            State {
+                quirks,
+                registers,
+                ram,
+                keys,
+            }
+        })
+}
+
+fn emulator(state: &State) -> Emulator {
+    let mut emu = Emulator::with_seed(0);
+    emu.set_quirks(state.quirks);
+    emu.write_ram(0, &state.ram).unwrap();
+    emu.set_registers(state.registers);
+    for (key, _) in state
+        .keys
+        .iter()
+        .enumerate()
+        .filter(|(_, pressed)| **pressed)
+    {
+        emu.press_key(key).unwrap();
+    }
+    emu
+}
+
+fn reference(state: &State) -> Reference {
+    let Registers {
+        pc,
+        sp,
+        v,
+        i,
+        dt,
+        st,
+    } = state.registers;
+    Reference {
+        pc,
+        sp,
+        stack: [0; STACK_SIZE],
+        v,
+        i,
+        dt,
+        st,
+        ram: state.ram.clone(),
+        display: [[false; DISPLAY_WIDTH]; DISPLAY_HEIGHT],
+        keys: state.keys,
+        quirks: state.quirks,
+    }
+}
+
+fn registers(reference: &Reference) -> Registers {
+    Registers {
+        pc: reference.pc,
+        sp: reference.sp,
+        v: reference.v,
+        i: reference.i,
+        dt: reference.dt,
+        st: reference.st,
+    }
+}
+
+// Run `ops` (at least one) from `pc` and return the emulator.
+fn run(registers: Registers, quirks: Quirks, ops: &[u16]) -> Emulator {
+    let mut emu = Emulator::with_seed(0);
+    emu.set_quirks(quirks);
+    let program: Vec<u8> = ops.iter().flat_map(|op| op.to_be_bytes()).collect();
+    emu.write_ram(registers.pc, &program).unwrap();
+    emu.set_registers(registers);
+    for _ in ops {
+        emu.cycle().unwrap();
+    }
+    emu
+}
+
+fn registers_at(pc: u16, v: [u8; 16], i: u16) -> Registers {
+    Registers {
+        pc,
+        sp: 0,
+        v,
+        i,
+        dt: 0,
+        st: 0,
+    }
+}
+
+proptest! {
+    #[test]
+    fn emulator_matches_reference(state in state()) {
+        let mut emu = emulator(&state);
+        let mut reference = reference(&state);
+
+        for step in 0..MAX_STEPS {
+            let pc = reference.pc as usize;
+            let op = match reference.ram.get(pc..pc + 2) {
+                Some(&[high, low]) => u16::from_be_bytes([high, low]),
+                _ => 0,
+            };
+            let (actual, expected) = if op >> 12 == 0xC {
+                // CXNN: the random number can't be predicted, only that it is masked by NN.
+                let x = (op >> 8 & 0xF) as usize;
+                let actual = emu.cycle();
+                let random = emu.get_registers().v[x];
+                prop_assert_eq!(random & !(op as u8), 0, "step {}: {:04X}", step, op);
+                reference.v[x] = random;
+                reference.pc += 2;
+                (actual, Ok(()))
+            } else {
+                (emu.cycle(), reference.step())
+            };
+            prop_assert_eq!(
+                actual.is_ok(),
+                expected.is_ok(),
+                "step {}: {:04X} gave {:?}",
+                step,
+                op,
+                actual
+            );
+            if actual.is_err() {
+                break;
+            }
+            prop_assert_eq!(emu.get_registers(), registers(&reference), "step {}: {:04X}", step, op);
+            prop_assert!(emu.get_ram()[..] == reference.ram[..], "step {}: {:04X}: RAM differs", step, op);
+            prop_assert!(
+                *emu.get_display_buffer() == reference.display,
+                "step {}: {:04X}: display differs",
+                step,
+                op
+            );
+        }
+    }
+
+    #[test]
+    fn add_sets_carry(x in 0_u16..0xF, y in 0_u16..0xF, vx: u8, vy: u8) {
+        prop_assume!(x != y);
+        let mut v = [0; 16];
+        v[x as usize] = vx;
+        v[y as usize] = vy;
+        let emu = run(registers_at(0x200, v, 0), Quirks::default(), &[0x8004 | x << 8 | y << 4]);
+
+        let registers = emu.get_registers();
+        let sum = vx as u16 + vy as u16;
+        prop_assert_eq!(registers.v[x as usize] as u16, sum & 0xFF);
+        prop_assert_eq!(registers.v[0xF], (sum > 0xFF) as u8);
+    }
+
+    #[test]
+    fn bcd_digits_make_up_the_value(x in 0_u16..16, vx: u8, i in 0_u16..MEMORY_SIZE as u16 - 2) {
+        let mut v = [0; 16];
+        v[x as usize] = vx;
+        let emu = run(registers_at(0x200, v, i), Quirks::default(), &[0xF033 | x << 8]);
+
+        let digits = &emu.get_ram()[i as usize..i as usize + 3];
+        prop_assert!(digits.iter().all(|&digit| digit < 10));
+        let value = digits[0] as u16 * 100 + digits[1] as u16 * 10 + digits[2] as u16;
+        prop_assert_eq!(value, vx as u16);
+    }
+
+    #[test]
+    fn store_and_load_registers_round_trip(
+        quirks in quirks(),
+        x in 0_u16..16,
+        v: [u8; 16],
+        i in 0x300_u16..(MEMORY_SIZE - 16) as u16,
+    ) {
+        // FX55, then FX65 from the same address after clearing the registers.
+        let mut emu = run(registers_at(0x200, v, i), quirks, &[0xF055 | x << 8]);
+        let stored = emu.get_registers();
+        let expected_i = if quirks.increment_i { i + x + 1 } else { i };
+        prop_assert_eq!(stored.i, expected_i);
+        prop_assert_eq!(&emu.get_ram()[i as usize..=(i + x) as usize], &v[..=x as usize]);
+
+        emu.write_ram(0x202, &(0xF065 | x << 8).to_be_bytes()).unwrap();
+        emu.set_registers(registers_at(0x202, [0; 16], i));
+        emu.cycle().unwrap();
+        let loaded = emu.get_registers();
+        prop_assert_eq!(&loaded.v[..=x as usize], &v[..=x as usize]);
+        prop_assert!(loaded.v[x as usize + 1..].iter().all(|&value| value == 0));
+        prop_assert_eq!(loaded.i, expected_i);
+    }
+
+    #[test]
+    fn call_and_return_are_symmetric(
+        sp in 0_u16..STACK_SIZE as u16,
+        pc in (0x200_u16..0x800).prop_map(|pc| pc & !1),
+        target in (0x900_u16..0xFFE).prop_map(|target| target & !1),
+    ) {
+        let mut emu = Emulator::with_seed(0);
+        emu.write_ram(pc, &(0x2000 | target).to_be_bytes()).unwrap();
+        emu.write_ram(target, &[0x00, 0xEE]).unwrap();
+        emu.set_registers(Registers { sp, ..registers_at(pc, [0; 16], 0) });
+
+        emu.cycle().unwrap();
+        let called = emu.get_registers();
+        prop_assert_eq!((called.pc, called.sp), (target, sp + 1));
+        emu.cycle().unwrap();
+        let returned = emu.get_registers();
+        prop_assert_eq!((returned.pc, returned.sp), (pc + 2, sp));
+    }
+}
@@ -0,0 +1,522 @@
+// These tests only use the core API, so they are run both with the default features and with
+// `--no-default-features` (no_std) to make sure the two configurations behave the same.
+use emulator::{
+    Access, CfgWarning, ControlFlowGraph, CpuError, EdgeKind, Emulator, EmulatorError, ErrorAction,
+    ErrorKind, ErrorPolicy, Instruction, MemoryError, Palette, Platform, Quirks, Region,
+    RegionKind, SymbolError, SymbolTable, WriteProtection, analyze_rom,
+    constants::{DISPLAY_HEIGHT, DISPLAY_WIDTH, STATE_SIZE},
+    disassemble,
+};
+
+const IBM_LOGO: &[u8] = include_bytes!("../../test_roms/IBM Logo.ch8");
+
+// Count the number of pixels that are turned on in the display buffer.
+fn lit_pixels(emu: &Emulator) -> usize {
+    emu.get_display_buffer()
+        .iter()
+        .flatten()
+        .filter(|&&pixel| pixel)
+        .count()
+}
+
+#[test]
+fn ibm_logo_draws_to_display() {
+    let mut emu = Emulator::new();
+    emu.load_rom(IBM_LOGO).unwrap();
+    assert_eq!(lit_pixels(&emu), 0);
+
+    for _ in 0..1000 {
+        emu.cycle().unwrap();
+    }
+
+    let lit = lit_pixels(&emu);
+    assert!(lit > 0 && lit < DISPLAY_WIDTH * DISPLAY_HEIGHT);
+}
+
+#[test]
+fn seeded_emulators_are_deterministic() {
+    // 6000 -> V0 = 0, A20C -> I = 0x20C, C0FF -> V0 = random, F029 -> I = sprite for V0,
+    // D005 -> draw it at (0, 0), 1208 -> loop forever.
+    let rom = [
+        0x60, 0x00, 0xA2, 0x0C, 0xC0, 0xFF, 0xF0, 0x29, 0xD0, 0x05, 0x12, 0x08,
+    ];
+
+    let mut first = Emulator::with_seed(42);
+    let mut second = Emulator::with_seed(42);
+    first.load_rom(&rom).unwrap();
+    second.load_rom(&rom).unwrap();
+
+    for _ in 0..6 {
+        first.cycle().unwrap();
+        second.cycle().unwrap();
+    }
+
+    assert_eq!(first.get_display_buffer(), second.get_display_buffer());
+}
+
+// The CPU error a cycle failed with.
+fn cpu_error(result: Result<(), EmulatorError>) -> CpuError {
+    match result {
+        Err(EmulatorError::Cpu { source }) => source.error,
+        other => panic!("expected a CPU error, got {other:?}"),
+    }
+}
+
+#[test]
+fn unimplemented_opcode_is_an_error() {
+    let mut emu = Emulator::new();
+    emu.load_rom(&[0xFF, 0xFF]).unwrap();
+    assert!(emu.cycle().is_err());
+}
+
+#[test]
+fn cpu_errors_describe_the_crash() {
+    // 6A2A -> VA = 0x2A, 00E0 -> clear, FFFF -> unimplemented, 1200 -> jump back.
+    let mut emu = Emulator::new();
+    emu.load_rom(&[0x6A, 0x2A, 0x00, 0xE0, 0xFF, 0xFF, 0x12, 0x00])
+        .unwrap();
+    emu.cycle().unwrap();
+    emu.cycle().unwrap();
+    let Err(EmulatorError::Cpu { source: crash }) = emu.cycle() else {
+        panic!("expected a CPU error");
+    };
+
+    assert!(matches!(
+        crash.error,
+        CpuError::UnimplementedOpcode { opcode: 0xFFFF }
+    ));
+    assert_eq!((crash.pc, crash.opcode), (0x204, Some(0xFFFF)));
+    assert_eq!(crash.registers.v[0xA], 0x2A);
+    assert!(crash.listing.starts_with("1FC  0000  "));
+    let line = crash.line.clone().unwrap();
+    assert!(crash.listing[line].starts_with("204  FFFF  "));
+    assert!(crash.listing.contains("206  1200  JP 0x200"));
+    assert!(crash.registers_summary().contains("VA=2A"));
+}
+
+#[test]
+fn return_with_empty_stack_is_an_error() {
+    let mut emu = Emulator::new();
+    emu.load_rom(&[0x00, 0xE0, 0x00, 0xEE]).unwrap();
+    emu.cycle().unwrap();
+    assert!(matches!(
+        cpu_error(emu.cycle()),
+        CpuError::ReturnWithEmptyStack {
+            pc: 0x202,
+            opcode: 0x00EE
+        }
+    ));
+}
+
+#[test]
+fn sprite_past_the_end_of_the_address_space_is_an_error() {
+    // AFFF -> I = 0xFFF, 60FF -> V0 = 0xFF, 61EA -> V1 = 0xEA, then F01E (I += V0) 240 times and
+    // F11E (I += V1) to get I = 0xFFF9, and D00F -> draw 15 bytes from I.
+    let mut rom = vec![0xAF, 0xFF, 0x60, 0xFF, 0x61, 0xEA];
+    rom.extend([0xF0, 0x1E].repeat(240));
+    rom.extend([0xF1, 0x1E, 0xD0, 0x0F]);
+    let mut emu = Emulator::new();
+    emu.load_rom(&rom).unwrap();
+    for _ in 0..244 {
+        emu.cycle().unwrap();
+    }
+    assert_eq!(emu.get_registers().i, 0xFFF9);
+    assert!(matches!(
+        cpu_error(emu.cycle()),
+        CpuError::IndexOutOfRange {
+            pc: 0x3E8,
+            opcode: 0xD00F,
+            i: 0xFFF9
+        }
+    ));
+}
+
+#[test]
+fn bcd_past_the_end_of_memory_is_an_error() {
+    // AFFE -> I = 0xFFE, F033 -> store 3 digits from I.
+    let mut emu = Emulator::new();
+    emu.load_rom(&[0xAF, 0xFE, 0xF0, 0x33]).unwrap();
+    emu.cycle().unwrap();
+    assert!(matches!(
+        cpu_error(emu.cycle()),
+        CpuError::IndexOutOfRange { i: 0xFFE, .. }
+    ));
+    assert_eq!(emu.get_ram()[0xFFE..], [0, 0]);
+}
+
+#[test]
+fn leaving_memory_is_an_error() {
+    // 1FFE -> jump to the last instruction, 3000 -> skip it (V0 = 0).
+    let mut emu = Emulator::new();
+    emu.load_rom(&[0x1F, 0xFE]).unwrap();
+    emu.write_ram(0xFFE, &[0x30, 0x00]).unwrap();
+    emu.cycle().unwrap();
+    assert!(matches!(
+        cpu_error(emu.cycle()),
+        CpuError::PcOutOfRange {
+            pc: 0xFFE,
+            opcode: 0x3000,
+            target: 0x1002
+        }
+    ));
+}
+
+#[test]
+fn error_policy_skips_errors_and_counts_them() {
+    // FFFF -> unknown, 6042 -> V0 = 0x42, 00EE -> return with an empty stack.
+    let mut emu = Emulator::new();
+    emu.load_rom(&[0xFF, 0xFF, 0x60, 0x42, 0x00, 0xEE]).unwrap();
+    let mut policy = ErrorPolicy::default();
+    policy.set_action(
+        "unknown-opcode".parse::<ErrorKind>().unwrap(),
+        "skip".parse::<ErrorAction>().unwrap(),
+    );
+    emu.set_error_policy(policy);
+
+    emu.run_frame(2).unwrap();
+    let report = emu.frame_report();
+    assert_eq!(report.cycles, 2);
+    assert_eq!(report.skipped.unknown_opcode, 1);
+    assert_eq!(report.skipped.total(), 1);
+    assert_eq!(emu.get_registers().v[0], 0x42);
+
+    // Stack errors still halt, and the report covers the frame up to the failing instruction.
+    assert!(matches!(
+        cpu_error(emu.run_frame(3)),
+        CpuError::ReturnWithEmptyStack { pc: 0x204, .. }
+    ));
+    let report = emu.frame_report();
+    assert_eq!(report.cycles, 1);
+    assert_eq!(report.skipped.total(), 0);
+    assert_eq!(report.total_skipped.unknown_opcode, 1);
+
+    emu.reset();
+    assert_eq!(emu.get_skipped_errors().total(), 0);
+    assert_eq!(emu.get_error_policy(), policy);
+}
+
+#[test]
+fn skipping_an_error_keeps_the_program_counter_in_memory() {
+    // The same jump off the end as above, skipped: execution carries on at the start of memory.
+    let mut emu = Emulator::new();
+    emu.set_error_policy(ErrorPolicy::all(ErrorAction::Skip));
+    emu.load_rom(&[0x1F, 0xFE]).unwrap();
+    emu.write_ram(0xFFE, &[0x30, 0x00]).unwrap();
+    emu.run_frame(2).unwrap();
+    assert_eq!(emu.get_registers().pc, 0x002);
+    assert_eq!(emu.get_skipped_errors().memory, 1);
+}
+
+#[test]
+fn memory_map_follows_the_loaded_rom() {
+    let mut emu = Emulator::new();
+    emu.load_rom(IBM_LOGO).unwrap();
+    let region = |kind, start, end| Region { kind, start, end };
+    assert_eq!(
+        emu.memory_map(),
+        [
+            region(RegionKind::Font, 0x000, 0x050),
+            region(RegionKind::Reserved, 0x050, 0x200),
+            region(RegionKind::Program, 0x200, 0x285),
+            region(RegionKind::Free, 0x285, 0x1000),
+        ]
+    );
+    emu.reset();
+    assert_eq!(emu.memory_map().len(), 3);
+}
+
+#[test]
+fn write_protection_guards_the_reserved_area() {
+    // A100 -> I = 0x100, F033 -> BCD of V0 at I.
+    let rom = [0xA1, 0x00, 0xF0, 0x33];
+    let mut emu = Emulator::new();
+    emu.load_rom(&rom).unwrap();
+    emu.run_frame(2).unwrap();
+
+    emu.reset();
+    emu.load_rom(&rom).unwrap();
+    emu.set_write_protection("error".parse::<WriteProtection>().unwrap());
+    emu.cycle().unwrap();
+    assert!(matches!(
+        cpu_error(emu.cycle()),
+        CpuError::Memory {
+            source: MemoryError::ProtectedWrite {
+                addr: 0x100,
+                region: RegionKind::Reserved
+            }
+        }
+    ));
+
+    // Writes to the program itself are allowed.
+    emu.reset();
+    emu.load_rom(&[0xA2, 0x00, 0xF0, 0x33]).unwrap();
+    emu.run_frame(2).unwrap();
+    assert_eq!(emu.get_ram()[0x200..0x203], [0, 0, 0]);
+}
+
+#[test]
+fn out_of_bounds_key_is_an_error() {
+    let mut emu = Emulator::new();
+    assert!(emu.press_key(0x10).is_err());
+    assert!(emu.release_key(0x10).is_err());
+    assert!(emu.press_key(0xF).is_ok());
+}
+
+#[test]
+fn save_state_round_trips() {
+    let mut emu = Emulator::with_seed(0);
+    emu.load_rom(IBM_LOGO).unwrap();
+    for _ in 0..10 {
+        emu.cycle().unwrap();
+    }
+    emu.press_key(0xA).unwrap();
+
+    let state = emu.save_state();
+    assert_eq!(state.len(), STATE_SIZE);
+
+    // Restoring into a fresh emulator and running both in lockstep must give identical results.
+    let mut restored = Emulator::with_seed(0);
+    restored.load_state(&state).unwrap();
+    assert_eq!(restored.save_state(), state);
+    // The program region (for write protection) comes with the state.
+    assert_eq!(restored.memory_map(), emu.memory_map());
+
+    for _ in 0..100 {
+        emu.cycle().unwrap();
+        restored.cycle().unwrap();
+    }
+    assert_eq!(emu.get_display_buffer(), restored.get_display_buffer());
+}
+
+#[test]
+fn invalid_save_state_is_rejected() {
+    let mut emu = Emulator::new();
+    let mut state = emu.save_state();
+
+    assert!(emu.load_state(&state[1..]).is_err());
+
+    state[0] = b'X';
+    assert!(emu.load_state(&state).is_err());
+}
+
+#[test]
+fn ibm_logo_disassembles() {
+    let lines = disassemble(IBM_LOGO, 0, 4);
+    let listing: Vec<String> = lines
+        .iter()
+        .map(|line| format!("{:03X} {}", line.addr, line.instruction))
+        .collect();
+    assert_eq!(
+        listing,
+        [
+            "000 CLS",
+            "002 LD I, 0x22A",
+            "004 LD V0, 0x0C",
+            "006 LD V1, 0x08"
+        ]
+    );
+
+    // Extension instructions are decoded but flagged, and F000 NNNN takes four bytes.
+    assert!(Instruction::decode(0x00FF).is_super_chip());
+    assert!(Instruction::decode(0xF000).is_xo_chip());
+    assert_eq!(Instruction::decode(0xF000).size(), 4);
+    assert_eq!(Instruction::decode(0x5121), Instruction::Unknown(0x5121));
+    assert!(Instruction::decode(0xD015).is_chip8());
+}
+
+#[test]
+fn quirks_change_opcode_behavior() {
+    // 6105 -> V1 = 5, 6203 -> V2 = 3, 8126 -> V1 = V2 >> 1 (or V1 >> 1), B200 -> jump to 0x200 + V0 (or V2).
+    let rom = [0x61, 0x05, 0x62, 0x03, 0x81, 0x26, 0xB2, 0x00];
+
+    let mut emu = Emulator::new();
+    assert_eq!(emu.get_quirks(), Quirks::default());
+    emu.load_rom(&rom).unwrap();
+    for _ in 0..4 {
+        emu.cycle().unwrap();
+    }
+    assert_eq!(emu.get_registers().v[1], 1);
+    assert_eq!(emu.get_registers().pc, 0x200);
+
+    let mut emu = Emulator::new();
+    emu.set_quirks(Platform::SuperChip.quirks());
+    emu.load_rom(&rom).unwrap();
+    for _ in 0..4 {
+        emu.cycle().unwrap();
+    }
+    assert_eq!(emu.get_registers().v[1], 2);
+    assert_eq!(emu.get_registers().pc, 0x200 + 3);
+
+    assert_eq!("XOCHIP".parse(), Ok(Platform::XoChip));
+    assert!("cosmac".parse::<Platform>().is_err());
+}
+
+#[test]
+fn rom_analysis_guesses_platform_and_quirks() {
+    let analysis = analyze_rom(IBM_LOGO);
+    assert_eq!(analysis.platform, Platform::Chip8);
+    assert!(analysis.evidence.is_empty());
+
+    // 00FF -> high resolution, 8106 -> V1 >>= 1 (in place), F165 F165 -> load twice without
+    // resetting I, 1208 -> loop forever. Data after the loop isn't reachable, so its F000 is ignored.
+    let rom = [
+        0x00, 0xFF, 0x81, 0x06, 0xF1, 0x65, 0xF1, 0x65, 0x12, 0x08, 0xF0, 0x00,
+    ];
+    let analysis = analyze_rom(&rom);
+    assert_eq!(analysis.platform, Platform::SuperChip);
+    assert_eq!(analysis.evidence[0].addr, 0x200);
+    assert_eq!(analysis.evidence[0].instruction, Instruction::High);
+    assert!(!analysis.quirks.shift_vy);
+    assert!(analysis.quirks.increment_i);
+    assert!(analysis.quirks.jump_vx);
+}
+
+#[test]
+fn control_flow_graph_separates_code_from_data() {
+    let cfg = ControlFlowGraph::build(IBM_LOGO);
+    assert_eq!(cfg.blocks.len(), 2);
+    assert_eq!(cfg.blocks[&0x228].edges[0].kind, EdgeKind::Jump);
+    let unreachable = cfg.unreachable_ranges();
+    assert_eq!(unreachable.len(), 1);
+    assert_eq!(unreachable[0], 0x22A..0x285);
+    assert!(cfg.warnings.is_empty());
+    assert!(cfg.to_dot().contains("b200 -> b228;"));
+
+    // 2206 -> call 0x206, 00EE -> return without a call, 3000 -> skip if V0 == 0,
+    // 1206 / 120A -> loop forever, so the subroutine never returns.
+    let rom = [
+        0x22, 0x06, 0x00, 0xEE, 0xFF, 0xFF, 0x30, 0x00, 0x12, 0x06, 0x12, 0x0A,
+    ];
+    let cfg = ControlFlowGraph::build(&rom);
+    assert!(!cfg.is_code(0x204));
+    assert!(cfg.is_code(0x20B));
+    assert_eq!(
+        cfg.warnings,
+        [
+            CfgWarning::CallWithoutReturn {
+                addr: 0x200,
+                target: 0x206
+            },
+            CfgWarning::ReturnWithoutCall { addr: 0x202 },
+        ]
+    );
+
+    // Only the part of a ROM that fits in memory is analyzed.
+    let cfg = ControlFlowGraph::build(&[0xFF; 70_000]);
+    let unreachable = cfg.unreachable_ranges();
+    assert_eq!(unreachable.len(), 1);
+    assert_eq!(unreachable[0], 0x202..0x1000);
+}
+
+#[test]
+fn coverage_records_code_and_data() {
+    let mut emu = Emulator::new();
+    emu.set_coverage_enabled(true);
+    emu.load_rom(IBM_LOGO).unwrap();
+    for _ in 0..100 {
+        emu.cycle().unwrap();
+    }
+
+    let coverage = emu.get_coverage().unwrap();
+    // Code up to the final `JP 0x228`, then the sprites drawn by DXYN. Loading the ROM isn't a write.
+    let ranges = coverage.ranges();
+    assert_eq!(ranges[0].0, 0x200..0x22A);
+    assert!(ranges[0].1.executed && !ranges[0].1.written);
+    assert_eq!(
+        coverage.get(0x22A),
+        Access {
+            read: true,
+            ..Access::default()
+        }
+    );
+    assert!(coverage.get(0x284).is_empty());
+
+    emu.set_coverage_enabled(false);
+    assert!(emu.get_coverage().is_none());
+}
+
+#[test]
+fn profiler_counts_addresses_and_call_stacks() {
+    // 2206 -> call 0x206, 1202 -> loop forever, 6001 -> V0 = 1 (at 0x206), 00EE -> return.
+    let rom = [0x22, 0x06, 0x12, 0x02, 0x00, 0x00, 0x60, 0x01, 0x00, 0xEE];
+    let mut emu = Emulator::new();
+    emu.set_profiling_enabled(true);
+    emu.load_rom(&rom).unwrap();
+    for _ in 0..10 {
+        emu.cycle().unwrap();
+    }
+
+    let profile = emu.get_profile().unwrap();
+    assert_eq!(profile.total(), 10);
+    assert_eq!(
+        profile.address_counts().collect::<Vec<_>>(),
+        [(0x200, 1), (0x202, 7), (0x206, 1), (0x208, 1)]
+    );
+    assert_eq!(
+        profile.stack_counts().collect::<Vec<_>>(),
+        [(&[0x200][..], 8), (&[0x200, 0x206][..], 2)]
+    );
+}
+
+#[test]
+fn symbols_name_addresses_and_resolve_locations() {
+    let symbols = SymbolTable::parse(
+        "# Octo-style symbols\nmain 0x200\nlogo = 0x22A ; sprite data\n\n0x2A0 draw_paddle\n",
+    )
+    .unwrap();
+    assert_eq!(symbols.address("logo"), Some(0x22A));
+    assert_eq!(symbols.name(0x2A0), Some("draw_paddle"));
+    assert_eq!(symbols.describe(0x2A0), "draw_paddle");
+    assert_eq!(symbols.describe(0x2A4), "draw_paddle+0x4");
+    assert_eq!(symbols.describe(0x1FE), "0x1FE");
+    assert_eq!(
+        symbols.instruction(Instruction::LdI(0x22A)).to_string(),
+        "LD I, logo"
+    );
+    assert_eq!(
+        symbols.instruction(Instruction::Call(0x2A6)).to_string(),
+        "CALL draw_paddle+0x6"
+    );
+
+    assert_eq!(symbols.resolve("draw_paddle+0x4").unwrap(), 0x2A4);
+    assert_eq!(symbols.resolve("0x300").unwrap(), 0x300);
+    assert!(matches!(
+        symbols.resolve("missing"),
+        Err(SymbolError::UnknownSymbol { .. })
+    ));
+    assert!(matches!(
+        SymbolTable::parse("main 0x200\nmain 0x202\n"),
+        Err(SymbolError::DuplicateName { line: 2, .. })
+    ));
+    assert!(matches!(
+        SymbolTable::parse("main\n"),
+        Err(SymbolError::InvalidLine { line: 1 })
+    ));
+}
+
+#[test]
+fn display_renders_to_scaled_rgba() {
+    // 00E0 -> clear, A206 -> I = 0x206, D001 -> draw 1 row at (0, 0), 8000 -> sprite row.
+    let mut emu = Emulator::new();
+    emu.load_rom(&[0x00, 0xE0, 0xA2, 0x06, 0xD0, 0x01, 0x80, 0x00])
+        .unwrap();
+    for _ in 0..3 {
+        emu.cycle().unwrap();
+    }
+
+    let palette = Palette {
+        foreground: [0x33, 0xFF, 0x66],
+        background: [0x10, 0x20, 0x30],
+    };
+    let scale = 3;
+    let image = emu.to_rgba(&palette, scale);
+    let width = DISPLAY_WIDTH * scale;
+    assert_eq!(image.len(), width * DISPLAY_HEIGHT * scale * 4);
+
+    let pixel = |x: usize, y: usize| &image[(y * width + x) * 4..][..4];
+    // The lit pixel at (0, 0) covers the top-left 3x3 square.
+    assert_eq!(pixel(0, 0), [0x33, 0xFF, 0x66, 0xFF]);
+    assert_eq!(pixel(2, 2), [0x33, 0xFF, 0x66, 0xFF]);
+    assert_eq!(pixel(3, 0), [0x10, 0x20, 0x30, 0xFF]);
+    assert_eq!(pixel(0, 3), [0x10, 0x20, 0x30, 0xFF]);
+}
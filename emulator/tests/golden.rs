@@ -0,0 +1,195 @@
+// Runs each ROM in `test_roms/` for a fixed number of frames with scripted key presses and
+// compares the final display with a golden image in `tests/golden/`.
+//
+// The golden images are plain PBM files (`P1`, `1` = lit pixel) so changes show up in diffs and
+// any image viewer can open them. After an intended change to the output, regenerate them with
+// `UPDATE_GOLDEN=1 cargo test -p emulator --test golden`. On a mismatch the test writes a diff
+// image to `target/tmp/` (white = lit in both, red = missing, green = unexpected).
+use std::env;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use emulator::{
+    Emulator,
+    constants::{DISPLAY_HEIGHT, DISPLAY_WIDTH},
+};
+
+type Screen = [[bool; DISPLAY_WIDTH]; DISPLAY_HEIGHT];
+
+const STEPS_PER_FRAME: usize = 10;
+// Size of a CHIP-8 pixel in diff images.
+const DIFF_SCALE: usize = 8;
+
+struct Case {
+    rom: &'static str,
+    frames: usize,
+    // (frame, key, pressed): key changes applied before running the frame.
+    inputs: &'static [(usize, usize, bool)],
+}
+
+const CASES: &[Case] = &[
+    Case {
+        rom: "IBM Logo.ch8",
+        frames: 60,
+        inputs: &[],
+    },
+    Case {
+        rom: "Chip8 emulator Logo.ch8",
+        frames: 120,
+        inputs: &[],
+    },
+    Case {
+        rom: "Pong (1 player).ch8",
+        frames: 300,
+        // Move the paddle up, then down.
+        inputs: &[
+            (60, 0x1, true),
+            (90, 0x1, false),
+            (150, 0x4, true),
+            (240, 0x4, false),
+        ],
+    },
+    Case {
+        rom: "Bowling.ch8",
+        frames: 300,
+        inputs: &[
+            (120, 0x5, true),
+            (130, 0x5, false),
+            (200, 0x5, true),
+            (210, 0x5, false),
+        ],
+    },
+    Case {
+        rom: "Self-Test Opcodes.ch8",
+        frames: 120,
+        inputs: &[],
+    },
+    Case {
+        rom: "Self-Test Flags.ch8",
+        frames: 120,
+        inputs: &[],
+    },
+    Case {
+        rom: "Self-Test Quirks.ch8",
+        frames: 120,
+        // Pick chip8 in the menu. The default quirks don't reset VF, so that check shows a cross.
+        inputs: &[(5, 0x1, true), (10, 0x1, false)],
+    },
+];
+
+fn run(case: &Case) -> Screen {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../test_roms")
+        .join(case.rom);
+    let rom = fs::read(&path).unwrap_or_else(|err| panic!("{}: {err}", path.display()));
+
+    let mut emu = Emulator::with_seed(0);
+    emu.load_rom(&rom).unwrap();
+    for frame in 0..case.frames {
+        for &(_, key, pressed) in case.inputs.iter().filter(|input| input.0 == frame) {
+            match pressed {
+                true => emu.press_key(key).unwrap(),
+                false => emu.release_key(key).unwrap(),
+            }
+        }
+        emu.run_frame(STEPS_PER_FRAME)
+            .unwrap_or_else(|err| panic!("{} failed in frame {frame}: {err}", case.rom));
+    }
+    *emu.get_display_buffer()
+}
+
+fn golden_path(case: &Case) -> PathBuf {
+    let name = case.rom.trim_end_matches(".ch8");
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{name}.pbm"))
+}
+
+fn to_pbm(case: &Case, screen: &Screen) -> String {
+    let mut text = format!(
+        "P1\n# {} after {} frames\n{DISPLAY_WIDTH} {DISPLAY_HEIGHT}\n",
+        case.rom, case.frames
+    );
+    for row in screen {
+        let _ = writeln!(
+            text,
+            "{}",
+            row.iter()
+                .map(|&pixel| if pixel { '1' } else { '0' })
+                .collect::<String>()
+        );
+    }
+    text
+}
+
+fn parse_pbm(text: &str) -> Screen {
+    let mut tokens = text
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .flat_map(str::split_whitespace);
+    assert_eq!(tokens.next(), Some("P1"), "not a plain PBM file");
+    let size = (tokens.next(), tokens.next());
+    let expected = (DISPLAY_WIDTH.to_string(), DISPLAY_HEIGHT.to_string());
+    assert_eq!(size, (Some(&*expected.0), Some(&*expected.1)));
+
+    let mut pixels = tokens.flat_map(str::chars).map(|digit| digit == '1');
+    let mut screen = [[false; DISPLAY_WIDTH]; DISPLAY_HEIGHT];
+    for pixel in screen.iter_mut().flatten() {
+        *pixel = pixels.next().expect("PBM file is too short");
+    }
+    screen
+}
+
+// A binary PPM image comparing the two screens.
+fn diff_image(expected: &Screen, actual: &Screen) -> Vec<u8> {
+    let (width, height) = (DISPLAY_WIDTH * DIFF_SCALE, DISPLAY_HEIGHT * DIFF_SCALE);
+    let mut image = format!("P6\n{width} {height}\n255\n").into_bytes();
+    for y in 0..height {
+        for x in 0..width {
+            let (row, col) = (y / DIFF_SCALE, x / DIFF_SCALE);
+            image.extend_from_slice(match (expected[row][col], actual[row][col]) {
+                (true, true) => &[0xFF, 0xFF, 0xFF],
+                (true, false) => &[0xFF, 0x00, 0x00],
+                (false, true) => &[0x00, 0xFF, 0x00],
+                (false, false) => &[0x00, 0x00, 0x00],
+            });
+        }
+    }
+    image
+}
+
+#[test]
+fn roms_match_golden_images() {
+    let update = env::var_os("UPDATE_GOLDEN").is_some();
+    let mut failures = Vec::new();
+    for case in CASES {
+        let actual = run(case);
+        let path = golden_path(case);
+        if update {
+            fs::write(&path, to_pbm(case, &actual)).unwrap();
+            continue;
+        }
+
+        let text = fs::read_to_string(&path).unwrap_or_else(|err| {
+            panic!(
+                "{}: {err} (run with UPDATE_GOLDEN=1 to create it)",
+                path.display()
+            )
+        });
+        let expected = parse_pbm(&text);
+        if expected != actual {
+            let diff = Path::new(env!("CARGO_TARGET_TMPDIR"))
+                .join(format!("{}.diff.ppm", case.rom.trim_end_matches(".ch8")));
+            fs::write(&diff, diff_image(&expected, &actual)).unwrap();
+            failures.push(format!(
+                "{} differs from {} (diff: {})\n{}",
+                case.rom,
+                path.display(),
+                diff.display(),
+                to_pbm(case, &actual)
+            ));
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
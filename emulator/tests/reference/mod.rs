@@ -0,0 +1,201 @@
+// A deliberately simple CHIP-8 interpreter used to check the emulator's CPU. It keeps its whole
+// state in plain public fields and favours obviousness over speed, so that it can be read against
+// the opcode table rather than against `cpu.rs`.
+use emulator::{
+    Quirks,
+    constants::{DISPLAY_HEIGHT, DISPLAY_WIDTH, MEMORY_SIZE, STACK_SIZE},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reference {
+    pub pc: u16,
+    pub sp: u16,
+    pub stack: [u16; STACK_SIZE],
+    pub v: [u8; 16],
+    pub i: u16,
+    pub dt: u8,
+    pub st: u8,
+    pub ram: Vec<u8>,
+    pub display: [[bool; DISPLAY_WIDTH]; DISPLAY_HEIGHT],
+    pub keys: [bool; 16],
+    pub quirks: Quirks,
+}
+
+// The reference refuses to run an instruction (the emulator must report an error as well).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fault;
+
+impl Reference {
+    fn read(&self, addr: usize) -> Result<u8, Fault> {
+        self.ram.get(addr).copied().ok_or(Fault)
+    }
+
+    fn write(&mut self, addr: usize, value: u8) -> Result<(), Fault> {
+        *self.ram.get_mut(addr).ok_or(Fault)? = value;
+        Ok(())
+    }
+
+    fn key(&self, idx: u8) -> Result<bool, Fault> {
+        self.keys.get(idx as usize).copied().ok_or(Fault)
+    }
+
+    // Fetch and execute one instruction. CXNN is not supported, because its result depends on the
+    // emulator's random number generator.
+    pub fn step(&mut self) -> Result<(), Fault> {
+        let pc = self.pc as usize;
+        if pc + 1 >= MEMORY_SIZE {
+            return Err(Fault);
+        }
+        let op = (self.ram[pc] as u16) << 8 | self.ram[pc + 1] as u16;
+        self.pc += 2;
+
+        let x = ((op >> 8) & 0xF) as usize;
+        let y = ((op >> 4) & 0xF) as usize;
+        let n = op & 0xF;
+        let nn = (op & 0xFF) as u8;
+        let nnn = op & 0xFFF;
+        let (vx, vy) = (self.v[x], self.v[y]);
+
+        match op >> 12 {
+            0x0 if op == 0x0000 => {}
+            0x0 if op == 0x00E0 => self.display = [[false; DISPLAY_WIDTH]; DISPLAY_HEIGHT],
+            0x0 if op == 0x00EE => {
+                if self.sp == 0 {
+                    return Err(Fault);
+                }
+                self.sp -= 1;
+                self.pc = *self.stack.get(self.sp as usize).ok_or(Fault)?;
+            }
+            0x1 => self.pc = nnn,
+            0x2 => {
+                *self.stack.get_mut(self.sp as usize).ok_or(Fault)? = self.pc;
+                self.sp += 1;
+                self.pc = nnn;
+            }
+            0x3 if vx == nn => self.pc += 2,
+            0x4 if vx != nn => self.pc += 2,
+            0x5 if n == 0 && vx == vy => self.pc += 2,
+            0x3 | 0x4 => {}
+            0x5 if n == 0 => {}
+            0x6 => self.v[x] = nn,
+            0x7 => self.v[x] = ((vx as u16 + nn as u16) % 256) as u8,
+            0x8 => match n {
+                0x0 => self.v[x] = vy,
+                0x1..=0x3 => {
+                    self.v[x] = match n {
+                        0x1 => vx | vy,
+                        0x2 => vx & vy,
+                        _ => vx ^ vy,
+                    };
+                    if self.quirks.vf_reset {
+                        self.v[0xF] = 0;
+                    }
+                }
+                0x4 => {
+                    let sum = vx as u16 + vy as u16;
+                    self.v[x] = (sum % 256) as u8;
+                    self.v[0xF] = (sum > 255) as u8;
+                }
+                0x5 => {
+                    self.v[x] = ((vx as i16 - vy as i16).rem_euclid(256)) as u8;
+                    self.v[0xF] = (vx >= vy) as u8;
+                }
+                0x7 => {
+                    self.v[x] = ((vy as i16 - vx as i16).rem_euclid(256)) as u8;
+                    self.v[0xF] = (vy >= vx) as u8;
+                }
+                0x6 | 0xE => {
+                    let value = if self.quirks.shift_vy { vy } else { vx };
+                    let (result, flag) = match n {
+                        0x6 => (value / 2, value % 2),
+                        _ => (((value as u16 * 2) % 256) as u8, value / 128),
+                    };
+                    self.v[x] = result;
+                    self.v[0xF] = flag;
+                }
+                _ => return Err(Fault),
+            },
+            0x9 if n == 0 => {
+                if vx != vy {
+                    self.pc += 2;
+                }
+            }
+            0xA => self.i = nnn,
+            0xB => {
+                let offset = if self.quirks.jump_vx { vx } else { self.v[0] };
+                self.pc = nnn + offset as u16;
+            }
+            0xD => {
+                let mut rows = Vec::new();
+                for row in 0..n as usize {
+                    rows.push(self.read(self.i as usize + row)?);
+                }
+                self.v[0xF] = 0;
+                for (row, bits) in rows.into_iter().enumerate() {
+                    for col in 0..8 {
+                        let px = vx as usize % DISPLAY_WIDTH + col;
+                        let py = vy as usize % DISPLAY_HEIGHT + row;
+                        let visible = px < DISPLAY_WIDTH && py < DISPLAY_HEIGHT;
+                        if bits & (0x80 >> col) == 0 || !(visible || self.quirks.wrap_sprites) {
+                            continue;
+                        }
+                        let pixel = &mut self.display[py % DISPLAY_HEIGHT][px % DISPLAY_WIDTH];
+                        if *pixel {
+                            self.v[0xF] = 1;
+                        }
+                        *pixel = !*pixel;
+                    }
+                }
+            }
+            0xE if nn == 0x9E => {
+                if self.key(vx)? {
+                    self.pc += 2;
+                }
+            }
+            0xE if nn == 0xA1 => {
+                if !self.key(vx)? {
+                    self.pc += 2;
+                }
+            }
+            0xF => match nn {
+                0x07 => self.v[x] = self.dt,
+                0x0A => match self.keys.iter().position(|&pressed| pressed) {
+                    Some(key) => self.v[x] = key as u8,
+                    None => self.pc -= 2,
+                },
+                0x15 => self.dt = vx,
+                0x18 => self.st = vx,
+                0x1E => self.i = self.i.wrapping_add(vx as u16),
+                0x29 => self.i = vx as u16 * 5,
+                0x33 => {
+                    let i = self.i as usize;
+                    self.write(i, vx / 100)?;
+                    self.write(i + 1, vx / 10 % 10)?;
+                    self.write(i + 2, vx % 10)?;
+                }
+                0x55 => {
+                    for reg in 0..=x {
+                        self.write(self.i as usize + reg, self.v[reg])?;
+                    }
+                    if self.quirks.increment_i {
+                        self.i += x as u16 + 1;
+                    }
+                }
+                0x65 => {
+                    for reg in 0..=x {
+                        self.v[reg] = self.read(self.i as usize + reg)?;
+                    }
+                    if self.quirks.increment_i {
+                        self.i += x as u16 + 1;
+                    }
+                }
+                _ => return Err(Fault),
+            },
+            _ => return Err(Fault),
+        }
+        if self.pc as usize >= MEMORY_SIZE {
+            return Err(Fault);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,146 @@
+#ifndef CHIP8_H
+#define CHIP8_H
+
+/* This file is generated by cbindgen from ffi/src/lib.rs. Do not edit it by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Width of the display in pixels.
+#define CHIP8_DISPLAY_WIDTH 64
+
+// Height of the display in pixels.
+#define CHIP8_DISPLAY_HEIGHT 32
+
+// Size of RAM in bytes.
+#define CHIP8_MEMORY_SIZE 4096
+
+// Number of keys on the keypad.
+#define CHIP8_NUM_KEYS 16
+
+// Size of a save state in bytes.
+#define CHIP8_STATE_SIZE 4417
+
+// Result of a call into the C API. Error codes mirror the variants of `EmulatorError`.
+typedef enum Chip8Status {
+  // The call succeeded.
+  CHIP8_STATUS_OK = 0,
+  // A required pointer argument was null.
+  CHIP8_STATUS_NULL_POINTER = -1,
+  // A provided output buffer was too small.
+  CHIP8_STATUS_BUFFER_TOO_SMALL = -2,
+  // Memory error (out of bounds access, stack overflow / underflow).
+  CHIP8_STATUS_MEMORY = -3,
+  // Keypad error (key index out of bounds).
+  CHIP8_STATUS_KEYPAD = -4,
+  // CPU execution error (e.g. unimplemented opcode).
+  CHIP8_STATUS_CPU = -5,
+  // Save state error (invalid length, magic bytes or version).
+  CHIP8_STATUS_STATE = -6,
+} Chip8Status;
+
+// Opaque handle to an emulator instance.
+typedef struct Chip8 Chip8;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Create a new emulator with an OS-seeded random number generator.
+// The handle must be released with `chip8_destroy`.
+struct Chip8 *chip8_create(void);
+
+// Create a new emulator whose random number generator is seeded with `seed`, for reproducible runs.
+// The handle must be released with `chip8_destroy`.
+struct Chip8 *chip8_create_with_seed(uint64_t seed);
+
+// Destroy an emulator created by `chip8_create`. Passing null is a no-op.
+//
+// # Safety
+// `chip8` must be null or a handle returned by `chip8_create` that has not been destroyed yet.
+void chip8_destroy(struct Chip8 *chip8);
+
+// Reset the emulator, clearing memory (including any loaded ROM), registers, keypad and display.
+//
+// # Safety
+// `chip8` must be null or a valid handle.
+enum Chip8Status chip8_reset(struct Chip8 *chip8);
+
+// Load `len` bytes of ROM data into memory at the program start address.
+//
+// # Safety
+// `chip8` must be null or a valid handle, and `data` must be null or point to `len` readable bytes.
+enum Chip8Status chip8_load_rom(struct Chip8 *chip8, const uint8_t *data, size_t len);
+
+// Perform a single CPU cycle (fetch, decode, execute).
+//
+// # Safety
+// `chip8` must be null or a valid handle.
+enum Chip8Status chip8_step(struct Chip8 *chip8);
+
+// Run a single display frame: `steps` CPU cycles followed by one timer tick.
+//
+// # Safety
+// `chip8` must be null or a valid handle.
+enum Chip8Status chip8_run_frame(struct Chip8 *chip8, size_t steps);
+
+// Tick (decrement) the delay and sound timers once.
+//
+// # Safety
+// `chip8` must be null or a valid handle.
+enum Chip8Status chip8_tick_timers(struct Chip8 *chip8);
+
+// Get the current value of the sound timer (the beep should play while it is non-zero).
+// Returns 0 for a null handle.
+//
+// # Safety
+// `chip8` must be null or a valid handle.
+uint8_t chip8_sound_timer(const struct Chip8 *chip8);
+
+// Copy the display into `out`, one byte per pixel (1 = on, 0 = off), row by row.
+// `len` must be at least `CHIP8_DISPLAY_WIDTH * CHIP8_DISPLAY_HEIGHT`.
+//
+// # Safety
+// `chip8` must be null or a valid handle, and `out` must be null or point to `len` writable bytes.
+enum Chip8Status chip8_get_framebuffer(const struct Chip8 *chip8, uint8_t *out, size_t len);
+
+// Mark the key at index `idx` (0x0 - 0xF) as pressed.
+//
+// # Safety
+// `chip8` must be null or a valid handle.
+enum Chip8Status chip8_press_key(struct Chip8 *chip8, size_t idx);
+
+// Mark the key at index `idx` (0x0 - 0xF) as released.
+//
+// # Safety
+// `chip8` must be null or a valid handle.
+enum Chip8Status chip8_release_key(struct Chip8 *chip8, size_t idx);
+
+// Write a save state of `CHIP8_STATE_SIZE` bytes into `out`.
+// The random number generator state is not included.
+//
+// # Safety
+// `chip8` must be null or a valid handle, and `out` must be null or point to `len` writable bytes.
+enum Chip8Status chip8_save_state(const struct Chip8 *chip8, uint8_t *out, size_t len);
+
+// Restore the emulator from a save state created by `chip8_save_state`.
+//
+// # Safety
+// `chip8` must be null or a valid handle, and `data` must be null or point to `len` readable bytes.
+enum Chip8Status chip8_load_state(struct Chip8 *chip8, const uint8_t *data, size_t len);
+
+// Get a description of the most recent error reported by this handle, or null if there was none.
+// The string is owned by the handle and stays valid until the next failing call or `chip8_destroy`.
+//
+// # Safety
+// `chip8` must be null or a valid handle.
+const char *chip8_last_error(const struct Chip8 *chip8);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* CHIP8_H */
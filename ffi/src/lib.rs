@@ -0,0 +1,321 @@
+//! C ABI for embedding the CHIP-8 emulator core.
+//!
+//! Every function taking a `Chip8` handle accepts a null pointer and reports it with
+//! `CHIP8_STATUS_NULL_POINTER` (or a neutral return value) rather than crashing.
+//! Errors that carry more detail can be retrieved with `chip8_last_error`.
+
+use std::ffi::{CString, c_char};
+use std::{ptr, slice};
+
+use emulator::{
+    Emulator, EmulatorError,
+    constants::{DISPLAY_HEIGHT, DISPLAY_WIDTH, MEMORY_SIZE, NUM_KEYS, STATE_SIZE},
+};
+
+/// Width of the display in pixels.
+pub const CHIP8_DISPLAY_WIDTH: usize = 64;
+/// Height of the display in pixels.
+pub const CHIP8_DISPLAY_HEIGHT: usize = 32;
+/// Size of RAM in bytes.
+pub const CHIP8_MEMORY_SIZE: usize = 4096;
+/// Number of keys on the keypad.
+pub const CHIP8_NUM_KEYS: usize = 16;
+/// Size of a save state in bytes.
+pub const CHIP8_STATE_SIZE: usize = 4417;
+
+// cbindgen can't evaluate constants from other crates, so the values above are spelled out
+// and checked against the emulator's own constants here.
+const _: () = assert!(CHIP8_DISPLAY_WIDTH == DISPLAY_WIDTH);
+const _: () = assert!(CHIP8_DISPLAY_HEIGHT == DISPLAY_HEIGHT);
+const _: () = assert!(CHIP8_MEMORY_SIZE == MEMORY_SIZE);
+const _: () = assert!(CHIP8_NUM_KEYS == NUM_KEYS);
+const _: () = assert!(CHIP8_STATE_SIZE == STATE_SIZE);
+
+/// Result of a call into the C API. Error codes mirror the variants of `EmulatorError`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Chip8Status {
+    /// The call succeeded.
+    Ok = 0,
+    /// A required pointer argument was null.
+    NullPointer = -1,
+    /// A provided output buffer was too small.
+    BufferTooSmall = -2,
+    /// Memory error (out of bounds access, stack overflow / underflow).
+    Memory = -3,
+    /// Keypad error (key index out of bounds).
+    Keypad = -4,
+    /// CPU execution error (e.g. unimplemented opcode).
+    Cpu = -5,
+    /// Save state error (invalid length, magic bytes or version).
+    State = -6,
+}
+
+/// Opaque handle to an emulator instance.
+pub struct Chip8 {
+    emu: Emulator,
+    last_error: Option<CString>,
+}
+
+impl Chip8 {
+    // Helper function to convert an emulator result into a status code, remembering the error message.
+    fn status(&mut self, result: Result<(), EmulatorError>) -> Chip8Status {
+        match result {
+            Ok(()) => Chip8Status::Ok,
+            Err(err) => {
+                let status = match err {
+                    EmulatorError::Memory { .. } => Chip8Status::Memory,
+                    EmulatorError::Keypad { .. } => Chip8Status::Keypad,
+                    EmulatorError::Cpu { .. } => Chip8Status::Cpu,
+                    EmulatorError::State { .. } => Chip8Status::State,
+                };
+                self.last_error = Some(error_message(&err));
+                status
+            }
+        }
+    }
+}
+
+// Helper function to flatten an error and its sources into a single C string.
+fn error_message(err: &EmulatorError) -> CString {
+    let mut message = err.to_string();
+    let mut source = std::error::Error::source(err);
+    while let Some(cause) = source {
+        message.push_str(": ");
+        message.push_str(&cause.to_string());
+        source = cause.source();
+    }
+    CString::new(message).unwrap_or_default()
+}
+
+/// Create a new emulator with an OS-seeded random number generator.
+/// The handle must be released with `chip8_destroy`.
+#[unsafe(no_mangle)]
+pub extern "C" fn chip8_create() -> *mut Chip8 {
+    Box::into_raw(Box::new(Chip8 {
+        emu: Emulator::new(),
+        last_error: None,
+    }))
+}
+
+/// Create a new emulator whose random number generator is seeded with `seed`, for reproducible runs.
+/// The handle must be released with `chip8_destroy`.
+#[unsafe(no_mangle)]
+pub extern "C" fn chip8_create_with_seed(seed: u64) -> *mut Chip8 {
+    Box::into_raw(Box::new(Chip8 {
+        emu: Emulator::with_seed(seed),
+        last_error: None,
+    }))
+}
+
+/// Destroy an emulator created by `chip8_create`. Passing null is a no-op.
+///
+/// # Safety
+/// `chip8` must be null or a handle returned by `chip8_create` that has not been destroyed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn chip8_destroy(chip8: *mut Chip8) {
+    if !chip8.is_null() {
+        drop(unsafe { Box::from_raw(chip8) });
+    }
+}
+
+/// Reset the emulator, clearing memory (including any loaded ROM), registers, keypad and display.
+///
+/// # Safety
+/// `chip8` must be null or a valid handle.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn chip8_reset(chip8: *mut Chip8) -> Chip8Status {
+    let Some(chip8) = (unsafe { chip8.as_mut() }) else {
+        return Chip8Status::NullPointer;
+    };
+    chip8.emu.reset();
+    Chip8Status::Ok
+}
+
+/// Load `len` bytes of ROM data into memory at the program start address.
+///
+/// # Safety
+/// `chip8` must be null or a valid handle, and `data` must be null or point to `len` readable bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn chip8_load_rom(
+    chip8: *mut Chip8,
+    data: *const u8,
+    len: usize,
+) -> Chip8Status {
+    let Some(chip8) = (unsafe { chip8.as_mut() }) else {
+        return Chip8Status::NullPointer;
+    };
+    if data.is_null() {
+        return Chip8Status::NullPointer;
+    }
+    let rom = unsafe { slice::from_raw_parts(data, len) };
+    let result = chip8.emu.load_rom(rom);
+    chip8.status(result)
+}
+
+/// Perform a single CPU cycle (fetch, decode, execute).
+///
+/// # Safety
+/// `chip8` must be null or a valid handle.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn chip8_step(chip8: *mut Chip8) -> Chip8Status {
+    let Some(chip8) = (unsafe { chip8.as_mut() }) else {
+        return Chip8Status::NullPointer;
+    };
+    let result = chip8.emu.cycle();
+    chip8.status(result)
+}
+
+/// Run a single display frame: `steps` CPU cycles followed by one timer tick.
+///
+/// # Safety
+/// `chip8` must be null or a valid handle.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn chip8_run_frame(chip8: *mut Chip8, steps: usize) -> Chip8Status {
+    let Some(chip8) = (unsafe { chip8.as_mut() }) else {
+        return Chip8Status::NullPointer;
+    };
+    let result = chip8.emu.run_frame(steps);
+    chip8.status(result)
+}
+
+/// Tick (decrement) the delay and sound timers once.
+///
+/// # Safety
+/// `chip8` must be null or a valid handle.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn chip8_tick_timers(chip8: *mut Chip8) -> Chip8Status {
+    let Some(chip8) = (unsafe { chip8.as_mut() }) else {
+        return Chip8Status::NullPointer;
+    };
+    chip8.emu.tick_timers();
+    Chip8Status::Ok
+}
+
+/// Get the current value of the sound timer (the beep should play while it is non-zero).
+/// Returns 0 for a null handle.
+///
+/// # Safety
+/// `chip8` must be null or a valid handle.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn chip8_sound_timer(chip8: *const Chip8) -> u8 {
+    match unsafe { chip8.as_ref() } {
+        Some(chip8) => chip8.emu.get_st(),
+        None => 0,
+    }
+}
+
+/// Copy the display into `out`, one byte per pixel (1 = on, 0 = off), row by row.
+/// `len` must be at least `CHIP8_DISPLAY_WIDTH * CHIP8_DISPLAY_HEIGHT`.
+///
+/// # Safety
+/// `chip8` must be null or a valid handle, and `out` must be null or point to `len` writable bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn chip8_get_framebuffer(
+    chip8: *const Chip8,
+    out: *mut u8,
+    len: usize,
+) -> Chip8Status {
+    let Some(chip8) = (unsafe { chip8.as_ref() }) else {
+        return Chip8Status::NullPointer;
+    };
+    if out.is_null() {
+        return Chip8Status::NullPointer;
+    }
+    if len < DISPLAY_WIDTH * DISPLAY_HEIGHT {
+        return Chip8Status::BufferTooSmall;
+    }
+
+    let out = unsafe { slice::from_raw_parts_mut(out, len) };
+    let pixels = chip8.emu.get_display_buffer().iter().flatten();
+    for (byte, &is_pixel_on) in out.iter_mut().zip(pixels) {
+        *byte = is_pixel_on as u8;
+    }
+    Chip8Status::Ok
+}
+
+/// Mark the key at index `idx` (0x0 - 0xF) as pressed.
+///
+/// # Safety
+/// `chip8` must be null or a valid handle.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn chip8_press_key(chip8: *mut Chip8, idx: usize) -> Chip8Status {
+    let Some(chip8) = (unsafe { chip8.as_mut() }) else {
+        return Chip8Status::NullPointer;
+    };
+    let result = chip8.emu.press_key(idx);
+    chip8.status(result)
+}
+
+/// Mark the key at index `idx` (0x0 - 0xF) as released.
+///
+/// # Safety
+/// `chip8` must be null or a valid handle.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn chip8_release_key(chip8: *mut Chip8, idx: usize) -> Chip8Status {
+    let Some(chip8) = (unsafe { chip8.as_mut() }) else {
+        return Chip8Status::NullPointer;
+    };
+    let result = chip8.emu.release_key(idx);
+    chip8.status(result)
+}
+
+/// Write a save state of `CHIP8_STATE_SIZE` bytes into `out`.
+/// The random number generator state is not included.
+///
+/// # Safety
+/// `chip8` must be null or a valid handle, and `out` must be null or point to `len` writable bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn chip8_save_state(
+    chip8: *const Chip8,
+    out: *mut u8,
+    len: usize,
+) -> Chip8Status {
+    let Some(chip8) = (unsafe { chip8.as_ref() }) else {
+        return Chip8Status::NullPointer;
+    };
+    if out.is_null() {
+        return Chip8Status::NullPointer;
+    }
+    if len < STATE_SIZE {
+        return Chip8Status::BufferTooSmall;
+    }
+
+    let state = chip8.emu.save_state();
+    unsafe { ptr::copy_nonoverlapping(state.as_ptr(), out, state.len()) };
+    Chip8Status::Ok
+}
+
+/// Restore the emulator from a save state created by `chip8_save_state`.
+///
+/// # Safety
+/// `chip8` must be null or a valid handle, and `data` must be null or point to `len` readable bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn chip8_load_state(
+    chip8: *mut Chip8,
+    data: *const u8,
+    len: usize,
+) -> Chip8Status {
+    let Some(chip8) = (unsafe { chip8.as_mut() }) else {
+        return Chip8Status::NullPointer;
+    };
+    if data.is_null() {
+        return Chip8Status::NullPointer;
+    }
+    let state = unsafe { slice::from_raw_parts(data, len) };
+    let result = chip8.emu.load_state(state);
+    chip8.status(result)
+}
+
+/// Get a description of the most recent error reported by this handle, or null if there was none.
+/// The string is owned by the handle and stays valid until the next failing call or `chip8_destroy`.
+///
+/// # Safety
+/// `chip8` must be null or a valid handle.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn chip8_last_error(chip8: *const Chip8) -> *const c_char {
+    match unsafe { chip8.as_ref() }.and_then(|chip8| chip8.last_error.as_ref()) {
+        Some(message) => message.as_ptr(),
+        None => ptr::null(),
+    }
+}
@@ -0,0 +1,46 @@
+// Compiles `tests/harness.c` against the generated header and the `chip8` shared library,
+// then runs it on the IBM logo ROM. Uses the C compiler from `$CC` (default: `cc`).
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// Directory containing the built `chip8` library, which cargo places next to the test
+// executable (`target/<profile>/deps`).
+fn library_dir() -> PathBuf {
+    let exe = env::current_exe().unwrap();
+    exe.parent().unwrap().to_path_buf()
+}
+
+#[test]
+fn c_harness_passes() {
+    let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let lib_dir = library_dir();
+    let harness = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("chip8_harness");
+    let compiler = env::var("CC").unwrap_or_else(|_| "cc".to_string());
+
+    let status = Command::new(compiler)
+        .arg(crate_dir.join("tests").join("harness.c"))
+        .arg("-I")
+        .arg(crate_dir.join("include"))
+        .arg("-L")
+        .arg(&lib_dir)
+        .arg(format!("-Wl,-rpath,{}", lib_dir.display()))
+        .arg("-lchip8")
+        .arg("-Wall")
+        .arg("-Werror")
+        .arg("-o")
+        .arg(&harness)
+        .status()
+        .expect("Failed to run the C compiler");
+    assert!(status.success(), "Failed to compile the C harness");
+
+    let output = Command::new(&harness)
+        .arg(crate_dir.join("../test_roms/IBM Logo.ch8"))
+        .output()
+        .expect("Failed to run the C harness");
+    assert!(
+        output.status.success(),
+        "C harness failed:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
@@ -0,0 +1,112 @@
+// C test harness for the CHIP-8 C API. Built and run by `tests/c_harness.rs` during `cargo test`.
+#include <stdio.h>
+#include <string.h>
+
+#include "chip8.h"
+
+static int failures = 0;
+
+#define CHECK(cond)                                                   \
+    do {                                                              \
+        if (!(cond)) {                                                \
+            fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__,    \
+                    __LINE__, #cond);                                 \
+            failures++;                                               \
+        }                                                             \
+    } while (0)
+
+static int lit_pixels(const Chip8 *chip8) {
+    uint8_t framebuffer[CHIP8_DISPLAY_WIDTH * CHIP8_DISPLAY_HEIGHT];
+    CHECK(chip8_get_framebuffer(chip8, framebuffer, sizeof framebuffer) == CHIP8_STATUS_OK);
+
+    int lit = 0;
+    for (size_t i = 0; i < sizeof framebuffer; i++) {
+        lit += framebuffer[i];
+    }
+    return lit;
+}
+
+static void test_null_handles(void) {
+    uint8_t byte = 0;
+    CHECK(chip8_step(NULL) == CHIP8_STATUS_NULL_POINTER);
+    CHECK(chip8_load_rom(NULL, &byte, 1) == CHIP8_STATUS_NULL_POINTER);
+    CHECK(chip8_sound_timer(NULL) == 0);
+    CHECK(chip8_last_error(NULL) == NULL);
+    chip8_destroy(NULL);
+}
+
+static void test_run_frames(const uint8_t *rom, size_t rom_len) {
+    Chip8 *chip8 = chip8_create_with_seed(0);
+    CHECK(chip8 != NULL);
+    CHECK(chip8_load_rom(chip8, rom, rom_len) == CHIP8_STATUS_OK);
+    CHECK(lit_pixels(chip8) == 0);
+
+    for (int i = 0; i < 60; i++) {
+        CHECK(chip8_run_frame(chip8, 8) == CHIP8_STATUS_OK);
+    }
+    CHECK(lit_pixels(chip8) > 0);
+    chip8_destroy(chip8);
+}
+
+static void test_errors(void) {
+    Chip8 *chip8 = chip8_create();
+    uint8_t invalid_opcode[] = {0xFF, 0xFF};
+    uint8_t framebuffer[8];
+
+    CHECK(chip8_last_error(chip8) == NULL);
+    CHECK(chip8_press_key(chip8, CHIP8_NUM_KEYS) == CHIP8_STATUS_KEYPAD);
+    CHECK(chip8_last_error(chip8) != NULL);
+
+    CHECK(chip8_load_rom(chip8, invalid_opcode, sizeof invalid_opcode) == CHIP8_STATUS_OK);
+    CHECK(chip8_step(chip8) == CHIP8_STATUS_CPU);
+    CHECK(strstr(chip8_last_error(chip8), "0xFFFF") != NULL);
+
+    CHECK(chip8_get_framebuffer(chip8, framebuffer, sizeof framebuffer) == CHIP8_STATUS_BUFFER_TOO_SMALL);
+    chip8_destroy(chip8);
+}
+
+static void test_save_state(const uint8_t *rom, size_t rom_len) {
+    static uint8_t state[CHIP8_STATE_SIZE];
+    Chip8 *chip8 = chip8_create_with_seed(0);
+
+    CHECK(chip8_load_rom(chip8, rom, rom_len) == CHIP8_STATUS_OK);
+    CHECK(chip8_run_frame(chip8, 100) == CHIP8_STATUS_OK);
+    int lit = lit_pixels(chip8);
+    CHECK(chip8_save_state(chip8, state, sizeof state) == CHIP8_STATUS_OK);
+
+    CHECK(chip8_reset(chip8) == CHIP8_STATUS_OK);
+    CHECK(lit_pixels(chip8) == 0);
+    CHECK(chip8_load_state(chip8, state, sizeof state) == CHIP8_STATUS_OK);
+    CHECK(lit_pixels(chip8) == lit);
+
+    CHECK(chip8_load_state(chip8, state, sizeof state - 1) == CHIP8_STATUS_STATE);
+    chip8_destroy(chip8);
+}
+
+int main(int argc, char **argv) {
+    if (argc != 2) {
+        fprintf(stderr, "usage: %s <rom>\n", argv[0]);
+        return 2;
+    }
+
+    static uint8_t rom[CHIP8_MEMORY_SIZE];
+    FILE *file = fopen(argv[1], "rb");
+    if (file == NULL) {
+        perror("fopen");
+        return 2;
+    }
+    size_t rom_len = fread(rom, 1, sizeof rom, file);
+    fclose(file);
+
+    test_null_handles();
+    test_run_frames(rom, rom_len);
+    test_errors();
+    test_save_state(rom, rom_len);
+
+    if (failures > 0) {
+        fprintf(stderr, "%d check(s) failed\n", failures);
+        return 1;
+    }
+    printf("all checks passed\n");
+    return 0;
+}
@@ -0,0 +1,20 @@
+#![no_main]
+// Runs arbitrary bytes as a ROM for a bounded number of frames. The emulator may stop with an
+// error (most random bytes aren't valid programs), but it must never panic.
+use emulator::Emulator;
+use libfuzzer_sys::fuzz_target;
+
+const FRAMES: usize = 1000;
+const STEPS_PER_FRAME: usize = 10;
+
+fuzz_target!(|rom: &[u8]| {
+    let mut emu = Emulator::with_seed(0);
+    if emu.load_rom(rom).is_err() {
+        return;
+    }
+    for _ in 0..FRAMES {
+        if emu.run_frame(STEPS_PER_FRAME).is_err() {
+            break;
+        }
+    }
+});
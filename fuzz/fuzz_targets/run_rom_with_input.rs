@@ -0,0 +1,36 @@
+#![no_main]
+// Like `run_rom`, with fuzzed quirks and keypad input (one 16-bit key mask per frame), and with
+// coverage and profiling enabled.
+use emulator::{Emulator, Quirks};
+use libfuzzer_sys::fuzz_target;
+
+const STEPS_PER_FRAME: usize = 10;
+
+fuzz_target!(|input: (u8, Vec<u16>, &[u8])| {
+    let (quirks, frames, rom) = input;
+    let mut emu = Emulator::with_seed(0);
+    emu.set_quirks(Quirks {
+        shift_vy: quirks & 0b00001 != 0,
+        increment_i: quirks & 0b00010 != 0,
+        vf_reset: quirks & 0b00100 != 0,
+        jump_vx: quirks & 0b01000 != 0,
+        wrap_sprites: quirks & 0b10000 != 0,
+    });
+    emu.set_coverage_enabled(true);
+    emu.set_profiling_enabled(true);
+    if emu.load_rom(rom).is_err() {
+        return;
+    }
+    for keys in frames {
+        for key in 0..16 {
+            let result = match keys & (1 << key) != 0 {
+                true => emu.press_key(key),
+                false => emu.release_key(key),
+            };
+            result.unwrap();
+        }
+        if emu.run_frame(STEPS_PER_FRAME).is_err() {
+            break;
+        }
+    }
+});
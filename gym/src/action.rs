@@ -0,0 +1,47 @@
+use emulator::constants::NUM_KEYS;
+
+// Maps discrete action indices to CHIP-8 keypad indices. `None` is a no-op (no key held).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActionSet {
+    keys: Vec<Option<usize>>,
+}
+
+impl ActionSet {
+    // Create an action set from a list of keypad indices, where `None` is a no-op action.
+    pub fn new(keys: Vec<Option<usize>>) -> Self {
+        Self { keys }
+    }
+
+    // Create an action set with a no-op (action 0) followed by the given keypad indices.
+    pub fn with_noop(keys: &[usize]) -> Self {
+        let mut actions = vec![None];
+        actions.extend(keys.iter().copied().map(Some));
+        Self::new(actions)
+    }
+
+    // Create an action set with a no-op (action 0) followed by all 16 keys (action N + 1 holds key N).
+    pub fn full() -> Self {
+        Self::with_noop(&(0..NUM_KEYS).collect::<Vec<_>>())
+    }
+
+    // Number of actions in the set.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    // Whether the action set is empty.
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    // Get the keypad index held by the given action, or `None` for a no-op or an invalid action.
+    pub fn key(&self, action: usize) -> Option<usize> {
+        self.keys.get(action).copied().flatten()
+    }
+}
+
+impl Default for ActionSet {
+    fn default() -> Self {
+        Self::full()
+    }
+}
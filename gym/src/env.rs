@@ -0,0 +1,162 @@
+use emulator::{
+    Emulator,
+    constants::{DISPLAY_HEIGHT, DISPLAY_WIDTH},
+};
+
+use crate::{action::ActionSet, error::EnvError, reward::Reward};
+
+// The display buffer after a step, indexed as `[y][x]`.
+pub type Observation = [[bool; DISPLAY_WIDTH]; DISPLAY_HEIGHT];
+
+// Configuration shared by every episode of an environment.
+#[derive(Debug, Clone)]
+pub struct EnvConfig {
+    pub actions: ActionSet,     // Mapping from action indices to keypad indices.
+    pub frame_skip: usize,      // Number of frames each action is held for (rewards are summed).
+    pub steps_per_frame: usize, // Number of CPU cycles per frame.
+    pub max_frames: Option<usize>, // End episodes after this many frames.
+    pub seed: u64,              // Seed for the first episode (episode N uses `seed + N`).
+}
+
+impl Default for EnvConfig {
+    fn default() -> Self {
+        Self {
+            actions: ActionSet::default(),
+            frame_skip: 4,
+            steps_per_frame: 500 / 60,
+            max_frames: None,
+            seed: 0,
+        }
+    }
+}
+
+// Result of a single environment step.
+#[derive(Debug, Clone)]
+pub struct Step {
+    pub observation: Observation,
+    pub reward: f32,
+    pub done: bool,
+}
+
+// A single CHIP-8 game as a reinforcement learning environment.
+pub struct Env<R: Reward> {
+    emu: Emulator,
+    rom: Vec<u8>,
+    config: EnvConfig,
+    reward: R,
+    held_key: Option<usize>,
+    frames: usize,
+    episode: u64,
+    done: bool,
+    // Set by the first `reset`; until then there is no ROM loaded to step.
+    started: bool,
+}
+
+impl<R: Reward> Env<R> {
+    // Create a new environment for the given ROM. Call `reset` before the first `step`.
+    pub fn new(rom: Vec<u8>, config: EnvConfig, reward: R) -> Self {
+        Self {
+            emu: Emulator::with_seed(config.seed),
+            rom,
+            config,
+            reward,
+            held_key: None,
+            frames: 0,
+            episode: 0,
+            done: true,
+            started: false,
+        }
+    }
+
+    // Start a new episode, reloading the ROM with the next seed, and return the first observation.
+    pub fn reset(&mut self) -> Result<Observation, EnvError> {
+        let seed = self.config.seed.wrapping_add(self.episode);
+        self.episode += 1;
+        self.reset_with_seed(seed)
+    }
+
+    // Start a new episode with the given random number generator seed.
+    pub fn reset_with_seed(&mut self, seed: u64) -> Result<Observation, EnvError> {
+        self.emu = Emulator::with_seed(seed);
+        self.emu.load_rom(&self.rom)?;
+        self.reward.reset(self.emu.get_ram());
+        self.held_key = None;
+        self.frames = 0;
+        self.done = false;
+        self.started = true;
+        Ok(*self.emu.get_display_buffer())
+    }
+
+    // Hold the key mapped to `action` for `frame_skip` frames and return the summed reward.
+    // An emulator error ends the episode and is returned to the caller.
+    pub fn step(&mut self, action: usize) -> Result<Step, EnvError> {
+        if !self.started {
+            return Err(EnvError::NotReset);
+        }
+        if action >= self.config.actions.len() {
+            return Err(EnvError::InvalidAction {
+                action,
+                num_actions: self.config.actions.len(),
+            });
+        }
+        self.hold_key(self.config.actions.key(action))?;
+
+        let mut reward = 0.0;
+        for _ in 0..self.config.frame_skip.max(1) {
+            if let Err(err) = self.emu.run_frame(self.config.steps_per_frame) {
+                self.done = true;
+                return Err(err.into());
+            }
+            self.frames += 1;
+
+            let ram = self.emu.get_ram();
+            reward += self.reward.reward(ram);
+            let out_of_frames = self.config.max_frames.is_some_and(|max| self.frames >= max);
+            if self.reward.done(ram) || out_of_frames {
+                self.done = true;
+                break;
+            }
+        }
+
+        Ok(Step {
+            observation: *self.emu.get_display_buffer(),
+            reward,
+            done: self.done,
+        })
+    }
+
+    // Whether the current episode has ended (or no episode has been started yet).
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    // Number of frames run in the current episode.
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    // Number of actions available to `step`.
+    pub fn num_actions(&self) -> usize {
+        self.config.actions.len()
+    }
+
+    // Get a reference to the underlying emulator (e.g. to inspect RAM or registers).
+    pub fn emulator(&self) -> &Emulator {
+        &self.emu
+    }
+
+    // Helper function to release the previously held key (if different) and press the new one.
+    fn hold_key(&mut self, key: Option<usize>) -> Result<(), EnvError> {
+        if self.held_key == key {
+            return Ok(());
+        }
+        if let Some(previous) = self.held_key.take() {
+            self.emu.release_key(previous)?;
+        }
+        if let Some(key) = key {
+            self.emu.press_key(key)?;
+        }
+        self.held_key = key;
+        Ok(())
+    }
+}
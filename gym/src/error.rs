@@ -0,0 +1,26 @@
+use emulator::EmulatorError;
+use miette::Diagnostic;
+use thiserror::Error;
+
+#[derive(Debug, Error, Diagnostic)]
+pub enum EnvError {
+    #[error("Invalid action {action}, the action set has {num_actions} actions")]
+    #[diagnostic(code(gym::env::invalid_action))]
+    InvalidAction { action: usize, num_actions: usize },
+
+    #[error("The environment must be reset before the first step")]
+    #[diagnostic(code(gym::env::not_reset))]
+    NotReset,
+
+    #[error("Expected {expected} actions (one per environment), got {actual}")]
+    #[diagnostic(code(gym::vec_env::action_count_mismatch))]
+    ActionCountMismatch { expected: usize, actual: usize },
+
+    #[error("Emulator error")]
+    #[diagnostic(transparent)]
+    Emulator {
+        #[from]
+        #[source]
+        source: EmulatorError,
+    },
+}
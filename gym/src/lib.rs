@@ -0,0 +1,12 @@
+// Gym-style reinforcement learning environments over the CHIP-8 emulator.
+mod action;
+mod env;
+mod error;
+mod reward;
+mod vec_env;
+
+pub use action::ActionSet;
+pub use env::{Env, EnvConfig, Observation, Step};
+pub use error::EnvError;
+pub use reward::{RamBcdDelta, RamDelta, RamEquals, Reward};
+pub use vec_env::VecEnv;
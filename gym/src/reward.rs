@@ -0,0 +1,141 @@
+// Computes rewards and episode termination from the contents of RAM after each frame.
+pub trait Reward: Send {
+    // Called when the environment is reset, after the ROM has been loaded.
+    fn reset(&mut self, _ram: &[u8]) {}
+
+    // Reward for the frame that was just run.
+    fn reward(&mut self, ram: &[u8]) -> f32;
+
+    // Whether the episode has ended.
+    fn done(&mut self, _ram: &[u8]) -> bool {
+        false
+    }
+}
+
+// Any closure over RAM can be used as a reward (which never ends the episode).
+impl<F> Reward for F
+where
+    F: FnMut(&[u8]) -> f32 + Send,
+{
+    fn reward(&mut self, ram: &[u8]) -> f32 {
+        self(ram)
+    }
+}
+
+// Rewards the change of a single byte in RAM since the previous frame (e.g. a score counter).
+#[derive(Debug, Clone)]
+pub struct RamDelta {
+    addr: usize,
+    scale: f32,
+    previous: u8,
+}
+
+impl RamDelta {
+    pub fn new(addr: u16) -> Self {
+        Self {
+            addr: addr as usize,
+            scale: 1.0,
+            previous: 0,
+        }
+    }
+
+    // Multiply rewards by the given factor (e.g. -1.0 to penalize an opponent's score).
+    pub fn scaled(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+}
+
+impl Reward for RamDelta {
+    fn reset(&mut self, ram: &[u8]) {
+        self.previous = read(ram, self.addr);
+    }
+
+    fn reward(&mut self, ram: &[u8]) -> f32 {
+        let current = read(ram, self.addr);
+        let delta = current as f32 - self.previous as f32;
+        self.previous = current;
+        delta * self.scale
+    }
+}
+
+// Rewards the change of a 3 digit BCD number in RAM (as written by FX33, which most games use
+// to draw their score) since the previous frame.
+#[derive(Debug, Clone)]
+pub struct RamBcdDelta {
+    addr: usize,
+    scale: f32,
+    previous: u32,
+}
+
+impl RamBcdDelta {
+    pub fn new(addr: u16) -> Self {
+        Self {
+            addr: addr as usize,
+            scale: 1.0,
+            previous: 0,
+        }
+    }
+
+    // Multiply rewards by the given factor.
+    pub fn scaled(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    // Helper function to decode the hundreds, tens and ones digits at the address.
+    fn value(&self, ram: &[u8]) -> u32 {
+        (self.addr..self.addr + 3).fold(0, |value, addr| value * 10 + read(ram, addr) as u32)
+    }
+}
+
+impl Reward for RamBcdDelta {
+    fn reset(&mut self, ram: &[u8]) {
+        self.previous = self.value(ram);
+    }
+
+    fn reward(&mut self, ram: &[u8]) -> f32 {
+        let current = self.value(ram);
+        let delta = current as f32 - self.previous as f32;
+        self.previous = current;
+        delta * self.scale
+    }
+}
+
+// Wraps another reward, ending the episode once a byte in RAM equals the given value
+// (e.g. a lives counter reaching zero).
+#[derive(Debug, Clone)]
+pub struct RamEquals<R> {
+    inner: R,
+    addr: usize,
+    value: u8,
+}
+
+impl<R: Reward> RamEquals<R> {
+    pub fn new(inner: R, addr: u16, value: u8) -> Self {
+        Self {
+            inner,
+            addr: addr as usize,
+            value,
+        }
+    }
+}
+
+impl<R: Reward> Reward for RamEquals<R> {
+    fn reset(&mut self, ram: &[u8]) {
+        self.inner.reset(ram);
+    }
+
+    fn reward(&mut self, ram: &[u8]) -> f32 {
+        self.inner.reward(ram)
+    }
+
+    fn done(&mut self, ram: &[u8]) -> bool {
+        read(ram, self.addr) == self.value || self.inner.done(ram)
+    }
+}
+
+// Helper function to read a byte from RAM, treating addresses past the end of RAM as zero.
+fn read(ram: &[u8], addr: usize) -> u8 {
+    ram.get(addr).copied().unwrap_or(0)
+}
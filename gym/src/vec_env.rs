@@ -0,0 +1,121 @@
+use std::thread;
+
+use crate::{
+    env::{Env, Observation, Step},
+    error::EnvError,
+    reward::Reward,
+};
+
+// Runs many environments in parallel, one action per environment per step.
+// Environments that finish an episode are reset automatically: the returned `Step` has
+// `done` set and carries the final observation, and the next step starts the new episode.
+pub struct VecEnv<R: Reward> {
+    envs: Vec<Env<R>>,
+    threads: usize,
+}
+
+impl<R: Reward> VecEnv<R> {
+    // Create a vectorized environment using all available CPU cores.
+    pub fn new(envs: Vec<Env<R>>) -> Self {
+        let threads = thread::available_parallelism().map_or(1, |n| n.get());
+        Self::with_threads(envs, threads)
+    }
+
+    // Create a vectorized environment using at most the given number of threads.
+    pub fn with_threads(envs: Vec<Env<R>>, threads: usize) -> Self {
+        Self {
+            envs,
+            threads: threads.max(1),
+        }
+    }
+
+    // Number of environments.
+    pub fn len(&self) -> usize {
+        self.envs.len()
+    }
+
+    // Whether there are no environments.
+    pub fn is_empty(&self) -> bool {
+        self.envs.is_empty()
+    }
+
+    // Get a reference to the environment at the given index.
+    pub fn get(&self, idx: usize) -> Option<&Env<R>> {
+        self.envs.get(idx)
+    }
+
+    // Reset every environment and return their first observations.
+    pub fn reset(&mut self) -> Result<Vec<Observation>, EnvError> {
+        self.run_parallel(|env, _| env.reset(), &vec![0; self.envs.len()])
+            .into_iter()
+            .collect()
+    }
+
+    // Step every environment with its action (`actions[i]` for environment `i`) and return one
+    // result per environment. The actions are checked before any environment is stepped. An
+    // environment whose step fails (an emulator error) ends its episode and is reset on the next
+    // step, while the others carry on.
+    pub fn step(&mut self, actions: &[usize]) -> Result<Vec<Result<Step, EnvError>>, EnvError> {
+        if actions.len() != self.envs.len() {
+            return Err(EnvError::ActionCountMismatch {
+                expected: self.envs.len(),
+                actual: actions.len(),
+            });
+        }
+        for (env, &action) in self.envs.iter().zip(actions) {
+            if action >= env.num_actions() {
+                return Err(EnvError::InvalidAction {
+                    action,
+                    num_actions: env.num_actions(),
+                });
+            }
+        }
+
+        Ok(self.run_parallel(
+            |env, action| {
+                if env.is_done() {
+                    env.reset()?;
+                }
+                env.step(action)
+            },
+            actions,
+        ))
+    }
+
+    // Helper function to run `f` on every environment (with its action), splitting the
+    // environments into one contiguous chunk per thread. Results are returned in order.
+    fn run_parallel<T, F>(&mut self, f: F, actions: &[usize]) -> Vec<T>
+    where
+        T: Send,
+        F: Fn(&mut Env<R>, usize) -> T + Sync,
+    {
+        if self.envs.is_empty() {
+            return Vec::new();
+        }
+
+        let num_envs = self.envs.len();
+        let chunk_size = num_envs.div_ceil(self.threads);
+        let f = &f;
+        thread::scope(|scope| {
+            let handles: Vec<_> = self
+                .envs
+                .chunks_mut(chunk_size)
+                .zip(actions.chunks(chunk_size))
+                .map(|(envs, actions)| {
+                    scope.spawn(move || {
+                        envs.iter_mut()
+                            .zip(actions)
+                            .map(|(env, &action)| f(env, action))
+                            .collect::<Vec<T>>()
+                    })
+                })
+                .collect();
+
+            let mut results = Vec::with_capacity(num_envs);
+            for handle in handles {
+                results.extend(handle.join().expect("Environment thread panicked"));
+            }
+            results
+        })
+    }
+}
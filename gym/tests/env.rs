@@ -0,0 +1,162 @@
+use gym::{ActionSet, Env, EnvConfig, EnvError, RamDelta, RamEquals, VecEnv};
+
+// Counts frames where key 5 is held into RAM at 0x301:
+// 6005 -> V0 = 5, A300 -> I = 0x300, E0A1 -> skip next if key V0 not pressed,
+// 7101 -> V1 += 1, A300 -> I = 0x300, F155 -> store V0 - V1 at I, 1204 -> loop.
+const KEY_COUNTER: [u8; 14] = [
+    0x60, 0x05, 0xA3, 0x00, 0xE0, 0xA1, 0x71, 0x01, 0xA3, 0x00, 0xF1, 0x55, 0x12, 0x04,
+];
+
+fn key_counter_env() -> Env<RamDelta> {
+    let config = EnvConfig {
+        actions: ActionSet::with_noop(&[0x5]),
+        frame_skip: 2,
+        steps_per_frame: 4,
+        ..EnvConfig::default()
+    };
+    Env::new(KEY_COUNTER.to_vec(), config, RamDelta::new(0x301))
+}
+
+#[test]
+fn action_holds_mapped_key() {
+    let mut env = key_counter_env();
+    env.reset().unwrap();
+
+    let noop = env.step(0).unwrap();
+    assert_eq!(noop.reward, 0.0);
+
+    let pressed = env.step(1).unwrap();
+    assert!(pressed.reward > 0.0);
+    assert!(!pressed.done);
+    assert_eq!(env.frames(), 4);
+}
+
+#[test]
+fn step_before_reset_is_rejected() {
+    let mut env = key_counter_env();
+    assert!(matches!(env.step(0), Err(EnvError::NotReset)));
+    env.reset().unwrap();
+    assert!(env.step(0).is_ok());
+}
+
+#[test]
+fn invalid_action_is_rejected() {
+    let mut env = key_counter_env();
+    env.reset().unwrap();
+    assert!(matches!(
+        env.step(2),
+        Err(EnvError::InvalidAction {
+            action: 2,
+            num_actions: 2
+        })
+    ));
+}
+
+#[test]
+fn episode_ends_on_max_frames_and_ram_condition() {
+    let config = EnvConfig {
+        max_frames: Some(10),
+        frame_skip: 3,
+        ..EnvConfig::default()
+    };
+    let mut env = Env::new(KEY_COUNTER.to_vec(), config, RamDelta::new(0x301));
+    env.reset().unwrap();
+    let mut steps = 0;
+    while !env.step(0).unwrap().done {
+        steps += 1;
+    }
+    assert_eq!(steps, 3);
+    assert_eq!(env.frames(), 10);
+
+    // 0x300 holds V0 (5) as soon as the first frame has run.
+    let reward = RamEquals::new(RamDelta::new(0x301), 0x300, 5);
+    let mut env = Env::new(KEY_COUNTER.to_vec(), EnvConfig::default(), reward);
+    env.reset().unwrap();
+    assert!(env.step(0).unwrap().done);
+}
+
+#[test]
+fn closures_can_be_rewards() {
+    let reward = |ram: &[u8]| ram[0x301] as f32;
+    let mut env = Env::new(KEY_COUNTER.to_vec(), EnvConfig::default(), reward);
+    env.reset().unwrap();
+    assert!(env.step(6).unwrap().reward > 0.0);
+}
+
+#[test]
+fn vec_env_matches_sequential_envs() {
+    let actions = [0, 1, 1, 0, 1];
+    let mut vec_env = VecEnv::with_threads((0..5).map(|_| key_counter_env()).collect(), 2);
+    vec_env.reset().unwrap();
+    let parallel = vec_env.step(&actions).unwrap();
+
+    for (action, step) in actions.iter().zip(parallel) {
+        let step = step.unwrap();
+        let mut env = key_counter_env();
+        env.reset().unwrap();
+        let sequential = env.step(*action).unwrap();
+        assert_eq!(sequential.reward, step.reward);
+        assert_eq!(sequential.observation, step.observation);
+    }
+
+    assert!(matches!(
+        vec_env.step(&[0]),
+        Err(EnvError::ActionCountMismatch { .. })
+    ));
+
+    // An invalid action is rejected before any environment is stepped.
+    let frames = vec_env.get(0).unwrap().frames();
+    assert!(matches!(
+        vec_env.step(&[0, 0, 0, 0, 2]),
+        Err(EnvError::InvalidAction { action: 2, .. })
+    ));
+    assert_eq!(vec_env.get(0).unwrap().frames(), frames);
+}
+
+#[test]
+fn vec_env_keeps_stepping_after_an_emulator_error() {
+    // 00FF is not a CHIP-8 instruction, so the first frame fails.
+    let crashing = Env::new(vec![0x00, 0xFF], EnvConfig::default(), RamDelta::new(0x301));
+    let mut vec_env = VecEnv::with_threads(vec![key_counter_env(), crashing], 2);
+    vec_env.reset().unwrap();
+
+    let steps = vec_env.step(&[1, 0]).unwrap();
+    assert!(steps[0].as_ref().unwrap().reward > 0.0);
+    assert!(matches!(steps[1], Err(EnvError::Emulator { .. })));
+    assert_eq!(vec_env.get(0).unwrap().frames(), 2);
+
+    // The failed environment is reset and fails again; the other one carries on.
+    let steps = vec_env.step(&[1, 0]).unwrap();
+    assert!(steps[0].is_ok());
+    assert!(steps[1].is_err());
+    assert_eq!(vec_env.get(0).unwrap().frames(), 4);
+}
+
+#[test]
+fn vec_env_resets_finished_episodes() {
+    let config = EnvConfig {
+        max_frames: Some(4),
+        ..EnvConfig::default()
+    };
+    let envs = (0..3)
+        .map(|_| Env::new(KEY_COUNTER.to_vec(), config.clone(), RamDelta::new(0x301)))
+        .collect();
+    let mut vec_env = VecEnv::new(envs);
+    vec_env.reset().unwrap();
+
+    assert!(
+        vec_env
+            .step(&[0, 0, 0])
+            .unwrap()
+            .iter()
+            .all(|step| step.as_ref().unwrap().done)
+    );
+    assert!(
+        vec_env
+            .step(&[0, 0, 0])
+            .unwrap()
+            .iter()
+            .all(|step| step.as_ref().unwrap().done)
+    );
+    assert_eq!(vec_env.get(0).unwrap().frames(), 4);
+}
@@ -0,0 +1,36 @@
+// Synthesizes the CHIP-8 beep as a 440 Hz square wave, keeping the phase continuous
+// between frames so the tone doesn't click.
+pub const SAMPLE_RATE: u32 = 44_100;
+pub const BEEP_FREQUENCY: u32 = 440;
+const AMPLITUDE: i16 = 4_000;
+
+pub struct Beep {
+    phase: u32, // Position within the current period, in samples times BEEP_FREQUENCY.
+    samples: Vec<i16>,
+}
+
+impl Beep {
+    pub fn new() -> Self {
+        Self {
+            phase: 0,
+            samples: Vec::new(),
+        }
+    }
+
+    // Render `frames` stereo frames (interleaved left / right), silent unless `on` is set.
+    pub fn render(&mut self, frames: usize, on: bool) -> &[i16] {
+        self.samples.clear();
+        for _ in 0..frames {
+            let sample = if !on {
+                0
+            } else if self.phase < SAMPLE_RATE / 2 {
+                AMPLITUDE
+            } else {
+                -AMPLITUDE
+            };
+            self.samples.extend_from_slice(&[sample, sample]);
+            self.phase = (self.phase + BEEP_FREQUENCY) % SAMPLE_RATE;
+        }
+        &self.samples
+    }
+}
@@ -0,0 +1,433 @@
+// libretro core wrapping the CHIP-8 emulator, so it can be run inside libretro frontends (e.g. RetroArch).
+mod audio;
+mod sys;
+
+use std::ffi::{CString, c_char, c_uint, c_void};
+use std::sync::Mutex;
+use std::{ptr, slice};
+
+use emulator::{
+    Emulator,
+    constants::{DISPLAY_HEIGHT, DISPLAY_WIDTH, NUM_KEYS, STATE_SIZE},
+};
+
+use crate::audio::{Beep, SAMPLE_RATE};
+use crate::sys::*;
+
+const FPS: u32 = 60;
+const STEPS_PER_FRAME: usize = 500 / FPS as usize; // 500 Hz CPU.
+const AUDIO_FRAMES_PER_FRAME: usize = (SAMPLE_RATE / FPS) as usize;
+// How long the frontend shows a crash message.
+const MESSAGE_FRAMES: c_uint = 5 * FPS;
+
+const ON_COLOR: u32 = 0x00FF_FFFF;
+const OFF_COLOR: u32 = 0x0000_0000;
+
+// RetroPad button mapped to each CHIP-8 key (index = keypad index). The d-pad maps to 2 / 4 / 6 / 8
+// and A to 5, which most games use for movement and action.
+const JOYPAD_MAP: [c_uint; NUM_KEYS] = [
+    RETRO_DEVICE_ID_JOYPAD_B,      // 0
+    RETRO_DEVICE_ID_JOYPAD_Y,      // 1
+    RETRO_DEVICE_ID_JOYPAD_UP,     // 2
+    RETRO_DEVICE_ID_JOYPAD_X,      // 3
+    RETRO_DEVICE_ID_JOYPAD_LEFT,   // 4
+    RETRO_DEVICE_ID_JOYPAD_A,      // 5
+    RETRO_DEVICE_ID_JOYPAD_RIGHT,  // 6
+    RETRO_DEVICE_ID_JOYPAD_L,      // 7
+    RETRO_DEVICE_ID_JOYPAD_DOWN,   // 8
+    RETRO_DEVICE_ID_JOYPAD_R,      // 9
+    RETRO_DEVICE_ID_JOYPAD_L2,     // A
+    RETRO_DEVICE_ID_JOYPAD_R2,     // B
+    RETRO_DEVICE_ID_JOYPAD_SELECT, // C
+    RETRO_DEVICE_ID_JOYPAD_START,  // D
+    RETRO_DEVICE_ID_JOYPAD_L3,     // E
+    RETRO_DEVICE_ID_JOYPAD_R3,     // F
+];
+
+// Keyboard key (RETROK_* code, which is ASCII for letters and digits) mapped to each CHIP-8 key,
+// using the same QWERTY layout as the desktop frontend.
+const KEYBOARD_MAP: [u8; NUM_KEYS] = [
+    b'x', b'1', b'2', b'3', b'q', b'w', b'e', b'a', b's', b'd', b'z', b'c', b'4', b'r', b'f', b'v',
+];
+
+// Callbacks registered by the frontend.
+struct Callbacks {
+    environment: Option<RetroEnvironmentT>,
+    video_refresh: Option<RetroVideoRefreshT>,
+    audio_sample_batch: Option<RetroAudioSampleBatchT>,
+    input_poll: Option<RetroInputPollT>,
+    input_state: Option<RetroInputStateT>,
+}
+
+// State of the loaded game.
+struct Core {
+    emu: Emulator,
+    rom: Vec<u8>,
+    beep: Beep,
+    framebuffer: Vec<u32>,
+    // Set when an instruction fails. The CPU and timers stay stopped until the game is reset or a
+    // state is loaded, rather than failing again every frame.
+    crashed: bool,
+}
+
+static CALLBACKS: Mutex<Callbacks> = Mutex::new(Callbacks {
+    environment: None,
+    video_refresh: None,
+    audio_sample_batch: None,
+    input_poll: None,
+    input_state: None,
+});
+
+static CORE: Mutex<Option<Core>> = Mutex::new(None);
+
+#[unsafe(no_mangle)]
+pub extern "C" fn retro_api_version() -> c_uint {
+    RETRO_API_VERSION
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn retro_init() {}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn retro_deinit() {
+    *CORE.lock().unwrap() = None;
+}
+
+/// # Safety
+/// `info` must be null or point to a writable `retro_system_info`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn retro_get_system_info(info: *mut RetroSystemInfo) {
+    let Some(info) = (unsafe { info.as_mut() }) else {
+        return;
+    };
+    *info = RetroSystemInfo {
+        library_name: c"chip8-rs".as_ptr(),
+        library_version: concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr() as *const c_char,
+        valid_extensions: c"ch8|c8".as_ptr(),
+        need_fullpath: false,
+        block_extract: false,
+    };
+}
+
+/// # Safety
+/// `info` must be null or point to a writable `retro_system_av_info`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn retro_get_system_av_info(info: *mut RetroSystemAvInfo) {
+    let Some(info) = (unsafe { info.as_mut() }) else {
+        return;
+    };
+    *info = RetroSystemAvInfo {
+        geometry: RetroGameGeometry {
+            base_width: DISPLAY_WIDTH as c_uint,
+            base_height: DISPLAY_HEIGHT as c_uint,
+            max_width: DISPLAY_WIDTH as c_uint,
+            max_height: DISPLAY_HEIGHT as c_uint,
+            aspect_ratio: DISPLAY_WIDTH as f32 / DISPLAY_HEIGHT as f32,
+        },
+        timing: RetroSystemTiming {
+            fps: FPS as f64,
+            sample_rate: SAMPLE_RATE as f64,
+        },
+    };
+}
+
+/// # Safety
+/// `cb` must be a valid environment callback for as long as the core is loaded.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn retro_set_environment(cb: RetroEnvironmentT) {
+    CALLBACKS.lock().unwrap().environment = Some(cb);
+
+    let mut no_game = false;
+    unsafe {
+        cb(
+            RETRO_ENVIRONMENT_SET_SUPPORT_NO_GAME,
+            &mut no_game as *mut bool as *mut c_void,
+        )
+    };
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn retro_set_video_refresh(cb: RetroVideoRefreshT) {
+    CALLBACKS.lock().unwrap().video_refresh = Some(cb);
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn retro_set_audio_sample(_cb: RetroAudioSampleT) {}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn retro_set_audio_sample_batch(cb: RetroAudioSampleBatchT) {
+    CALLBACKS.lock().unwrap().audio_sample_batch = Some(cb);
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn retro_set_input_poll(cb: RetroInputPollT) {
+    CALLBACKS.lock().unwrap().input_poll = Some(cb);
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn retro_set_input_state(cb: RetroInputStateT) {
+    CALLBACKS.lock().unwrap().input_state = Some(cb);
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn retro_set_controller_port_device(_port: c_uint, _device: c_uint) {}
+
+/// # Safety
+/// `game` must be null or point to a `retro_game_info` whose `data` holds `size` readable bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn retro_load_game(game: *const RetroGameInfo) -> bool {
+    let Some(game) = (unsafe { game.as_ref() }) else {
+        return false;
+    };
+    if game.data.is_null() {
+        return false;
+    }
+    let rom = unsafe { slice::from_raw_parts(game.data as *const u8, game.size) }.to_vec();
+
+    let Some(environment) = CALLBACKS.lock().unwrap().environment else {
+        return false;
+    };
+    let mut pixel_format = RETRO_PIXEL_FORMAT_XRGB8888;
+    if !unsafe {
+        environment(
+            RETRO_ENVIRONMENT_SET_PIXEL_FORMAT,
+            &mut pixel_format as *mut c_uint as *mut c_void,
+        )
+    } {
+        return false;
+    }
+    set_input_descriptors(environment);
+
+    let mut emu = Emulator::new();
+    if emu.load_rom(&rom).is_err() {
+        return false;
+    }
+
+    *CORE.lock().unwrap() = Some(Core {
+        emu,
+        rom,
+        beep: Beep::new(),
+        framebuffer: vec![OFF_COLOR; DISPLAY_WIDTH * DISPLAY_HEIGHT],
+        crashed: false,
+    });
+    true
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn retro_load_game_special(
+    _game_type: c_uint,
+    _info: *const RetroGameInfo,
+    _num_info: usize,
+) -> bool {
+    false
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn retro_unload_game() {
+    *CORE.lock().unwrap() = None;
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn retro_reset() {
+    if let Some(core) = CORE.lock().unwrap().as_mut() {
+        core.emu.reset();
+        // The ROM was validated when the game was loaded, so reloading it can't fail.
+        let _ = core.emu.load_rom(&core.rom);
+        core.crashed = false;
+    }
+}
+
+// Run a single frame: poll input, run the CPU, then submit video and audio to the frontend.
+#[unsafe(no_mangle)]
+pub extern "C" fn retro_run() {
+    let (environment, video_refresh, audio_sample_batch, input_poll, input_state) = {
+        let callbacks = CALLBACKS.lock().unwrap();
+        (
+            callbacks.environment,
+            callbacks.video_refresh,
+            callbacks.audio_sample_batch,
+            callbacks.input_poll,
+            callbacks.input_state,
+        )
+    };
+
+    let mut core = CORE.lock().unwrap();
+    let Some(core) = core.as_mut() else {
+        return;
+    };
+
+    // Input
+    if let (Some(input_poll), Some(input_state)) = (input_poll, input_state) {
+        unsafe { input_poll() };
+        for key in 0..NUM_KEYS {
+            let pressed = unsafe {
+                input_state(0, RETRO_DEVICE_JOYPAD, 0, JOYPAD_MAP[key]) != 0
+                    || input_state(0, RETRO_DEVICE_KEYBOARD, 0, KEYBOARD_MAP[key] as c_uint) != 0
+            };
+            // Key indices are always in range, so these can't fail.
+            let _ = if pressed {
+                core.emu.press_key(key)
+            } else {
+                core.emu.release_key(key)
+            };
+        }
+    }
+
+    // CPU. The first error (e.g. an unimplemented opcode) stops the emulator where it is, like the
+    // desktop frontend, and is shown by the frontend.
+    if !core.crashed
+        && let Err(err) = core.emu.run_frame(STEPS_PER_FRAME)
+    {
+        core.crashed = true;
+        if let Some(environment) = environment {
+            show_message(environment, &err);
+        }
+    }
+
+    // Video
+    let pixels = core.emu.get_display_buffer().iter().flatten();
+    for (color, &is_pixel_on) in core.framebuffer.iter_mut().zip(pixels) {
+        *color = if is_pixel_on { ON_COLOR } else { OFF_COLOR };
+    }
+    if let Some(video_refresh) = video_refresh {
+        unsafe {
+            video_refresh(
+                core.framebuffer.as_ptr() as *const c_void,
+                DISPLAY_WIDTH as c_uint,
+                DISPLAY_HEIGHT as c_uint,
+                DISPLAY_WIDTH * size_of::<u32>(),
+            )
+        };
+    }
+
+    // Audio
+    let samples = core.beep.render(
+        AUDIO_FRAMES_PER_FRAME,
+        !core.crashed && core.emu.get_st() > 0,
+    );
+    if let Some(audio_sample_batch) = audio_sample_batch {
+        unsafe { audio_sample_batch(samples.as_ptr(), AUDIO_FRAMES_PER_FRAME) };
+    }
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn retro_serialize_size() -> usize {
+    STATE_SIZE
+}
+
+/// # Safety
+/// `data` must be null or point to `size` writable bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn retro_serialize(data: *mut c_void, size: usize) -> bool {
+    let core = CORE.lock().unwrap();
+    let Some(core) = core.as_ref() else {
+        return false;
+    };
+    if data.is_null() || size < STATE_SIZE {
+        return false;
+    }
+
+    let state = core.emu.save_state();
+    unsafe { ptr::copy_nonoverlapping(state.as_ptr(), data as *mut u8, state.len()) };
+    true
+}
+
+/// # Safety
+/// `data` must be null or point to `size` readable bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn retro_unserialize(data: *const c_void, size: usize) -> bool {
+    let mut core = CORE.lock().unwrap();
+    let Some(core) = core.as_mut() else {
+        return false;
+    };
+    if data.is_null() {
+        return false;
+    }
+
+    let state = unsafe { slice::from_raw_parts(data as *const u8, size) };
+    if core.emu.load_state(state).is_err() {
+        return false;
+    }
+    core.crashed = false;
+    true
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn retro_cheat_reset() {}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn retro_cheat_set(_index: c_uint, _enabled: bool, _code: *const c_char) {}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn retro_get_region() -> c_uint {
+    RETRO_REGION_NTSC
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn retro_get_memory_data(_id: c_uint) -> *mut c_void {
+    ptr::null_mut()
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn retro_get_memory_size(_id: c_uint) -> usize {
+    0
+}
+
+// Show an error and its causes on screen (e.g. as a RetroArch notification).
+fn show_message(environment: RetroEnvironmentT, err: &dyn std::error::Error) {
+    let mut text = format!("CHIP-8 crashed: {err}");
+    let mut source = err.source();
+    while let Some(cause) = source {
+        text.push_str(": ");
+        text.push_str(&cause.to_string());
+        source = cause.source();
+    }
+    // Error messages don't contain NUL bytes.
+    let Ok(text) = CString::new(text) else {
+        return;
+    };
+    let mut message = RetroMessage {
+        msg: text.as_ptr(),
+        frames: MESSAGE_FRAMES,
+    };
+    unsafe {
+        environment(
+            RETRO_ENVIRONMENT_SET_MESSAGE,
+            &mut message as *mut RetroMessage as *mut c_void,
+        )
+    };
+}
+
+// Helper function to describe the RetroPad mapping to the frontend (shown in its input settings).
+fn set_input_descriptors(environment: RetroEnvironmentT) {
+    const NAMES: [&std::ffi::CStr; NUM_KEYS] = [
+        c"Key 0", c"Key 1", c"Key 2", c"Key 3", c"Key 4", c"Key 5", c"Key 6", c"Key 7", c"Key 8",
+        c"Key 9", c"Key A", c"Key B", c"Key C", c"Key D", c"Key E", c"Key F",
+    ];
+
+    let mut descriptors: Vec<RetroInputDescriptor> = JOYPAD_MAP
+        .iter()
+        .zip(NAMES)
+        .map(|(&id, name)| RetroInputDescriptor {
+            port: 0,
+            device: RETRO_DEVICE_JOYPAD,
+            index: 0,
+            id,
+            description: name.as_ptr(),
+        })
+        .collect();
+    descriptors.push(RetroInputDescriptor {
+        port: 0,
+        device: 0,
+        index: 0,
+        id: 0,
+        description: ptr::null(),
+    });
+
+    unsafe {
+        environment(
+            RETRO_ENVIRONMENT_SET_INPUT_DESCRIPTORS,
+            descriptors.as_mut_ptr() as *mut c_void,
+        )
+    };
+}
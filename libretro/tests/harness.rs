@@ -0,0 +1,265 @@
+// Minimal libretro frontend that loads the built core (`libchip8_libretro.so`) and drives it
+// through the C API, the same way RetroArch would.
+use std::env;
+use std::ffi::{CStr, c_char, c_uint, c_void};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use libloading::{Library, Symbol};
+
+const RETRO_ENVIRONMENT_SET_MESSAGE: c_uint = 6;
+const RETRO_ENVIRONMENT_SET_PIXEL_FORMAT: c_uint = 10;
+const RETRO_PIXEL_FORMAT_XRGB8888: c_uint = 1;
+const RETRO_DEVICE_JOYPAD: c_uint = 1;
+
+#[repr(C)]
+struct RetroSystemInfo {
+    library_name: *const c_char,
+    library_version: *const c_char,
+    valid_extensions: *const c_char,
+    need_fullpath: bool,
+    block_extract: bool,
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct RetroSystemAvInfo {
+    base_width: c_uint,
+    base_height: c_uint,
+    max_width: c_uint,
+    max_height: c_uint,
+    aspect_ratio: f32,
+    fps: f64,
+    sample_rate: f64,
+}
+
+#[repr(C)]
+struct RetroGameInfo {
+    path: *const c_char,
+    data: *const c_void,
+    size: usize,
+    meta: *const c_char,
+}
+
+#[repr(C)]
+struct RetroMessage {
+    msg: *const c_char,
+    frames: c_uint,
+}
+
+// What the frontend has received from the core.
+struct Frontend {
+    pixel_format: Option<c_uint>,
+    frames: usize,
+    lit_pixels: usize,
+    audio_frames: usize,
+    loud_samples: usize,
+    pressed: Option<c_uint>,
+    messages: Vec<String>,
+}
+
+static FRONTEND: Mutex<Frontend> = Mutex::new(Frontend {
+    pixel_format: None,
+    frames: 0,
+    lit_pixels: 0,
+    audio_frames: 0,
+    loud_samples: 0,
+    pressed: None,
+    messages: Vec::new(),
+});
+
+unsafe extern "C" fn environment(cmd: c_uint, data: *mut c_void) -> bool {
+    if cmd == RETRO_ENVIRONMENT_SET_PIXEL_FORMAT {
+        FRONTEND.lock().unwrap().pixel_format = Some(unsafe { *(data as *const c_uint) });
+    }
+    if cmd == RETRO_ENVIRONMENT_SET_MESSAGE {
+        let message = unsafe { &*(data as *const RetroMessage) };
+        let text = unsafe { CStr::from_ptr(message.msg) };
+        FRONTEND
+            .lock()
+            .unwrap()
+            .messages
+            .push(text.to_str().unwrap().to_owned());
+    }
+    true
+}
+
+unsafe extern "C" fn video_refresh(
+    data: *const c_void,
+    width: c_uint,
+    height: c_uint,
+    pitch: usize,
+) {
+    assert_eq!((width, height, pitch), (64, 32, 64 * 4));
+    let pixels = unsafe { std::slice::from_raw_parts(data as *const u32, 64 * 32) };
+    let mut frontend = FRONTEND.lock().unwrap();
+    frontend.frames += 1;
+    frontend.lit_pixels = pixels.iter().filter(|&&color| color != 0).count();
+}
+
+unsafe extern "C" fn audio_sample(_left: i16, _right: i16) {}
+
+unsafe extern "C" fn audio_sample_batch(data: *const i16, frames: usize) -> usize {
+    let samples = unsafe { std::slice::from_raw_parts(data, frames * 2) };
+    let mut frontend = FRONTEND.lock().unwrap();
+    frontend.audio_frames += frames;
+    frontend.loud_samples += samples.iter().filter(|&&sample| sample != 0).count();
+    frames
+}
+
+unsafe extern "C" fn input_poll() {}
+
+unsafe extern "C" fn input_state(_port: c_uint, device: c_uint, _index: c_uint, id: c_uint) -> i16 {
+    let pressed = FRONTEND.lock().unwrap().pressed;
+    (device == RETRO_DEVICE_JOYPAD && pressed == Some(id)) as i16
+}
+
+// Path of the built core, which cargo places next to the test executable (`target/<profile>/deps`).
+fn core_path() -> PathBuf {
+    let exe = env::current_exe().unwrap();
+    let dir = exe.parent().unwrap();
+    dir.join(libloading::library_filename("chip8_libretro"))
+}
+
+// Run `frames` frames, returning the number of audio samples that were not silent.
+unsafe fn run_frames(lib: &Library, frames: usize) -> usize {
+    let retro_run: Symbol<unsafe extern "C" fn()> = unsafe { lib.get(b"retro_run") }.unwrap();
+    let before = FRONTEND.lock().unwrap().loud_samples;
+    for _ in 0..frames {
+        unsafe { retro_run() };
+    }
+    FRONTEND.lock().unwrap().loud_samples - before
+}
+
+// libretro cores are global singletons, so everything is checked in a single test.
+#[test]
+fn core_runs_in_minimal_frontend() {
+    unsafe {
+        let lib = Library::new(core_path()).expect("Failed to load the libretro core");
+
+        let api_version: Symbol<unsafe extern "C" fn() -> c_uint> =
+            lib.get(b"retro_api_version").unwrap();
+        assert_eq!(api_version(), 1);
+
+        let get_system_info: Symbol<unsafe extern "C" fn(*mut RetroSystemInfo)> =
+            lib.get(b"retro_get_system_info").unwrap();
+        let mut info: RetroSystemInfo = std::mem::zeroed();
+        get_system_info(&mut info);
+        assert_eq!(
+            CStr::from_ptr(info.library_name).to_str().unwrap(),
+            "chip8-rs"
+        );
+        assert!(!info.need_fullpath);
+
+        let get_av_info: Symbol<unsafe extern "C" fn(*mut RetroSystemAvInfo)> =
+            lib.get(b"retro_get_system_av_info").unwrap();
+        let mut av_info = RetroSystemAvInfo::default();
+        get_av_info(&mut av_info);
+        assert_eq!((av_info.base_width, av_info.base_height), (64, 32));
+        assert_eq!(av_info.fps, 60.0);
+
+        lib.get::<unsafe extern "C" fn(unsafe extern "C" fn(c_uint, *mut c_void) -> bool)>(
+            b"retro_set_environment",
+        )
+        .unwrap()(environment);
+        lib.get::<unsafe extern "C" fn(unsafe extern "C" fn(*const c_void, c_uint, c_uint, usize))>(b"retro_set_video_refresh").unwrap()(video_refresh);
+        lib.get::<unsafe extern "C" fn(unsafe extern "C" fn(i16, i16))>(b"retro_set_audio_sample")
+            .unwrap()(audio_sample);
+        lib.get::<unsafe extern "C" fn(unsafe extern "C" fn(*const i16, usize) -> usize)>(
+            b"retro_set_audio_sample_batch",
+        )
+        .unwrap()(audio_sample_batch);
+        lib.get::<unsafe extern "C" fn(unsafe extern "C" fn())>(b"retro_set_input_poll")
+            .unwrap()(input_poll);
+        lib.get::<unsafe extern "C" fn(unsafe extern "C" fn(c_uint, c_uint, c_uint, c_uint) -> i16)>(b"retro_set_input_state").unwrap()(input_state);
+        lib.get::<unsafe extern "C" fn()>(b"retro_init").unwrap()();
+
+        // Waits for key 5 (RetroPad A), then beeps: 6005 -> V0 = 5, E0A1 -> skip if key V0 not pressed,
+        // 1208 -> jump to beep, 1202 -> loop, 601E -> V0 = 30, F018 -> sound timer = V0, 120C -> loop.
+        let rom: [u8; 14] = [
+            0x60, 0x05, 0xE0, 0xA1, 0x12, 0x08, 0x12, 0x02, 0x60, 0x1E, 0xF0, 0x18, 0x12, 0x0C,
+        ];
+        let game = RetroGameInfo {
+            path: std::ptr::null(),
+            data: rom.as_ptr() as *const c_void,
+            size: rom.len(),
+            meta: std::ptr::null(),
+        };
+        let load_game: Symbol<unsafe extern "C" fn(*const RetroGameInfo) -> bool> =
+            lib.get(b"retro_load_game").unwrap();
+        assert!(load_game(&game));
+        assert_eq!(
+            FRONTEND.lock().unwrap().pixel_format,
+            Some(RETRO_PIXEL_FORMAT_XRGB8888)
+        );
+
+        // Silent until A is pressed.
+        assert_eq!(run_frames(&lib, 5), 0);
+        {
+            let frontend = FRONTEND.lock().unwrap();
+            assert_eq!(frontend.frames, 5);
+            assert_eq!(frontend.audio_frames, 5 * 735);
+        }
+        FRONTEND.lock().unwrap().pressed = Some(8); // RETRO_DEVICE_ID_JOYPAD_A
+        assert!(run_frames(&lib, 5) > 0);
+
+        // Save state, let the beep run out, then restore it and hear the beep again.
+        let serialize_size: Symbol<unsafe extern "C" fn() -> usize> =
+            lib.get(b"retro_serialize_size").unwrap();
+        let serialize: Symbol<unsafe extern "C" fn(*mut c_void, usize) -> bool> =
+            lib.get(b"retro_serialize").unwrap();
+        let unserialize: Symbol<unsafe extern "C" fn(*const c_void, usize) -> bool> =
+            lib.get(b"retro_unserialize").unwrap();
+        let mut state = vec![0u8; serialize_size()];
+        assert!(serialize(state.as_mut_ptr() as *mut c_void, state.len()));
+        FRONTEND.lock().unwrap().pressed = None;
+        run_frames(&lib, 60);
+        assert_eq!(run_frames(&lib, 5), 0);
+        assert!(unserialize(state.as_ptr() as *const c_void, state.len()));
+        assert!(run_frames(&lib, 5) > 0);
+        assert!(!unserialize(
+            state.as_ptr() as *const c_void,
+            state.len() - 1
+        ));
+
+        // The IBM logo draws to the screen.
+        let ibm_logo = include_bytes!("../../test_roms/IBM Logo.ch8");
+        let game = RetroGameInfo {
+            data: ibm_logo.as_ptr() as *const c_void,
+            size: ibm_logo.len(),
+            ..game
+        };
+        lib.get::<unsafe extern "C" fn()>(b"retro_unload_game")
+            .unwrap()();
+        assert!(load_game(&game));
+        run_frames(&lib, 60);
+        assert!(FRONTEND.lock().unwrap().lit_pixels > 0);
+        assert!(FRONTEND.lock().unwrap().messages.is_empty());
+
+        // A crash is reported once and stops the CPU and the beep: 601E -> V0 = 30,
+        // F018 -> sound timer = V0, FFFF -> unimplemented.
+        let crash: [u8; 6] = [0x60, 0x1E, 0xF0, 0x18, 0xFF, 0xFF];
+        let game = RetroGameInfo {
+            data: crash.as_ptr() as *const c_void,
+            size: crash.len(),
+            ..game
+        };
+        lib.get::<unsafe extern "C" fn()>(b"retro_unload_game")
+            .unwrap()();
+        assert!(load_game(&game));
+        assert_eq!(run_frames(&lib, 10), 0);
+        {
+            let frontend = FRONTEND.lock().unwrap();
+            assert_eq!(frontend.messages.len(), 1);
+            assert!(frontend.messages[0].starts_with("CHIP-8 crashed"));
+        }
+        // Resetting runs the ROM (and crashes) again.
+        lib.get::<unsafe extern "C" fn()>(b"retro_reset").unwrap()();
+        run_frames(&lib, 10);
+        assert_eq!(FRONTEND.lock().unwrap().messages.len(), 2);
+
+        lib.get::<unsafe extern "C" fn()>(b"retro_unload_game")
+            .unwrap()();
+        lib.get::<unsafe extern "C" fn()>(b"retro_deinit").unwrap()();
+    }
+}
@@ -0,0 +1,225 @@
+use emulator::{
+    Emulator as Core, EmulatorError,
+    constants::{DISPLAY_HEIGHT, DISPLAY_WIDTH, MEMORY_SIZE, NUM_KEYS, NUM_REGS, STATE_SIZE},
+};
+use numpy::{IntoPyArray, PyArray1, PyArray2, ndarray::Array2};
+use pyo3::{create_exception, exceptions::PyException, prelude::*, types::PyBytes};
+
+create_exception!(
+    chip8,
+    Chip8Error,
+    PyException,
+    "Raised when the emulator reports an error (memory, keypad, CPU or save state)."
+);
+
+// Helper function to convert an emulator error into a Python `Chip8Error`, keeping the error chain.
+fn to_py_err(err: EmulatorError) -> PyErr {
+    let mut message = err.to_string();
+    let mut source = std::error::Error::source(&err);
+    while let Some(cause) = source {
+        message.push_str(": ");
+        message.push_str(&cause.to_string());
+        source = cause.source();
+    }
+    Chip8Error::new_err(message)
+}
+
+/// A CHIP-8 emulator.
+///
+/// The display, RAM and V registers are returned as NumPy array copies, so they can be
+/// kept around for analysis without being affected by later steps.
+#[pyclass(module = "chip8", unsendable)]
+pub struct Emulator {
+    emu: Core,
+}
+
+#[pymethods]
+impl Emulator {
+    /// Create a new emulator. Pass `seed` for a reproducible random number generator (CXNN).
+    #[new]
+    #[pyo3(signature = (seed = None))]
+    fn new(seed: Option<u64>) -> Self {
+        let emu = match seed {
+            Some(seed) => Core::with_seed(seed),
+            None => Core::new(),
+        };
+        Self { emu }
+    }
+
+    /// Reset memory (including any loaded ROM), registers, keypad and display.
+    fn reset(&mut self) {
+        self.emu.reset();
+    }
+
+    /// Load ROM data into memory at the program start address (0x200).
+    fn load_rom(&mut self, data: &[u8]) -> PyResult<()> {
+        self.emu.load_rom(data).map_err(to_py_err)
+    }
+
+    /// Perform `count` CPU cycles (fetch, decode, execute).
+    #[pyo3(signature = (count = 1))]
+    fn step(&mut self, count: usize) -> PyResult<()> {
+        for _ in 0..count {
+            self.emu.cycle().map_err(to_py_err)?;
+        }
+        Ok(())
+    }
+
+    /// Run `frames` display frames, each being `steps` CPU cycles followed by one timer tick.
+    #[pyo3(signature = (steps = 8, frames = 1))]
+    fn run_frame(&mut self, steps: usize, frames: usize) -> PyResult<()> {
+        for _ in 0..frames {
+            self.emu.run_frame(steps).map_err(to_py_err)?;
+        }
+        Ok(())
+    }
+
+    /// Tick (decrement) the delay and sound timers once.
+    fn tick_timers(&mut self) {
+        self.emu.tick_timers();
+    }
+
+    /// Mark the key at index `idx` (0x0 - 0xF) as pressed.
+    fn press_key(&mut self, idx: usize) -> PyResult<()> {
+        self.emu.press_key(idx).map_err(to_py_err)
+    }
+
+    /// Mark the key at index `idx` (0x0 - 0xF) as released.
+    fn release_key(&mut self, idx: usize) -> PyResult<()> {
+        self.emu.release_key(idx).map_err(to_py_err)
+    }
+
+    /// The display as a `(32, 64)` boolean array, indexed as `[y, x]`.
+    #[getter]
+    fn display<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray2<bool>> {
+        let buffer = self.emu.get_display_buffer();
+        Array2::from_shape_fn((DISPLAY_HEIGHT, DISPLAY_WIDTH), |(y, x)| buffer[y][x])
+            .into_pyarray(py)
+    }
+
+    /// The whole of RAM as a `(4096,)` uint8 array.
+    #[getter]
+    fn ram<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<u8>> {
+        PyArray1::from_slice(py, self.emu.get_ram())
+    }
+
+    /// Write bytes into RAM starting at `addr`.
+    fn write_ram(&mut self, addr: u16, data: &[u8]) -> PyResult<()> {
+        self.emu.write_ram(addr, data).map_err(to_py_err)
+    }
+
+    /// The V registers (V0 - VF) as a `(16,)` uint8 array.
+    #[getter]
+    fn v<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<u8>> {
+        PyArray1::from_slice(py, &self.emu.get_registers().v)
+    }
+
+    /// Set V register `idx` (0x0 - 0xF) to `value`.
+    fn set_v(&mut self, idx: usize, value: u8) -> PyResult<()> {
+        if idx >= NUM_REGS {
+            return Err(Chip8Error::new_err(format!(
+                "V register index out of bounds: {idx}"
+            )));
+        }
+        let mut registers = self.emu.get_registers();
+        registers.v[idx] = value;
+        self.emu.set_registers(registers);
+        Ok(())
+    }
+
+    /// Program counter.
+    #[getter]
+    fn pc(&self) -> u16 {
+        self.emu.get_registers().pc
+    }
+
+    #[setter]
+    fn set_pc(&mut self, value: u16) {
+        let mut registers = self.emu.get_registers();
+        registers.pc = value;
+        self.emu.set_registers(registers);
+    }
+
+    /// Stack pointer.
+    #[getter]
+    fn sp(&self) -> u16 {
+        self.emu.get_registers().sp
+    }
+
+    #[setter]
+    fn set_sp(&mut self, value: u16) {
+        let mut registers = self.emu.get_registers();
+        registers.sp = value;
+        self.emu.set_registers(registers);
+    }
+
+    /// Index register (I).
+    #[getter]
+    fn i(&self) -> u16 {
+        self.emu.get_registers().i
+    }
+
+    #[setter]
+    fn set_i(&mut self, value: u16) {
+        let mut registers = self.emu.get_registers();
+        registers.i = value;
+        self.emu.set_registers(registers);
+    }
+
+    /// Delay timer.
+    #[getter]
+    fn dt(&self) -> u8 {
+        self.emu.get_registers().dt
+    }
+
+    #[setter]
+    fn set_dt(&mut self, value: u8) {
+        let mut registers = self.emu.get_registers();
+        registers.dt = value;
+        self.emu.set_registers(registers);
+    }
+
+    /// Sound timer (the beep plays while it is non-zero).
+    #[getter]
+    fn st(&self) -> u8 {
+        self.emu.get_registers().st
+    }
+
+    #[setter]
+    fn set_st(&mut self, value: u8) {
+        let mut registers = self.emu.get_registers();
+        registers.st = value;
+        self.emu.set_registers(registers);
+    }
+
+    /// Capture the full emulator state as `bytes` (the random number generator state is not included).
+    fn save_state<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &self.emu.save_state())
+    }
+
+    /// Restore the emulator state from `bytes` returned by `save_state`.
+    fn load_state(&mut self, data: &[u8]) -> PyResult<()> {
+        self.emu.load_state(data).map_err(to_py_err)
+    }
+
+    fn __repr__(&self) -> String {
+        let registers = self.emu.get_registers();
+        format!(
+            "Emulator(pc={:#05X}, i={:#05X}, sp={})",
+            registers.pc, registers.i, registers.sp
+        )
+    }
+}
+
+/// Python bindings for the chip8-rs CHIP-8 emulator core.
+#[pymodule(name = "chip8")]
+fn chip8_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Emulator>()?;
+    m.add("Chip8Error", m.py().get_type::<Chip8Error>())?;
+    m.add("DISPLAY_WIDTH", DISPLAY_WIDTH)?;
+    m.add("DISPLAY_HEIGHT", DISPLAY_HEIGHT)?;
+    m.add("MEMORY_SIZE", MEMORY_SIZE)?;
+    m.add("NUM_KEYS", NUM_KEYS)?;
+    m.add("STATE_SIZE", STATE_SIZE)?;
+    Ok(())
+}
@@ -0,0 +1,64 @@
+"""Tests for the Python bindings. Run with `maturin develop && pytest` from the `python` directory."""
+
+from pathlib import Path
+
+import numpy as np
+import pytest
+
+import chip8
+
+IBM_LOGO = (Path(__file__).parents[2] / "test_roms" / "IBM Logo.ch8").read_bytes()
+
+
+def test_display_is_numpy_array():
+    emu = chip8.Emulator(seed=0)
+    assert emu.display.shape == (chip8.DISPLAY_HEIGHT, chip8.DISPLAY_WIDTH)
+    assert emu.display.dtype == np.bool_
+    assert not emu.display.any()
+
+
+def test_ibm_logo_draws_to_display():
+    emu = chip8.Emulator(seed=0)
+    emu.load_rom(IBM_LOGO)
+    emu.run_frame(steps=8, frames=60)
+    assert emu.display.sum() > 0
+
+
+def test_ram_contains_rom():
+    emu = chip8.Emulator(seed=0)
+    emu.load_rom(IBM_LOGO)
+    assert emu.ram.shape == (chip8.MEMORY_SIZE,)
+    assert bytes(emu.ram[0x200 : 0x200 + len(IBM_LOGO)]) == IBM_LOGO
+
+
+def test_registers_are_writable():
+    emu = chip8.Emulator(seed=0)
+    # 8014 -> V0 += V1, set VF to carry.
+    emu.write_ram(0x200, bytes([0x80, 0x14]))
+    emu.set_v(0, 0xFF)
+    emu.set_v(1, 0x02)
+    emu.step()
+    assert emu.v[0] == 0x01
+    assert emu.v[0xF] == 1
+    assert emu.pc == 0x202
+
+
+def test_save_state_round_trips():
+    emu = chip8.Emulator(seed=0)
+    emu.load_rom(IBM_LOGO)
+    emu.run_frame(steps=8, frames=10)
+    state = emu.save_state()
+    assert len(state) == chip8.STATE_SIZE
+
+    display = emu.display
+    emu.reset()
+    emu.load_state(state)
+    assert np.array_equal(emu.display, display)
+
+
+def test_errors_raise_chip8_error():
+    emu = chip8.Emulator(seed=0)
+    with pytest.raises(chip8.Chip8Error):
+        emu.press_key(chip8.NUM_KEYS)
+    with pytest.raises(chip8.Chip8Error):
+        emu.load_state(b"not a save state")
@@ -0,0 +1,370 @@
+#!/usr/bin/env python3
+# Writes this repository's own conformance ROMs, `Self-Test Opcodes.ch8`, `Self-Test Flags.ch8`
+# and `Self-Test Quirks.ch8`, next to this script. They are unrelated to other CHIP-8 test suites
+# (e.g. Timendus' `chip8-test-suite`). After changing a ROM, run this again and update the ROM's
+# SHA-1 in `desktop/data/test-suite.toml` (`chip8-emu test-suite --hashes test_roms` lists it).
+#
+# Each ROM runs a list of checks and draws a mark per check, left to right and top to bottom in
+# slots of 8x8 pixels: a tick if the check passed, a cross if it failed.
+from pathlib import Path
+
+PROGRAM_START = 0x200
+TICK = [0x01, 0x02, 0x84, 0x48, 0x30]
+CROSS = [0x88, 0x50, 0x20, 0x50, 0x88]
+# VC and VD hold the position of the mark being drawn, VE the result of a quirk check, so checks
+# don't use them otherwise.
+X, Y, E = 0xC, 0xD, 0xE
+
+
+class Rom:
+    def __init__(self):
+        # ("label", name) or ("code", size, bytes_for(labels)).
+        self.items = []
+        self.checks = 0
+        self.labels = 0
+
+    def new_label(self):
+        self.labels += 1
+        return f"_{self.labels}"
+
+    def label(self, name):
+        self.items.append(("label", name))
+
+    def op(self, opcode):
+        self.items.append(("code", 2, lambda labels: opcode.to_bytes(2, "big")))
+
+    # An instruction whose opcode depends on the addresses of labels.
+    def op_with(self, opcode_for):
+        self.items.append(("code", 2, lambda labels: opcode_for(labels).to_bytes(2, "big")))
+
+    # An NNN instruction (1NNN, 2NNN, ANNN, BNNN) addressing a label.
+    def op_to(self, prefix, label):
+        self.op_with(lambda labels: prefix << 12 | labels[label])
+
+    def data(self, data):
+        self.items.append(("code", len(data), lambda labels: bytes(data)))
+
+    def ld(self, x, nn):
+        self.op(0x6000 | x << 8 | nn)
+
+    def alu(self, x, y, n):
+        self.op(0x8000 | x << 8 | y << 4 | n)
+
+    # Draw the next mark: a tick if VX equals NN for every `(x, nn)`, a cross otherwise.
+    def expect(self, *pairs):
+        fail = self.new_label()
+        for x, nn in pairs:
+            self.op(0x3000 | x << 8 | nn)  # SE VX, NN
+            self.op_to(0x1, fail)
+        self.mark(fail)
+
+    # Draw the next mark: a tick if VE equals VY, a cross otherwise.
+    def expect_quirk(self, y):
+        fail = self.new_label()
+        self.op(0x5000 | E << 8 | y << 4)  # SE VE, VY
+        self.op_to(0x1, fail)
+        self.mark(fail)
+
+    def mark(self, fail):
+        draw = self.new_label()
+        self.op_to(0xA, "tick")
+        self.op_to(0x1, draw)
+        self.label(fail)
+        self.op_to(0xA, "cross")
+        self.label(draw)
+        self.ld(X, self.checks % 8 * 8)
+        self.ld(Y, self.checks // 8 * 8)
+        self.op(0xD005 | X << 8 | Y << 4)
+        self.checks += 1
+
+    def assemble(self):
+        self.label("end")
+        self.op_to(0x1, "end")
+        self.label("tick")
+        self.data(TICK)
+        self.label("cross")
+        self.data(CROSS)
+
+        labels, addr = {}, PROGRAM_START
+        for item in self.items:
+            if item[0] == "label":
+                labels[item[1]] = addr
+            else:
+                addr += item[1]
+        return b"".join(item[2](labels) for item in self.items if item[0] == "code")
+
+
+def opcodes():
+    rom = Rom()
+    rom.op(0x00E0)
+
+    # 3XNN, 4XNN, 5XY0, 9XY0: V0 counts the instructions that weren't skipped.
+    rom.ld(0, 0)
+    rom.ld(1, 0x42)
+    rom.ld(2, 0x42)
+    rom.ld(3, 0)
+    for opcode in [0x3142, 0x4143, 0x5120, 0x9130]:  # skip
+        rom.op(opcode)
+        rom.op(0x7001)
+    for opcode in [0x3143, 0x4142, 0x5130, 0x9120]:  # don't skip
+        rom.op(opcode)
+        rom.op(0x7001)
+    rom.expect((0, 4))
+
+    # 6XNN, 7XNN (wraps around without touching VF).
+    rom.ld(0xF, 0x55)
+    rom.ld(0, 0xFF)
+    rom.op(0x7002)
+    rom.expect((0, 0x01), (0xF, 0x55))
+
+    # 8XY0 - 8XY3.
+    rom.ld(1, 0x3C)
+    rom.ld(2, 0x0F)
+    for n, result in [(0, 0x0F), (1, 0x3F), (2, 0x0C), (3, 0x33)]:
+        rom.alu(0, 1, 0)
+        rom.alu(0, 2, n)
+        rom.expect((0, result))
+
+    # 8XY4, 8XY5, 8XY7 results (the flags are checked by the flags ROM).
+    rom.ld(1, 0x20)
+    for vx, n, result in [(0xF0, 4, 0x10), (0x10, 5, 0xF0), (0x10, 7, 0x10)]:
+        rom.ld(0, vx)
+        rom.alu(0, 1, n)
+        rom.expect((0, result))
+
+    # 8XY6, 8XYE with VX = VY, so the result doesn't depend on the shift quirk.
+    for n, result in [(6, 0x40), (0xE, 0x02)]:
+        rom.ld(0, 0x81)
+        rom.ld(1, 0x81)
+        rom.alu(0, 1, n)
+        rom.expect((0, result))
+
+    # 2NNN / 00EE: the subroutine sets V0, the caller V1 after it returns. 1NNN skips over the
+    # subroutine.
+    after = rom.new_label()
+    rom.ld(0, 0)
+    rom.ld(1, 0)
+    rom.op_to(0x2, "subroutine")
+    rom.ld(1, 2)
+    rom.expect((0, 1), (1, 2))
+    rom.op_to(0x1, after)
+    rom.label("subroutine")
+    rom.ld(0, 1)
+    rom.op(0x00EE)
+    rom.label(after)
+
+    # BNNN: V0 equals the register selected by the jump quirk, so both jump to the same place.
+    rom.ld(0, 2)
+    rom.op_with(lambda labels: 0x6002 | (labels["jump"] >> 8 & 0xF) << 8)
+    rom.ld(1, 0)
+    rom.op_to(0xB, "jump")
+    rom.label("jump")
+    rom.ld(1, 1)  # skipped by the jump
+    rom.ld(1, 2)
+    rom.expect((1, 2))
+
+    # ANNN, FX55, FX1E, FX65: store two registers, then load the second one back.
+    rom.op_to(0xA, "scratch")
+    rom.ld(0, 0x12)
+    rom.ld(1, 0x34)
+    rom.op(0xF155)
+    rom.op_to(0xA, "scratch")
+    rom.ld(2, 1)
+    rom.op(0xF21E)
+    rom.op(0xF065)
+    rom.expect((0, 0x34))
+
+    # FX33.
+    rom.op_to(0xA, "scratch")
+    rom.ld(3, 254)
+    rom.op(0xF333)
+    rom.op_to(0xA, "scratch")
+    rom.op(0xF265)
+    rom.expect((0, 2), (1, 5), (2, 4))
+
+    # FX29: the first row of the "7" glyph.
+    rom.ld(0, 7)
+    rom.op(0xF029)
+    rom.op(0xF065)
+    rom.expect((0, 0xF0))
+
+    # FX15 / FX07: the delay timer may tick once in between.
+    rom.ld(0, 0x20)
+    rom.op(0xF015)
+    rom.op(0xF107)
+    rom.op(0x411F)  # SNE V1, 0x1F
+    rom.ld(1, 0x20)
+    rom.expect((1, 0x20))
+
+    # CXNN with NN = 0 is always 0.
+    rom.ld(0, 0xFF)
+    rom.op(0xC000)
+    rom.expect((0, 0))
+
+    # DXYN sets VF on collision: draw the same sprite twice in the bottom right corner.
+    rom.ld(0, 56)
+    rom.ld(1, 27)
+    rom.op_to(0xA, "cross")
+    rom.op(0xD015)
+    rom.alu(2, 0xF, 0)
+    rom.op(0xD015)
+    rom.expect((2, 0), (0xF, 1))
+
+    # EX9E / EXA1 without any key held.
+    rom.ld(0, 5)
+    rom.ld(1, 0)
+    rom.op(0xE09E)
+    rom.op(0x7101)
+    rom.op(0xE0A1)
+    rom.op(0x7101)
+    rom.expect((1, 1))
+
+    rom.op_to(0x1, "end")
+    rom.label("scratch")
+    rom.data([0, 0, 0])
+    return rom
+
+
+def flags():
+    rom = Rom()
+    rom.op(0x00E0)
+
+    # (VX, VY, N, result, VF), with VX = VY for the shifts.
+    for vx, vy, n, result, vf in [
+        (0x10, 0x20, 4, 0x30, 0),
+        (0xF0, 0x20, 4, 0x10, 1),
+        (0x30, 0x10, 5, 0x20, 1),
+        (0x10, 0x20, 5, 0xF0, 0),
+        (0x10, 0x30, 7, 0x20, 1),
+        (0x20, 0x10, 7, 0xF0, 0),
+        (0x82, 0x82, 6, 0x41, 0),
+        (0x83, 0x83, 6, 0x41, 1),
+        (0x41, 0x41, 0xE, 0x82, 0),
+        (0xC1, 0xC1, 0xE, 0x82, 1),
+    ]:
+        rom.ld(0, vx)
+        rom.ld(1, vy)
+        rom.alu(0, 1, n)
+        rom.expect((0, result), (0xF, vf))
+
+    # With VF as VX, the flag overwrites the result.
+    for vx, vy, n, vf in [(0xFF, 0x01, 4, 1), (0x01, 0x02, 5, 0), (0x02, 0x01, 7, 0)]:
+        rom.ld(0xF, vx)
+        rom.ld(1, vy)
+        rom.alu(0xF, 1, n)
+        rom.expect((0xF, vf))
+    return rom
+
+
+# The quirks expected for each platform, selected with keys 1 - 3:
+# vf-reset, shift-vy, increment-i, jump-vx, wrap-sprites.
+PLATFORMS = [
+    [1, 1, 1, 0, 0],  # 1: chip8
+    [0, 0, 0, 1, 0],  # 2: superchip
+    [0, 1, 1, 0, 1],  # 3: xochip
+]
+
+
+def quirks():
+    rom = Rom()
+    rom.op(0x00E0)
+
+    # Wait for the platform key, then load its expectations into V5 - V9.
+    advance, loaded = rom.new_label(), rom.new_label()
+    rom.op_to(0xA, "expectations")
+    rom.op(0xF00A)
+    rom.ld(1, 5)
+    rom.label("select")
+    rom.op(0x70FF)
+    rom.op(0x3000)  # SE V0, 0
+    rom.op_to(0x1, advance)
+    rom.op_to(0x1, loaded)
+    rom.label(advance)
+    rom.op(0xF11E)
+    rom.op_to(0x1, "select")
+    rom.label(loaded)
+    rom.op(0xF465)
+    for i in range(5):
+        rom.alu(5 + i, i, 0)
+
+    # vf-reset: 8XY1 clears VF.
+    rom.ld(0, 3)
+    rom.ld(1, 5)
+    rom.ld(0xF, 7)
+    rom.alu(0, 1, 1)
+    rom.ld(E, 1)
+    rom.op(0x3F00)  # SE VF, 0
+    rom.ld(E, 0)
+    rom.expect_quirk(5)
+
+    # shift-vy: 8XY6 shifts VY instead of VX.
+    rom.ld(0, 4)
+    rom.ld(1, 8)
+    rom.alu(0, 1, 6)
+    rom.ld(E, 1)
+    rom.op(0x3004)  # SE V0, 4
+    rom.ld(E, 0)
+    rom.expect_quirk(6)
+
+    # increment-i: the second FX65 reads the next byte if the first one advanced I.
+    rom.op_to(0xA, "pair")
+    rom.op(0xF065)
+    rom.op(0xF065)
+    rom.ld(E, 1)
+    rom.op(0x3022)  # SE V0, 0x22
+    rom.ld(E, 0)
+    rom.expect_quirk(7)
+
+    # jump-vx: BXNN adds VX (2) instead of V0 (0).
+    jumped = rom.new_label()
+    rom.ld(0, 0)
+    rom.op_with(lambda labels: 0x6002 | (labels["jumps"] >> 8 & 0xF) << 8)
+    rom.op_to(0xB, "jumps")
+    rom.label("jumps")
+    rom.op_to(0x1, "jump_v0")
+    rom.ld(E, 1)
+    rom.op_to(0x1, jumped)
+    rom.label("jump_v0")
+    rom.ld(E, 0)
+    rom.label(jumped)
+    rom.expect_quirk(8)
+
+    # wrap-sprites: a row drawn across the right edge lights pixel (0, 31) if it wraps. Both
+    # sprites are drawn again to clear the row.
+    rom.ld(0, 60)
+    rom.ld(1, 31)
+    rom.ld(2, 0)
+    rom.op_to(0xA, "row")
+    rom.op(0xD011)
+    rom.op_to(0xA, "dot")
+    rom.op(0xD211)
+    rom.alu(E, 0xF, 0)
+    rom.op(0xD211)
+    rom.op_to(0xA, "row")
+    rom.op(0xD011)
+    rom.expect_quirk(9)
+
+    rom.op_to(0x1, "end")
+    rom.label("pair")
+    rom.data([0x11, 0x22])
+    rom.label("row")
+    rom.data([0xFF])
+    rom.label("dot")
+    rom.data([0x80])
+    rom.label("expectations")
+    for expectations in PLATFORMS:
+        rom.data(expectations)
+    return rom
+
+
+if __name__ == "__main__":
+    here = Path(__file__).parent
+    for name, build in [
+        ("Self-Test Opcodes", opcodes),
+        ("Self-Test Flags", flags),
+        ("Self-Test Quirks", quirks),
+    ]:
+        rom = build()
+        data = rom.assemble()
+        (here / f"{name}.ch8").write_bytes(data)
+        print(f"{name}.ch8: {len(data)} bytes, {rom.checks} checks")
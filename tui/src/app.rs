@@ -0,0 +1,357 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use emulator::{
+    Emulator, EmulatorError, SymbolTable, analyze_rom, constants::NUM_KEYS, disassemble,
+};
+use ratatui::crossterm::event::{
+    self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, KeyboardEnhancementFlags,
+    PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+};
+use ratatui::crossterm::{execute, terminal::supports_keyboard_enhancement};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+
+use anyhow::{Context, Result};
+
+use crate::args::Args;
+use crate::keyboard::{Keypad, map_keyboard};
+use crate::render::Screen;
+
+const FRAME_RATE: u64 = 60;
+const PANEL_WIDTH: u16 = 30;
+// Number of instructions shown before the PC in the disassembly panel.
+const DISASM_CONTEXT: u16 = 5;
+
+pub struct App {
+    pub args: Args,
+    pub emu: Emulator,
+    pub rom: Vec<u8>,
+    pub keypad: Keypad,
+    pub paused: bool,
+    pub symbols: SymbolTable,
+    pub breakpoints: BTreeSet<u16>,
+    // Paused at a breakpoint, which is passed when resuming.
+    pub at_breakpoint: bool,
+    pub error: Option<String>,
+    pub last_st: u8,
+    pub quit: bool,
+}
+
+impl App {
+    pub fn new(args: Args) -> Result<Self> {
+        let rom = fs::read(&args.rom_path)
+            .with_context(|| format!("Failed to read ROM file '{}'", args.rom_path))?;
+
+        let quirks = match args.platform {
+            Some(platform) => platform.quirks(),
+            None => analyze_rom(&rom).quirks,
+        };
+        let mut emu = Emulator::new();
+        emu.set_quirks(quirks);
+        emu.load_rom(&rom).context("Failed to load ROM")?;
+
+        let symbols = match &args.symbols {
+            Some(path) => {
+                let text = fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read symbol file '{}'", path.display()))?;
+                SymbolTable::parse(&text)
+                    .with_context(|| format!("Invalid symbol file '{}'", path.display()))?
+            }
+            None => SymbolTable::new(),
+        };
+        let breakpoints = args
+            .breakpoints
+            .iter()
+            .map(|location| {
+                symbols
+                    .resolve(location)
+                    .with_context(|| format!("Invalid breakpoint '{location}'"))
+            })
+            .collect::<Result<_>>()?;
+
+        let keypad = Keypad::new(Some(Duration::from_millis(args.key_hold_ms)));
+        Ok(Self {
+            args,
+            emu,
+            rom,
+            keypad,
+            paused: false,
+            symbols,
+            breakpoints,
+            at_breakpoint: false,
+            error: None,
+            last_st: 0,
+            quit: false,
+        })
+    }
+
+    pub fn run(mut self) -> Result<()> {
+        let mut terminal = ratatui::init();
+
+        // Terminals supporting the kitty keyboard protocol can report key releases, which makes
+        // the key hold timeout unnecessary.
+        let enhanced = supports_keyboard_enhancement().unwrap_or(false);
+        if enhanced {
+            execute!(
+                io::stdout(),
+                PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::REPORT_EVENT_TYPES)
+            )?;
+            self.keypad = Keypad::new(None);
+        }
+
+        let result = self.event_loop(&mut terminal);
+
+        if enhanced {
+            let _ = execute!(io::stdout(), PopKeyboardEnhancementFlags);
+        }
+        ratatui::restore();
+        result
+    }
+
+    fn event_loop(&mut self, terminal: &mut DefaultTerminal) -> Result<()> {
+        let frame_duration = Duration::from_micros(1_000_000 / FRAME_RATE);
+        let mut next_frame = Instant::now();
+
+        while !self.quit {
+            // Handle input until the next frame is due.
+            let timeout = next_frame.saturating_duration_since(Instant::now());
+            if event::poll(timeout).context("Failed to poll terminal events")? {
+                let event = event::read().context("Failed to read terminal event")?;
+                self.handle_event(event);
+                continue;
+            }
+
+            let now = Instant::now();
+            self.keypad.expire(&mut self.emu, now);
+            if !self.paused {
+                self.run_frame();
+            }
+            terminal
+                .draw(|frame| self.draw(frame))
+                .context("Failed to draw to the terminal")?;
+
+            // Don't try to catch up after a stall (e.g. a suspended terminal).
+            next_frame = (next_frame + frame_duration).max(now);
+        }
+        Ok(())
+    }
+
+    fn steps_per_frame(&self) -> usize {
+        self.args
+            .steps_per_frame
+            .unwrap_or((self.args.cpu_frequency as u64 / FRAME_RATE) as usize)
+    }
+
+    fn run_frame(&mut self) {
+        if let Err(err) = self.run_cycles(self.steps_per_frame()) {
+            self.halt(&err);
+        }
+
+        let st = self.emu.get_st();
+        if self.args.bell && self.last_st == 0 && st > 0 {
+            let mut stdout = io::stdout();
+            let _ = stdout.write_all(b"\x07").and_then(|()| stdout.flush());
+        }
+        self.last_st = st;
+    }
+
+    // Run a frame's cycles and tick the timers, unless the PC reaches a breakpoint first.
+    fn run_cycles(&mut self, steps: usize) -> Result<(), EmulatorError> {
+        if self.breakpoints.is_empty() {
+            return self.emu.run_frame(steps);
+        }
+        for _ in 0..steps {
+            if !self.at_breakpoint && self.breakpoints.contains(&self.emu.get_registers().pc) {
+                self.at_breakpoint = true;
+                self.paused = true;
+                return Ok(());
+            }
+            self.at_breakpoint = false;
+            self.emu.cycle()?;
+        }
+        self.emu.tick_timers();
+        Ok(())
+    }
+
+    // Pause on an error and show it in the status bar, rather than repeating it every frame.
+    fn halt(&mut self, err: &dyn std::error::Error) {
+        let mut message = err.to_string();
+        let mut source = err.source();
+        while let Some(cause) = source {
+            message.push_str(": ");
+            message.push_str(&cause.to_string());
+            source = cause.source();
+        }
+        self.error = Some(message);
+        self.paused = true;
+    }
+
+    fn reset(&mut self) {
+        self.emu.reset();
+        if let Err(err) = self.emu.load_rom(&self.rom) {
+            self.halt(&err);
+            return;
+        }
+        self.error = None;
+        self.paused = false;
+        self.at_breakpoint = false;
+        self.last_st = 0;
+    }
+
+    fn handle_event(&mut self, event: Event) {
+        let Event::Key(KeyEvent {
+            code,
+            modifiers,
+            kind,
+            ..
+        }) = event
+        else {
+            return;
+        };
+
+        if kind == KeyEventKind::Release {
+            if let Some(idx) = map_keyboard(code) {
+                self.keypad.release(&mut self.emu, idx);
+            }
+            return;
+        }
+
+        match code {
+            KeyCode::Esc => self.quit = true,
+            // Raw mode swallows SIGINT, so handle Ctrl+C here.
+            KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => self.quit = true,
+            KeyCode::F(5) if kind == KeyEventKind::Press => self.paused = !self.paused,
+            KeyCode::F(6) if self.paused => {
+                self.at_breakpoint = false;
+                if let Err(err) = self.emu.cycle() {
+                    self.halt(&err);
+                }
+            }
+            KeyCode::F(7) if kind == KeyEventKind::Press => self.reset(),
+            code => {
+                if let Some(idx) = map_keyboard(code) {
+                    self.keypad.press(&mut self.emu, idx, Instant::now());
+                }
+            }
+        }
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let (screen_width, screen_height) = Screen::size(self.args.render);
+        let panel_width = if self.args.no_panel { 0 } else { PANEL_WIDTH };
+
+        let [main, status] = Layout::vertical([
+            Constraint::Length(screen_height.max(20) + 2),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [screen_area, panel_area] = Layout::horizontal([
+            Constraint::Length(screen_width + 2),
+            Constraint::Length(panel_width),
+        ])
+        .areas(main);
+        let [screen_area] =
+            Layout::vertical([Constraint::Length(screen_height + 2)]).areas(screen_area);
+
+        let title = Path::new(&self.args.rom_path)
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let screen_block = Block::bordered().title(format!(" {title} "));
+        let screen_inner = screen_block.inner(screen_area);
+        frame.render_widget(screen_block, screen_area);
+        frame.render_widget(
+            Screen {
+                display: self.emu.get_display_buffer(),
+                mode: self.args.render,
+            },
+            screen_inner,
+        );
+
+        if !self.args.no_panel {
+            frame.render_widget(
+                Paragraph::new(self.panel_lines(panel_area.height.saturating_sub(2)))
+                    .block(Block::bordered().title(" CPU ")),
+                panel_area,
+            );
+        }
+
+        let status_line = match (&self.error, self.paused) {
+            (Some(error), _) => Line::from(format!(" Halted: {error} (F7 reset)")).red(),
+            (None, true) if self.at_breakpoint => Line::from(format!(
+                " Breakpoint at {}: F5 resume  F6 step  F7 reset  Esc quit",
+                self.symbols.describe(self.emu.get_registers().pc)
+            ))
+            .yellow(),
+            (None, true) => Line::from(" Paused: F5 resume  F6 step  F7 reset  Esc quit").yellow(),
+            (None, false) => Line::from(" F5 pause  F6 step  F7 reset  Esc quit").dim(),
+        };
+        frame.render_widget(status_line, status);
+    }
+
+    // Registers, keypad and a disassembly window around the PC, fitted to `height` lines.
+    fn panel_lines(&self, height: u16) -> Vec<Line<'static>> {
+        let registers = self.emu.get_registers();
+        let mut lines = vec![
+            Line::from(format!(
+                "PC {:03X}  I {:03X}  SP {:X}",
+                registers.pc, registers.i, registers.sp
+            )),
+            Line::from(format!("DT {:02X}   ST {:02X}", registers.dt, registers.st)),
+        ];
+        for (row_idx, values) in registers.v.chunks(4).enumerate() {
+            let spans: Vec<Span> = values
+                .iter()
+                .enumerate()
+                .map(|(col, value)| Span::raw(format!("V{:X} {value:02X}  ", row_idx * 4 + col)))
+                .collect();
+            lines.push(Line::from(spans));
+        }
+
+        let keys: Vec<Span> = (0..NUM_KEYS)
+            .map(|idx| {
+                let key = Span::raw(format!("{idx:X}"));
+                if self.keypad.is_pressed(idx) {
+                    key.reversed()
+                } else {
+                    key.dim()
+                }
+            })
+            .collect();
+        lines.push(Line::from(keys));
+        lines.push(Line::default());
+
+        let count = (height as usize).saturating_sub(lines.len());
+        let start = registers.pc.saturating_sub(DISASM_CONTEXT * 2);
+        for line in disassemble(self.emu.get_ram(), start, count) {
+            if let Some(name) = self.symbols.name(line.addr) {
+                lines.push(Line::from(format!("{name}:")).dim());
+            }
+            let text = format!(
+                "{:03X}  {:04X}  {}",
+                line.addr,
+                line.opcode,
+                self.symbols.instruction(line.instruction)
+            );
+            if line.addr == registers.pc {
+                lines.push(Line::styled(
+                    text,
+                    Style::new().add_modifier(Modifier::REVERSED),
+                ));
+            } else if self.breakpoints.contains(&line.addr) {
+                lines.push(Line::from(text).red());
+            } else {
+                lines.push(Line::from(text));
+            }
+        }
+        lines.truncate(height as usize);
+        lines
+    }
+}
@@ -0,0 +1,61 @@
+use std::path::PathBuf;
+
+use clap::{Parser, ValueEnum};
+use emulator::Platform;
+
+#[derive(Parser, Debug)]
+#[command(
+    author = "Teo Welton",
+    version = "0.0.1",
+    about = "A CHIP-8 emulator for the terminal."
+)]
+pub struct Args {
+    /// Path to the ROM file to be loaded.
+    pub rom_path: String,
+
+    /// CPU frequency in Hz (default: 500)
+    #[arg(short = 'f', long, value_name = "HZ", default_value_t = 500)]
+    pub cpu_frequency: u32,
+
+    /// Number of CPU steps per frame (overrides calculated value using CPU frequency and 60 Hz frames)
+    #[arg(short = 's', long, value_name = "STEPS")]
+    pub steps_per_frame: Option<usize>,
+
+    /// Platform whose quirks to emulate: chip8, superchip or xochip (default: guessed from the ROM)
+    #[arg(short = 'p', long, value_name = "PLATFORM")]
+    pub platform: Option<Platform>,
+
+    /// How display pixels are drawn with Unicode characters.
+    #[arg(short = 'r', long, value_enum, default_value_t = RenderMode::HalfBlock)]
+    pub render: RenderMode,
+
+    /// How long a key stays pressed after the terminal reports it, for terminals without key release events.
+    #[arg(long, value_name = "MS", default_value_t = 150)]
+    pub key_hold_ms: u64,
+
+    /// Ring the terminal bell when the sound timer starts.
+    #[arg(short = 'b', long)]
+    pub bell: bool,
+
+    /// Hide the register and disassembly panel.
+    #[arg(long)]
+    pub no_panel: bool,
+
+    /// Symbol file (`name 0x2A0` per line, e.g. from an assembler) used to show labels in the
+    /// disassembly and to set breakpoints by name.
+    #[arg(long, value_name = "FILE")]
+    pub symbols: Option<PathBuf>,
+
+    /// Pause when the PC reaches this location: an address (0x2A4), a symbol (draw_paddle) or a
+    /// symbol with an offset (draw_paddle+0x4). Can be given several times.
+    #[arg(long = "break", value_name = "LOCATION")]
+    pub breakpoints: Vec<String>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RenderMode {
+    /// Two pixels per character cell using `▀` (keeps the 2:1 pixel aspect of most terminal fonts).
+    HalfBlock,
+    /// Eight pixels per character cell using braille patterns (smaller, but works in monochrome).
+    Braille,
+}
@@ -0,0 +1,82 @@
+use std::time::{Duration, Instant};
+
+use emulator::{Emulator, constants::NUM_KEYS};
+use ratatui::crossterm::event::KeyCode;
+
+// Maps a terminal key to a CHIP-8 keypad index.
+// CHIP-8    QWERTY
+// 1 2 3 C   1 2 3 4
+// 4 5 6 D   Q W E R
+// 7 8 9 E   A S D F
+// A 0 B F   Z X C V
+pub fn map_keyboard(code: KeyCode) -> Option<usize> {
+    let KeyCode::Char(c) = code else {
+        return None;
+    };
+    match c.to_ascii_lowercase() {
+        '1' => Some(0x1),
+        '2' => Some(0x2),
+        '3' => Some(0x3),
+        '4' => Some(0xC),
+        'q' => Some(0x4),
+        'w' => Some(0x5),
+        'e' => Some(0x6),
+        'r' => Some(0xD),
+        'a' => Some(0x7),
+        's' => Some(0x8),
+        'd' => Some(0x9),
+        'f' => Some(0xE),
+        'z' => Some(0xA),
+        'x' => Some(0x0),
+        'c' => Some(0xB),
+        'v' => Some(0xF),
+        _ => None,
+    }
+}
+
+// Tracks which keypad keys are held down.
+//
+// Most terminals only report key presses (and auto-repeated presses while a key is held), never
+// releases. Without release events, a key is treated as held until `hold` has passed since it was
+// last reported. Terminals supporting the kitty keyboard protocol report real releases, in which
+// case the timeout is disabled.
+pub struct Keypad {
+    hold: Option<Duration>,
+    last_pressed: [Option<Instant>; NUM_KEYS],
+}
+
+impl Keypad {
+    pub fn new(hold: Option<Duration>) -> Self {
+        Self {
+            hold,
+            last_pressed: [None; NUM_KEYS],
+        }
+    }
+
+    pub fn press(&mut self, emu: &mut Emulator, idx: usize, now: Instant) {
+        self.last_pressed[idx] = Some(now);
+        let _ = emu.press_key(idx);
+    }
+
+    pub fn release(&mut self, emu: &mut Emulator, idx: usize) {
+        self.last_pressed[idx] = None;
+        let _ = emu.release_key(idx);
+    }
+
+    // Release all keys that have not been reported for longer than the hold time.
+    pub fn expire(&mut self, emu: &mut Emulator, now: Instant) {
+        let Some(hold) = self.hold else {
+            return;
+        };
+        for (idx, last_pressed) in self.last_pressed.iter_mut().enumerate() {
+            if last_pressed.is_some_and(|at| now.duration_since(at) >= hold) {
+                *last_pressed = None;
+                let _ = emu.release_key(idx);
+            }
+        }
+    }
+
+    pub fn is_pressed(&self, idx: usize) -> bool {
+        self.last_pressed[idx].is_some()
+    }
+}
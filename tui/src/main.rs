@@ -0,0 +1,20 @@
+mod app;
+mod args;
+mod keyboard;
+mod render;
+
+use anyhow::Result;
+use app::App;
+use args::Args;
+use clap::Parser;
+
+fn main() -> Result<()> {
+    // Parse command line arguments.
+    let args = Args::parse();
+
+    // Initialize application (loads the ROM before the terminal is taken over, so errors stay readable).
+    let app = App::new(args)?;
+
+    // Run the application until the user quits.
+    app.run()
+}
@@ -0,0 +1,64 @@
+use emulator::constants::{DISPLAY_HEIGHT, DISPLAY_WIDTH};
+use ratatui::{buffer::Buffer, layout::Rect, style::Color, widgets::Widget};
+
+use crate::args::RenderMode;
+
+const PIXEL_ON: Color = Color::White;
+const PIXEL_OFF: Color = Color::Black;
+
+// Braille dot bits, indexed as [y][x] within a 2x4 cell.
+const BRAILLE_DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+// Widget drawing the CHIP-8 display with Unicode block or braille characters.
+pub struct Screen<'a> {
+    pub display: &'a [[bool; DISPLAY_WIDTH]; DISPLAY_HEIGHT],
+    pub mode: RenderMode,
+}
+
+impl Screen<'_> {
+    // Size of the rendered display in character cells (width, height).
+    pub fn size(mode: RenderMode) -> (u16, u16) {
+        match mode {
+            RenderMode::HalfBlock => (DISPLAY_WIDTH as u16, DISPLAY_HEIGHT as u16 / 2),
+            RenderMode::Braille => (DISPLAY_WIDTH as u16 / 2, DISPLAY_HEIGHT as u16 / 4),
+        }
+    }
+}
+
+impl Widget for Screen<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let (width, height) = Screen::size(self.mode);
+
+        for cell_y in 0..height.min(area.height) {
+            for cell_x in 0..width.min(area.width) {
+                let Some(cell) = buf.cell_mut((area.x + cell_x, area.y + cell_y)) else {
+                    continue;
+                };
+                let (x, y) = (cell_x as usize, cell_y as usize);
+
+                match self.mode {
+                    RenderMode::HalfBlock => {
+                        // The upper half of the cell is the foreground, the lower half the background.
+                        let top = self.display[y * 2][x];
+                        let bottom = self.display[y * 2 + 1][x];
+                        cell.set_symbol("▀")
+                            .set_fg(if top { PIXEL_ON } else { PIXEL_OFF })
+                            .set_bg(if bottom { PIXEL_ON } else { PIXEL_OFF });
+                    }
+                    RenderMode::Braille => {
+                        let mut dots = 0;
+                        for (dy, row) in BRAILLE_DOTS.iter().enumerate() {
+                            for (dx, &bit) in row.iter().enumerate() {
+                                if self.display[y * 4 + dy][x * 2 + dx] {
+                                    dots |= bit;
+                                }
+                            }
+                        }
+                        let symbol = char::from_u32(0x2800 + dots).unwrap_or(' ');
+                        cell.set_char(symbol).set_fg(PIXEL_ON).set_bg(PIXEL_OFF);
+                    }
+                }
+            }
+        }
+    }
+}
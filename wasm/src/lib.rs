@@ -0,0 +1,152 @@
+use emulator::{
+    Emulator,
+    constants::{DISPLAY_HEIGHT, DISPLAY_WIDTH},
+};
+use wasm_bindgen::prelude::*;
+
+// Default number of CPU cycles per frame (500 Hz CPU / 60 Hz display).
+const DEFAULT_STEPS_PER_FRAME: usize = 500 / 60;
+
+// Default colors for lit and unlit pixels, as 0xRRGGBBAA.
+const DEFAULT_ON_COLOR: u32 = 0xFFFFFFFF;
+const DEFAULT_OFF_COLOR: u32 = 0x000000FF;
+
+// JavaScript facing wrapper around the emulator, which also owns an RGBA framebuffer
+// that can be viewed directly from wasm memory (e.g. to build an `ImageData`).
+#[wasm_bindgen]
+pub struct Chip8 {
+    emu: Emulator,
+    framebuffer: Vec<u8>,
+    steps_per_frame: usize,
+    on_color: u32,
+    off_color: u32,
+}
+
+#[wasm_bindgen]
+impl Chip8 {
+    // Create a new emulator. Without a seed, the random number generator is seeded from `Math.random()`.
+    #[wasm_bindgen(constructor)]
+    pub fn new(seed: Option<u32>) -> Chip8 {
+        let seed = match seed {
+            Some(seed) => seed as u64,
+            None => (js_sys::Math::random() * u32::MAX as f64) as u64,
+        };
+
+        let mut chip8 = Chip8 {
+            emu: Emulator::with_seed(seed),
+            framebuffer: vec![0; DISPLAY_WIDTH * DISPLAY_HEIGHT * 4],
+            steps_per_frame: DEFAULT_STEPS_PER_FRAME,
+            on_color: DEFAULT_ON_COLOR,
+            off_color: DEFAULT_OFF_COLOR,
+        };
+        chip8.update_framebuffer();
+        chip8
+    }
+
+    // Width of the display (and framebuffer) in pixels.
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> usize {
+        DISPLAY_WIDTH
+    }
+
+    // Height of the display (and framebuffer) in pixels.
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> usize {
+        DISPLAY_HEIGHT
+    }
+
+    // Number of CPU cycles performed by each call to `run_frame`.
+    #[wasm_bindgen(getter = stepsPerFrame)]
+    pub fn steps_per_frame(&self) -> usize {
+        self.steps_per_frame
+    }
+
+    #[wasm_bindgen(setter = stepsPerFrame)]
+    pub fn set_steps_per_frame(&mut self, steps: usize) {
+        self.steps_per_frame = steps;
+    }
+
+    // Set the colors used for lit and unlit pixels in the framebuffer, as 0xRRGGBBAA.
+    #[wasm_bindgen(js_name = setPalette)]
+    pub fn set_palette(&mut self, on_color: u32, off_color: u32) {
+        self.on_color = on_color;
+        self.off_color = off_color;
+        self.update_framebuffer();
+    }
+
+    // Reset the emulator and load a ROM into memory.
+    #[wasm_bindgen(js_name = loadRom)]
+    pub fn load_rom(&mut self, data: &[u8]) -> Result<(), JsError> {
+        self.emu.reset();
+        self.emu.load_rom(data).map_err(to_js_error)?;
+        self.update_framebuffer();
+        Ok(())
+    }
+
+    // Reset the emulator, clearing memory (including any loaded ROM) and the display.
+    pub fn reset(&mut self) {
+        self.emu.reset();
+        self.update_framebuffer();
+    }
+
+    // Run a single frame (`stepsPerFrame` CPU cycles and one timer tick), then update the framebuffer.
+    #[wasm_bindgen(js_name = runFrame)]
+    pub fn run_frame(&mut self) -> Result<(), JsError> {
+        let result = self.emu.run_frame(self.steps_per_frame);
+        self.update_framebuffer();
+        result.map_err(to_js_error)
+    }
+
+    // Mark the key at the given index (0x0 - 0xF) as pressed.
+    #[wasm_bindgen(js_name = pressKey)]
+    pub fn press_key(&mut self, idx: usize) -> Result<(), JsError> {
+        self.emu.press_key(idx).map_err(to_js_error)
+    }
+
+    // Mark the key at the given index (0x0 - 0xF) as released.
+    #[wasm_bindgen(js_name = releaseKey)]
+    pub fn release_key(&mut self, idx: usize) -> Result<(), JsError> {
+        self.emu.release_key(idx).map_err(to_js_error)
+    }
+
+    // Pointer to the RGBA framebuffer in wasm memory, for building a zero-copy view with
+    // `new Uint8ClampedArray(memory.buffer, ptr, width * height * 4)`.
+    // The view is invalidated if wasm memory grows, so recreate it after each frame.
+    #[wasm_bindgen(js_name = framebufferPtr)]
+    pub fn framebuffer_ptr(&self) -> *const u8 {
+        self.framebuffer.as_ptr()
+    }
+
+    // Copy of the RGBA framebuffer.
+    pub fn framebuffer(&self) -> Vec<u8> {
+        self.framebuffer.clone()
+    }
+
+    // Current value of the sound timer.
+    #[wasm_bindgen(getter = soundTimer)]
+    pub fn sound_timer(&self) -> u8 {
+        self.emu.get_st()
+    }
+
+    // Whether the beep should currently be playing (sound timer is non-zero).
+    #[wasm_bindgen(getter = isBeeping)]
+    pub fn is_beeping(&self) -> bool {
+        self.emu.get_st() > 0
+    }
+
+    // Helper function to redraw the RGBA framebuffer from the emulator's display buffer.
+    fn update_framebuffer(&mut self) {
+        let on = self.on_color.to_be_bytes();
+        let off = self.off_color.to_be_bytes();
+
+        let pixels = self.emu.get_display_buffer().iter().flatten();
+        for (rgba, &is_pixel_on) in self.framebuffer.chunks_exact_mut(4).zip(pixels) {
+            rgba.copy_from_slice(if is_pixel_on { &on } else { &off });
+        }
+    }
+}
+
+// Helper function to convert an emulator error into a JavaScript `Error`.
+fn to_js_error(err: impl core::fmt::Display) -> JsError {
+    JsError::new(&err.to_string())
+}
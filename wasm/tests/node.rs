@@ -0,0 +1,58 @@
+// Run with `cargo test --target wasm32-unknown-unknown` from the `wasm` directory
+// (requires `wasm-bindgen-test-runner` from `wasm-bindgen-cli` and Node.js).
+#![cfg(target_arch = "wasm32")]
+
+use chip8_wasm::Chip8;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+const IBM_LOGO: &[u8] = include_bytes!("../../test_roms/IBM Logo.ch8");
+
+#[wasm_bindgen_test]
+fn framebuffer_is_rgba_sized() {
+    let chip8 = Chip8::new(Some(0));
+    assert_eq!(
+        chip8.framebuffer().len(),
+        chip8.width() * chip8.height() * 4
+    );
+}
+
+#[wasm_bindgen_test]
+fn ibm_logo_draws_to_framebuffer() {
+    let mut chip8 = Chip8::new(Some(0));
+    chip8.load_rom(IBM_LOGO).unwrap();
+    for _ in 0..60 {
+        chip8.run_frame().unwrap();
+    }
+
+    let lit = chip8
+        .framebuffer()
+        .chunks_exact(4)
+        .filter(|rgba| rgba == &[0xFF, 0xFF, 0xFF, 0xFF])
+        .count();
+    assert!(lit > 0);
+}
+
+#[wasm_bindgen_test]
+fn palette_is_applied() {
+    let mut chip8 = Chip8::new(Some(0));
+    chip8.set_palette(0x00FF00FF, 0x112233FF);
+    assert_eq!(&chip8.framebuffer()[..4], &[0x11, 0x22, 0x33, 0xFF]);
+}
+
+#[wasm_bindgen_test]
+fn sound_timer_counts_down() {
+    // 600A -> V0 = 10, F018 -> sound timer = V0, 1204 -> loop forever.
+    let mut chip8 = Chip8::new(Some(0));
+    chip8
+        .load_rom(&[0x60, 0x0A, 0xF0, 0x18, 0x12, 0x04])
+        .unwrap();
+    chip8.run_frame().unwrap();
+    assert!(chip8.is_beeping());
+    assert_eq!(chip8.sound_timer(), 9);
+}
+
+#[wasm_bindgen_test]
+fn invalid_key_is_an_error() {
+    let mut chip8 = Chip8::new(Some(0));
+    assert!(chip8.press_key(0x10).is_err());
+}